@@ -1,6 +1,9 @@
 fn main() {
     csbindgen::Builder::default()
         .input_extern_file("src/lib.rs")
+        // FFI 用到的枚举和 #[repr(C)] 结构体定义在 common::types
+        // csbindgen 不展开常量：结构体里的定长数组要写字面量长度，写常量名生成的 C# fixed 数组没有长度
+        .input_extern_file("src/common/types.rs")
         .csharp_class_name("IrisNative")
         .csharp_namespace("MOGA_Vision.Native")
         .generate_csharp_file("../externLib/NativeMethods.g.cs") // 确保路径指向你的 WPF 项目
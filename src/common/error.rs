@@ -0,0 +1,53 @@
+use crate::common::types::IrisStatus;
+use std::cell::RefCell;
+use std::fmt;
+
+// 内部失败的结果：status 返回给 C#，message 存为本线程的最后一条错误
+#[derive(Clone, Debug)]
+pub struct IrisError {
+    pub status: IrisStatus,
+    pub message: String,
+}
+
+impl IrisError {
+    pub fn new(status: IrisStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for IrisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.status, self.message)
+    }
+}
+
+pub type IrisResult<T = ()> = Result<T, IrisError>;
+
+// 条件不满足时返回 status 错误，省去 FFI 参数检查里的 if 块
+pub fn ensure(condition: bool, status: IrisStatus, message: impl FnOnce() -> String) -> IrisResult {
+    if condition {
+        Ok(())
+    } else {
+        Err(IrisError::new(status, message()))
+    }
+}
+
+thread_local! {
+    // 每个线程各自保存，C# 在多个线程上调用时互不覆盖
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+pub fn set_last_error(message: &str) {
+    LAST_ERROR.with(|e| {
+        let mut e = e.borrow_mut();
+        e.clear();
+        e.push_str(message);
+    });
+}
+
+pub fn last_error() -> String {
+    LAST_ERROR.with(|e| e.borrow().clone())
+}
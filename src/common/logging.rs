@@ -0,0 +1,303 @@
+use crate::common::types::IrisLogLevel;
+use std::ffi::{c_char, CString, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, Once};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 宿主的日志回调：level、target (模块路径或 "wgpu" 等)、message 都在调用期间有效
+// 可能在任意线程上被调用 (包括 wgpu 的回调线程)，宿主需自行切回 UI 线程
+pub type LogCallback = extern "C" fn(IrisLogLevel, *const c_char, *const c_char);
+
+// 引擎的日志出口：注册了回调或日志文件时写到它们，都没有时退回 stderr
+struct Logger {
+    sinks: Mutex<Sinks>,
+}
+
+struct Sinks {
+    callback: Option<LogCallback>,
+    file: Option<RotatingFile>,
+}
+
+static LOGGER: Logger = Logger {
+    sinks: Mutex::new(Sinks {
+        callback: None,
+        file: None,
+    }),
+};
+
+// 默认只记录警告和错误，wgpu 的 Info 级日志很多
+const DEFAULT_LEVEL: log::LevelFilter = log::LevelFilter::Warn;
+
+// 每个 FFI 入口都会调用，只有第一次生效
+pub fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        // 宿主进程里已有别的 log 实现时保留它
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(DEFAULT_LEVEL);
+        }
+        // panic 信息 (含位置) 也写进日志，FFI 入口随后把它转成 Panic 状态；
+        // 宿主或其他库先装的 hook 照常调用
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            log::error!(target: "panic", "{}", info);
+            previous(info);
+        }));
+    });
+}
+
+pub fn set_level(level: IrisLogLevel) {
+    log::set_max_level(level.to_filter());
+}
+
+pub fn set_callback(callback: Option<LogCallback>) {
+    LOGGER.sinks().callback = callback;
+}
+
+// path 为 None 时关闭日志文件
+pub fn set_file(path: Option<PathBuf>, max_bytes: u64, max_files: u32) -> io::Result<()> {
+    let file = path
+        .map(|path| RotatingFile::open(path, max_bytes, max_files))
+        .transpose()?;
+    LOGGER.sinks().file = file;
+    Ok(())
+}
+
+impl Logger {
+    // 某个回调或写文件时 panic 过也继续记录，日志不能因此整体失效
+    fn sinks(&self) -> MutexGuard<'_, Sinks> {
+        self.sinks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        let callback = {
+            let mut sinks = self.sinks();
+            let line = format!(
+                "{} [{}] {}: {}\n",
+                timestamp(),
+                record.level(),
+                record.target(),
+                message
+            );
+            if let Some(file) = &mut sinks.file {
+                // 日志文件写失败时不能再记日志，只能丢弃这一行
+                let _ = file.write(&line);
+            } else if sinks.callback.is_none() {
+                // 不用 eprint!：stderr 已关闭时它会在持锁状态下 panic，panic hook 再记日志就会死锁
+                let _ = io::stderr().write_all(line.as_bytes());
+            }
+            sinks.callback
+        };
+        // 在锁外调用，回调里再调用 iris_log_* 也不会死锁
+        if let Some(callback) = callback {
+            let target = c_string(record.target());
+            let message = c_string(&message);
+            callback(
+                IrisLogLevel::from_level(record.level()),
+                target.as_ptr(),
+                message.as_ptr(),
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(RotatingFile {
+            file: Some(file), ..
+        }) = &mut self.sinks().file
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
+// 消息里的 NUL 会截断 C 字符串，替换为空格
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', " ")).unwrap()
+}
+
+// UTC 时间 "2024-01-02 03:04:05.678Z"
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    // 公历日期换算 (Howard Hinnant 的 civil_from_days)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60,
+        now.subsec_millis()
+    )
+}
+
+// 超过 max_bytes 时滚动：path -> path.1 -> path.2 ...，最多保留 max_files 个旧文件
+// max_bytes 为 0 表示不滚动
+struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: u32,
+    // 滚动时先关闭再改名，Windows 不能重命名打开中的文件
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, max_files: u32) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            file: Some(file),
+            size,
+        })
+    }
+
+    fn backup(&self, index: u32) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.backup(self.max_files));
+            for i in (1..self.max_files).rev() {
+                let from = self.backup(i);
+                if from.exists() {
+                    fs::rename(from, self.backup(i + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.backup(1))?;
+        }
+        self.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?,
+        );
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64;
+        if self.max_bytes > 0 && self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        let file = match &mut self.file {
+            Some(file) => file,
+            // 上次滚动失败，重新打开
+            None => self.file.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?,
+            ),
+        };
+        file.write_all(line.as_bytes())?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{timestamp, RotatingFile};
+    use std::fs;
+    use std::path::PathBuf;
+
+    // 每个测试用独立的临时目录，并行运行时互不干扰
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("moga_iris_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_lines(file: &mut RotatingFile, count: u32) {
+        for i in 0..count {
+            file.write(&format!("line {:02}\n", i)).unwrap();
+        }
+    }
+
+    #[test]
+    fn rotates_and_keeps_max_files() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("iris.log");
+        // 每行 8 字节，上限 20 字节时每个文件写 2 行
+        let mut file = RotatingFile::open(path.clone(), 20, 3).unwrap();
+        write_lines(&mut file, 20);
+        drop(file);
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("iris.log"), "line 18\nline 19\n");
+        assert_eq!(read("iris.log.1"), "line 16\nline 17\n");
+        assert_eq!(read("iris.log.2"), "line 14\nline 15\n");
+        assert_eq!(read("iris.log.3"), "line 12\nline 13\n");
+        // 当前文件加最多 max_files 个旧文件
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn zero_max_files_keeps_only_current() {
+        let dir = temp_dir("rotate_none");
+        let path = dir.join("iris.log");
+        let mut file = RotatingFile::open(path.clone(), 20, 0).unwrap();
+        write_lines(&mut file, 5);
+        drop(file);
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 04\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn timestamp_format() {
+        // "2024-01-02 03:04:05.678Z"
+        let ts = timestamp();
+        assert_eq!(ts.len(), 24, "{}", ts);
+        for (i, c) in ts.char_indices() {
+            match i {
+                4 | 7 => assert_eq!(c, '-'),
+                10 => assert_eq!(c, ' '),
+                13 | 16 => assert_eq!(c, ':'),
+                19 => assert_eq!(c, '.'),
+                23 => assert_eq!(c, 'Z'),
+                _ => assert!(c.is_ascii_digit(), "{}", ts),
+            }
+        }
+        assert!(ts.as_str() >= "2024");
+    }
+}
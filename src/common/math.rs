@@ -1 +1,451 @@
+use glam::{DAffine2, DMat2, DMat3, DVec2, DVec3, Mat4};
 
+// 2D 视图变换：图像坐标 <-> 屏幕坐标
+// 图像坐标以像素为单位，像素 (i, j) 覆盖 [i, i+1) x [j, j+1)，中心在 (i + 0.5, j + 0.5)
+// 屏幕坐标以视口左上角为原点，y 轴向下
+// screen = viewport / 2 + R(rotation) * (image - center) * zoom
+pub struct ViewTransform {
+    // 屏幕像素 / 图像像素
+    zoom: f64,
+    // 位于视口中心的图像坐标
+    center: DVec2,
+    // 顺时针旋转的 90° 次数，0~3
+    quarter_turns: u32,
+    viewport: DVec2,
+    image_size: DVec2,
+    // 自动适应窗口：视口或图像尺寸变化时重新铺满，用户平移 / 缩放后关闭
+    fit: bool,
+    // 图像坐标 -> 世界坐标 (毫米) 的标定，测量值和坐标读数据此换算
+    calibration: Option<Calibration>,
+}
+
+// 缩放范围，防止除零和浮点溢出
+const MIN_ZOOM: f64 = 1e-4;
+const MAX_ZOOM: f64 = 1e4;
+
+impl ViewTransform {
+    pub fn new(viewport_width: u32, viewport_height: u32) -> Self {
+        let mut view = Self {
+            zoom: 1.0,
+            center: DVec2::ZERO,
+            quarter_turns: 0,
+            viewport: DVec2::new(viewport_width.max(1) as f64, viewport_height.max(1) as f64),
+            image_size: DVec2::ONE,
+            fit: true,
+            calibration: None,
+        };
+        view.zoom_to_fit();
+        view
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn calibration(&self) -> Option<&Calibration> {
+        self.calibration.as_ref()
+    }
+
+    // None 清除标定；标定无效时不修改并返回 false
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) -> bool {
+        if calibration.as_ref().is_some_and(|c| !c.is_valid()) {
+            return false;
+        }
+        self.calibration = calibration;
+        true
+    }
+
+    pub fn viewport_size(&self) -> (u32, u32) {
+        (self.viewport.x as u32, self.viewport.y as u32)
+    }
+
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.viewport = DVec2::new(width.max(1) as f64, height.max(1) as f64);
+        if self.fit {
+            self.zoom_to_fit();
+        }
+    }
+
+    pub fn set_image_size(&mut self, width: u32, height: u32) {
+        let size = DVec2::new(width.max(1) as f64, height.max(1) as f64);
+        if size == self.image_size {
+            return;
+        }
+        self.image_size = size;
+        if self.fit {
+            self.zoom_to_fit();
+        }
+    }
+
+    // 顺时针旋转 90° 的矩阵 (y 轴向下)，按 quarter_turns 次叠加
+    fn rotation(&self) -> DMat2 {
+        match self.quarter_turns % 4 {
+            0 => DMat2::IDENTITY,
+            1 => DMat2::from_cols(DVec2::new(0.0, 1.0), DVec2::new(-1.0, 0.0)),
+            2 => DMat2::from_cols(DVec2::new(-1.0, 0.0), DVec2::new(0.0, -1.0)),
+            _ => DMat2::from_cols(DVec2::new(0.0, -1.0), DVec2::new(1.0, 0.0)),
+        }
+    }
+
+    pub fn image_to_screen_affine(&self) -> DAffine2 {
+        let linear = self.rotation() * self.zoom;
+        let translation = self.viewport * 0.5 - linear * self.center;
+        DAffine2::from_mat2_translation(linear, translation)
+    }
+
+    pub fn image_to_screen(&self, p: DVec2) -> DVec2 {
+        self.image_to_screen_affine().transform_point2(p)
+    }
+
+    pub fn screen_to_image(&self, p: DVec2) -> DVec2 {
+        // 旋转矩阵正交，逆矩阵即转置
+        self.center + self.rotation().transpose() * ((p - self.viewport * 0.5) / self.zoom)
+    }
+
+    fn screen_to_clip_affine(&self) -> DAffine2 {
+        DAffine2::from_mat2_translation(
+            DMat2::from_diagonal(DVec2::new(2.0 / self.viewport.x, -2.0 / self.viewport.y)),
+            DVec2::new(-1.0, 1.0),
+        )
+    }
+
+    // 图像坐标 -> 裁剪空间，供所有绘制图像空间内容的管线使用
+    pub fn image_to_clip(&self) -> Mat4 {
+        affine_to_mat4(self.screen_to_clip_affine() * self.image_to_screen_affine())
+    }
+
+    // 屏幕坐标 -> 裁剪空间，供 HUD 等固定在屏幕上的内容使用
+    pub fn screen_to_clip(&self) -> Mat4 {
+        affine_to_mat4(self.screen_to_clip_affine())
+    }
+
+    // 平移，单位为屏幕像素
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.center -= self.rotation().transpose() * (DVec2::new(dx, dy) / self.zoom);
+        self.fit = false;
+    }
+
+    // 以屏幕上的某一点为锚点缩放，锚点下的图像坐标保持不动
+    pub fn zoom_at(&mut self, screen_x: f64, screen_y: f64, factor: f64) {
+        if !(factor.is_finite() && factor > 0.0) {
+            return;
+        }
+        let anchor = DVec2::new(screen_x, screen_y);
+        let before = self.screen_to_image(anchor);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.screen_to_image(anchor);
+        self.center += before - after;
+        self.fit = false;
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        if zoom.is_finite() && zoom > 0.0 {
+            self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+            self.fit = false;
+        }
+    }
+
+    // 铺满视口并居中，之后视口变化时持续自动适应
+    pub fn zoom_to_fit(&mut self) {
+        let rotated = if self.quarter_turns % 2 == 1 {
+            DVec2::new(self.image_size.y, self.image_size.x)
+        } else {
+            self.image_size
+        };
+        self.zoom = (self.viewport.x / rotated.x)
+            .min(self.viewport.y / rotated.y)
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center = self.image_size * 0.5;
+        self.fit = true;
+    }
+
+    // 1:1 显示，保持当前中心
+    pub fn zoom_actual(&mut self) {
+        self.set_zoom(1.0);
+    }
+
+    // 按 90° 步进旋转，正数为顺时针；保持视口中心的图像坐标不变
+    pub fn rotate(&mut self, quarter_turns: i32) {
+        self.quarter_turns = (self.quarter_turns as i32 + quarter_turns).rem_euclid(4) as u32;
+        if self.fit {
+            self.zoom_to_fit();
+        }
+    }
+}
+
+// 像素 -> 世界坐标 (毫米) 的标定模型，作用于图像坐标 (与 ViewTransform 的约定相同)
+// JSON 形式: { "type": "pixel_size", "mm_per_pixel": 0.01 }
+//           { "type": "affine", "matrix": [a, b, c, d, e, f] }      X = a x + b y + c, Y = d x + e y + f
+//           { "type": "homography", "matrix": [h0, ..., h8] }       按行排列的 3x3 矩阵，结果再除以齐次坐标
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Calibration {
+    // 各向同性的像素尺寸，图像原点即世界原点
+    PixelSize { mm_per_pixel: f64 },
+    Affine { matrix: [f64; 6] },
+    // 平面单应，用于相机斜视拍摄的平面
+    Homography { matrix: [f64; 9] },
+}
+
+impl Calibration {
+    // 齐次坐标下的 图像 -> 世界 矩阵
+    fn matrix(&self) -> DMat3 {
+        match *self {
+            Calibration::PixelSize { mm_per_pixel } => {
+                DMat3::from_diagonal(DVec3::new(mm_per_pixel, mm_per_pixel, 1.0))
+            }
+            Calibration::Affine { matrix: m } => {
+                DMat3::from_cols_array(&[m[0], m[3], 0.0, m[1], m[4], 0.0, m[2], m[5], 1.0])
+            }
+            // glam 按列存储，按行给出的矩阵需要转置
+            Calibration::Homography { matrix } => DMat3::from_cols_array(&matrix).transpose(),
+        }
+    }
+
+    // 参数有限且可逆
+    pub fn is_valid(&self) -> bool {
+        if let Calibration::PixelSize { mm_per_pixel } = *self {
+            return mm_per_pixel.is_finite() && mm_per_pixel > 0.0;
+        }
+        let m = self.matrix();
+        let det = m.determinant();
+        m.is_finite() && det.is_finite() && det != 0.0
+    }
+
+    pub fn image_to_world(&self, p: DVec2) -> DVec2 {
+        project(self.matrix(), p)
+    }
+
+    pub fn world_to_image(&self, p: DVec2) -> DVec2 {
+        project(self.matrix().inverse(), p)
+    }
+}
+
+// 齐次变换，点落在单应的消失线上时结果为非有限值
+fn project(m: DMat3, p: DVec2) -> DVec2 {
+    let q = m * p.extend(1.0);
+    q.truncate() / q.z
+}
+
+fn affine_to_mat4(affine: DAffine2) -> Mat4 {
+    let m = affine.matrix2;
+    let t = affine.translation;
+    Mat4::from_cols_array(&[
+        m.x_axis.x as f32,
+        m.x_axis.y as f32,
+        0.0,
+        0.0,
+        m.y_axis.x as f32,
+        m.y_axis.y as f32,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+        0.0,
+        t.x as f32,
+        t.y as f32,
+        0.0,
+        1.0,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Calibration, ViewTransform};
+    use glam::DVec2;
+
+    const POINTS: [[f64; 2]; 4] = [[0.0, 0.0], [640.0, 0.0], [123.5, 456.25], [1919.0, 1079.0]];
+
+    fn sample_view() -> ViewTransform {
+        let mut view = ViewTransform::new(800, 600);
+        view.set_image_size(1920, 1080);
+        view
+    }
+
+    fn assert_view_round_trip(view: &ViewTransform) {
+        for p in POINTS.map(DVec2::from) {
+            let screen = view.image_to_screen(p);
+            let back = view.screen_to_image(screen);
+            assert!(back.distance(p) < 1e-9, "{} -> {} -> {}", p, screen, back);
+        }
+    }
+
+    #[test]
+    fn view_round_trip() {
+        let mut view = sample_view();
+        assert_view_round_trip(&view);
+        view.pan(37.0, -12.5);
+        assert_view_round_trip(&view);
+        view.zoom_at(100.0, 450.0, 3.7);
+        assert_view_round_trip(&view);
+        for _ in 0..4 {
+            view.rotate(1);
+            assert_view_round_trip(&view);
+        }
+        view.rotate(-3);
+        view.pan(-5.0, 8.0);
+        assert_view_round_trip(&view);
+    }
+
+    #[test]
+    fn zoom_at_keeps_anchor() {
+        let mut view = sample_view();
+        view.rotate(1);
+        view.pan(20.0, 30.0);
+        let anchor = DVec2::new(123.0, 456.0);
+        for factor in [2.0, 0.25, 10.0] {
+            let before = view.screen_to_image(anchor);
+            view.zoom_at(anchor.x, anchor.y, factor);
+            assert!(view.screen_to_image(anchor).distance(before) < 1e-9);
+            assert!(view.image_to_screen(before).distance(anchor) < 1e-9);
+        }
+        // 无效倍率不改变视图
+        let zoom = view.zoom();
+        view.zoom_at(anchor.x, anchor.y, 0.0);
+        view.zoom_at(anchor.x, anchor.y, f64::NAN);
+        assert_eq!(view.zoom(), zoom);
+    }
+
+    #[test]
+    fn rotate_quarter_turns() {
+        let mut view = sample_view();
+        view.set_zoom(2.0);
+        let center = DVec2::new(400.0, 300.0);
+        let image_center = view.screen_to_image(center);
+        // 图像 +x 方向依次顺时针转到屏幕的右、下、左、上 (y 轴向下)
+        let expected = [
+            DVec2::new(20.0, 0.0),
+            DVec2::new(0.0, 20.0),
+            DVec2::new(-20.0, 0.0),
+            DVec2::new(0.0, -20.0),
+        ];
+        for (turns, offset) in expected.into_iter().enumerate() {
+            let mut rotated = sample_view();
+            rotated.set_zoom(2.0);
+            rotated.rotate(turns as i32);
+            let p = image_center + DVec2::new(10.0, 0.0);
+            assert!(rotated.image_to_screen(image_center).distance(center) < 1e-9);
+            assert!(
+                rotated.image_to_screen(p).distance(center + offset) < 1e-9,
+                "{} 次: {}",
+                turns,
+                rotated.image_to_screen(p)
+            );
+            // 反向旋转等价于补足一整圈
+            let mut reverse = sample_view();
+            reverse.set_zoom(2.0);
+            reverse.rotate(turns as i32 - 4);
+            assert!(reverse.image_to_screen(p).distance(center + offset) < 1e-9);
+        }
+        view.rotate(4);
+        assert!(view.image_to_screen(image_center).distance(center) < 1e-9);
+    }
+
+    fn assert_round_trip(calibration: &Calibration) {
+        assert!(calibration.is_valid());
+        for p in POINTS.map(DVec2::from) {
+            let world = calibration.image_to_world(p);
+            let back = calibration.world_to_image(world);
+            assert!(
+                back.distance(p) < 1e-6,
+                "{:?}: {} -> {} -> {}",
+                calibration,
+                p,
+                world,
+                back
+            );
+            let image = calibration.world_to_image(p);
+            assert!(calibration.image_to_world(image).distance(p) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn affine_round_trip() {
+        let calibration = Calibration::Affine {
+            matrix: [0.02, 0.001, -5.0, -0.0015, 0.021, 3.0],
+        };
+        let expected = DVec2::new(
+            0.02 * 100.0 + 0.001 * 200.0 - 5.0,
+            -0.0015 * 100.0 + 0.021 * 200.0 + 3.0,
+        );
+        assert!(
+            calibration
+                .image_to_world(DVec2::new(100.0, 200.0))
+                .distance(expected)
+                < 1e-12
+        );
+        assert_round_trip(&calibration);
+    }
+
+    #[test]
+    fn homography_round_trip() {
+        let calibration = Calibration::Homography {
+            matrix: [0.02, 0.001, -5.0, -0.0015, 0.021, 3.0, 1e-5, 2e-5, 1.0],
+        };
+        // 按行排列：第三行决定齐次坐标
+        let p = DVec2::new(100.0, 200.0);
+        let w = 1e-5 * 100.0 + 2e-5 * 200.0 + 1.0;
+        let expected = DVec2::new(
+            (0.02 * 100.0 + 0.001 * 200.0 - 5.0) / w,
+            (-0.0015 * 100.0 + 0.021 * 200.0 + 3.0) / w,
+        );
+        assert!(calibration.image_to_world(p).distance(expected) < 1e-12);
+        assert_round_trip(&calibration);
+    }
+
+    #[test]
+    fn singular_matrix_is_invalid() {
+        let calibration = Calibration::Affine {
+            matrix: [1.0, 2.0, 0.0, 2.0, 4.0, 0.0],
+        };
+        assert!(!calibration.is_valid());
+        assert!(!Calibration::PixelSize { mm_per_pixel: 0.0 }.is_valid());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let cases = [
+            (
+                Calibration::PixelSize { mm_per_pixel: 0.01 },
+                serde_json::json!({ "type": "pixel_size", "mm_per_pixel": 0.01 }),
+            ),
+            (
+                Calibration::Affine {
+                    matrix: [0.02, 0.001, -5.0, -0.0015, 0.021, 3.0],
+                },
+                serde_json::json!({ "type": "affine", "matrix": [0.02, 0.001, -5.0, -0.0015, 0.021, 3.0] }),
+            ),
+            (
+                Calibration::Homography {
+                    matrix: [0.02, 0.001, -5.0, -0.0015, 0.021, 3.0, 1e-5, 2e-5, 1.0],
+                },
+                serde_json::json!({
+                    "type": "homography",
+                    "matrix": [0.02, 0.001, -5.0, -0.0015, 0.021, 3.0, 1e-5, 2e-5, 1.0]
+                }),
+            ),
+        ];
+        for (calibration, json) in cases {
+            // 序列化结果须与文档中的 JSON 形式一致，反序列化后原样还原
+            assert_eq!(serde_json::to_value(calibration).unwrap(), json);
+            let text = serde_json::to_string(&calibration).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Calibration>(&text).unwrap(),
+                calibration
+            );
+            assert_eq!(
+                serde_json::from_value::<Calibration>(json).unwrap(),
+                calibration
+            );
+        }
+        // 未知类型或缺少字段时拒绝
+        assert!(
+            serde_json::from_str::<Calibration>(r#"{"type":"polar","mm_per_pixel":0.01}"#).is_err()
+        );
+        assert!(
+            serde_json::from_str::<Calibration>(r#"{"type":"affine","matrix":[1,0,0,0,1]}"#)
+                .is_err()
+        );
+    }
+}
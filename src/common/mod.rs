@@ -1 +1,4 @@
-
+pub mod error;
+pub mod logging;
+pub mod math;
+pub mod types;
@@ -1 +1,900 @@
+use crate::common::error::{IrisError, IrisResult};
 
+// 离屏渲染目标支持的像素格式 (与 C# 端枚举一一对应)
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisTextureFormat {
+    Bgra8UnormSrgb = 0,
+    Bgra8Unorm = 1,
+    Rgba8UnormSrgb = 2,
+    Rgba8Unorm = 3,
+}
+
+impl TryFrom<u32> for IrisTextureFormat {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisTextureFormat::Bgra8UnormSrgb),
+            1 => Ok(IrisTextureFormat::Bgra8Unorm),
+            2 => Ok(IrisTextureFormat::Rgba8UnormSrgb),
+            3 => Ok(IrisTextureFormat::Rgba8Unorm),
+            _ => Err(invalid_enum("IrisTextureFormat", value)),
+        }
+    }
+}
+
+impl IrisTextureFormat {
+    pub fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            IrisTextureFormat::Bgra8UnormSrgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+            IrisTextureFormat::Bgra8Unorm => wgpu::TextureFormat::Bgra8Unorm,
+            IrisTextureFormat::Rgba8UnormSrgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            IrisTextureFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+// 后端掩码，可按位组合；传 0 表示交给 wgpu 自动选择全部后端
+pub const IRIS_BACKEND_VULKAN: u32 = 1 << 0;
+pub const IRIS_BACKEND_GL: u32 = 1 << 1;
+pub const IRIS_BACKEND_METAL: u32 = 1 << 2;
+pub const IRIS_BACKEND_DX12: u32 = 1 << 3;
+
+pub fn backends_from_mask(mask: u32) -> wgpu::Backends {
+    if mask == 0 {
+        return wgpu::Backends::all();
+    }
+    let mut backends = wgpu::Backends::empty();
+    if mask & IRIS_BACKEND_VULKAN != 0 {
+        backends |= wgpu::Backends::VULKAN;
+    }
+    if mask & IRIS_BACKEND_GL != 0 {
+        backends |= wgpu::Backends::GL;
+    }
+    if mask & IRIS_BACKEND_METAL != 0 {
+        backends |= wgpu::Backends::METAL;
+    }
+    if mask & IRIS_BACKEND_DX12 != 0 {
+        backends |= wgpu::Backends::DX12;
+    }
+    backends
+}
+
+// 单个后端 -> 掩码中对应的位，掩码里没有的后端 (Empty、BrowserWebGpu) 为 0
+pub fn backend_to_mask(backend: wgpu::Backend) -> u32 {
+    match backend {
+        wgpu::Backend::Vulkan => IRIS_BACKEND_VULKAN,
+        wgpu::Backend::Gl => IRIS_BACKEND_GL,
+        wgpu::Backend::Metal => IRIS_BACKEND_METAL,
+        wgpu::Backend::Dx12 => IRIS_BACKEND_DX12,
+        _ => 0,
+    }
+}
+
+// 宿主窗口的种类，决定 IrisWindowHandle 中各字段的含义
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisWindowKind {
+    Win32 = 0,
+    Xlib = 1,
+    Xcb = 2,
+    Wayland = 3,
+}
+
+impl TryFrom<u32> for IrisWindowKind {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisWindowKind::Win32),
+            1 => Ok(IrisWindowKind::Xlib),
+            2 => Ok(IrisWindowKind::Xcb),
+            3 => Ok(IrisWindowKind::Wayland),
+            _ => Err(invalid_enum("IrisWindowKind", value)),
+        }
+    }
+}
+
+// 带类型标记的原生窗口句柄，kind 为 IrisWindowKind 的取值 (按 u32 传，越界时返回 InvalidArgument)：
+// Win32   display = HINSTANCE (可为空)，window = HWND
+// Xlib    display = Display*，window_id = Window，screen 为屏幕号
+// Xcb     display = xcb_connection_t*，window_id = xcb_window_t，screen 为屏幕号
+// Wayland display = wl_display*，window = wl_surface*
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IrisWindowHandle {
+    pub kind: u32,
+    pub display: *mut std::ffi::c_void,
+    pub window: *mut std::ffi::c_void,
+    pub window_id: u64,
+    pub screen: i32,
+}
+
+// 适配器选择偏好；双显卡机器上 HighPerformance 倾向独显，LowPower 倾向核显
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisPowerPreference {
+    HighPerformance = 0,
+    LowPower = 1,
+    None = 2,
+}
+
+impl TryFrom<u32> for IrisPowerPreference {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisPowerPreference::HighPerformance),
+            1 => Ok(IrisPowerPreference::LowPower),
+            2 => Ok(IrisPowerPreference::None),
+            _ => Err(invalid_enum("IrisPowerPreference", value)),
+        }
+    }
+}
+
+impl IrisPowerPreference {
+    pub fn to_wgpu(self) -> wgpu::PowerPreference {
+        match self {
+            IrisPowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            IrisPowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            IrisPowerPreference::None => wgpu::PowerPreference::None,
+        }
+    }
+}
+
+// 窗口的呈现模式；LowLatency 依次尝试 Mailbox、Immediate，都不支持时退回 AutoNoVsync
+// 显卡不支持指定的模式时退回 Fifo (所有平台都支持)
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisPresentMode {
+    LowLatency = 0,
+    Fifo = 1,
+    FifoRelaxed = 2,
+    Mailbox = 3,
+    Immediate = 4,
+}
+
+impl TryFrom<u32> for IrisPresentMode {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisPresentMode::LowLatency),
+            1 => Ok(IrisPresentMode::Fifo),
+            2 => Ok(IrisPresentMode::FifoRelaxed),
+            3 => Ok(IrisPresentMode::Mailbox),
+            4 => Ok(IrisPresentMode::Immediate),
+            _ => Err(invalid_enum("IrisPresentMode", value)),
+        }
+    }
+}
+
+// 窗口 surface 的格式偏好：Srgb 由硬件做伽马编码，Linear 输出线性格式 (着色器自行编码)
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisSurfaceFormat {
+    Srgb = 0,
+    Linear = 1,
+}
+
+impl TryFrom<u32> for IrisSurfaceFormat {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisSurfaceFormat::Srgb),
+            1 => Ok(IrisSurfaceFormat::Linear),
+            _ => Err(invalid_enum("IrisSurfaceFormat", value)),
+        }
+    }
+}
+
+// 引擎创建选项；全部为 0 的结构体即默认值：
+// 平台默认后端、高性能适配器、低延迟呈现、帧积压 1、sRGB 格式、不开 MSAA
+// frame_latency 为 0 按 1 处理；sample_count 为 0 或 1 表示不开 MSAA，可选 2/4/8
+// surface_format 和 present_mode 只对窗口引擎生效，离屏引擎的格式由创建参数指定
+// select_adapter 为 true 时使用 iris_adapter_enumerate (以同一个 backends 掩码) 列出的第 adapter_index 个适配器，
+// 此时忽略 power_preference 和 force_fallback_adapter，窗口引擎的后端掩码为 0 也表示全部后端
+// power_preference、present_mode、surface_format 分别为 IrisPowerPreference、IrisPresentMode、
+// IrisSurfaceFormat 的取值，按 u32 传入，越界时返回 InvalidArgument
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IrisEngineOptions {
+    pub backends: u32,
+    pub power_preference: u32,
+    pub present_mode: u32,
+    pub frame_latency: u32,
+    pub surface_format: u32,
+    pub sample_count: u32,
+    pub force_fallback_adapter: bool,
+    pub select_adapter: bool,
+    pub adapter_index: u32,
+}
+
+// 回读输出的像素排列
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisReadbackFormat {
+    Bgra8 = 0,
+    Rgba8 = 1,
+}
+
+impl TryFrom<u32> for IrisReadbackFormat {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisReadbackFormat::Bgra8),
+            1 => Ok(IrisReadbackFormat::Rgba8),
+            _ => Err(invalid_enum("IrisReadbackFormat", value)),
+        }
+    }
+}
+
+// 回读状态 (poll 模式下由宿主轮询)
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisReadbackStatus {
+    Idle = 0,
+    Pending = 1,
+    Ready = 2,
+    Failed = 3,
+}
+
+// 相机图像的像素格式 (对应 GenICam PFNC)，上传后在 GPU 上统一解码为浮点纹理
+// Mono10/12/16 为 16 位小端容器；Mono10Packed/Mono12Packed 为 GigE Vision 的 2 像素 3 字节打包；
+// Mono10p/Mono12p 为 PFNC 的 LSB 优先连续位流打包
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisPixelFormat {
+    Mono8 = 0,
+    Rgba8 = 1,
+    Bgra8 = 2,
+    Mono10 = 3,
+    Mono12 = 4,
+    Mono16 = 5,
+    Mono10Packed = 6,
+    Mono12Packed = 7,
+    Mono10p = 8,
+    Mono12p = 9,
+    Rgb8 = 10,
+    Bgr8 = 11,
+    Yuv422Yuyv = 12,
+    Yuv422Uyvy = 13,
+    BayerRg8 = 14,
+    BayerGr8 = 15,
+    BayerGb8 = 16,
+    BayerBg8 = 17,
+    BayerRg12 = 18,
+    BayerGr12 = 19,
+    BayerGb12 = 20,
+    BayerBg12 = 21,
+    BayerRg16 = 22,
+    BayerGr16 = 23,
+    BayerGb16 = 24,
+    BayerBg16 = 25,
+}
+
+impl TryFrom<u32> for IrisPixelFormat {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisPixelFormat::Mono8),
+            1 => Ok(IrisPixelFormat::Rgba8),
+            2 => Ok(IrisPixelFormat::Bgra8),
+            3 => Ok(IrisPixelFormat::Mono10),
+            4 => Ok(IrisPixelFormat::Mono12),
+            5 => Ok(IrisPixelFormat::Mono16),
+            6 => Ok(IrisPixelFormat::Mono10Packed),
+            7 => Ok(IrisPixelFormat::Mono12Packed),
+            8 => Ok(IrisPixelFormat::Mono10p),
+            9 => Ok(IrisPixelFormat::Mono12p),
+            10 => Ok(IrisPixelFormat::Rgb8),
+            11 => Ok(IrisPixelFormat::Bgr8),
+            12 => Ok(IrisPixelFormat::Yuv422Yuyv),
+            13 => Ok(IrisPixelFormat::Yuv422Uyvy),
+            14 => Ok(IrisPixelFormat::BayerRg8),
+            15 => Ok(IrisPixelFormat::BayerGr8),
+            16 => Ok(IrisPixelFormat::BayerGb8),
+            17 => Ok(IrisPixelFormat::BayerBg8),
+            18 => Ok(IrisPixelFormat::BayerRg12),
+            19 => Ok(IrisPixelFormat::BayerGr12),
+            20 => Ok(IrisPixelFormat::BayerGb12),
+            21 => Ok(IrisPixelFormat::BayerBg12),
+            22 => Ok(IrisPixelFormat::BayerRg16),
+            23 => Ok(IrisPixelFormat::BayerGr16),
+            24 => Ok(IrisPixelFormat::BayerGb16),
+            25 => Ok(IrisPixelFormat::BayerBg16),
+            _ => Err(invalid_enum("IrisPixelFormat", value)),
+        }
+    }
+}
+
+impl IrisPixelFormat {
+    // 一行 width 个像素实际占用的字节数；按 u64 计算，宽度再大也不会溢出
+    pub fn row_bytes(self, width: u32) -> u64 {
+        let width = width as u64;
+        match self {
+            IrisPixelFormat::Mono8
+            | IrisPixelFormat::BayerRg8
+            | IrisPixelFormat::BayerGr8
+            | IrisPixelFormat::BayerGb8
+            | IrisPixelFormat::BayerBg8 => width,
+            // Bayer 12/16 与 Mono10/12/16 一样使用 16 位小端容器
+            IrisPixelFormat::Mono10
+            | IrisPixelFormat::Mono12
+            | IrisPixelFormat::Mono16
+            | IrisPixelFormat::BayerRg12
+            | IrisPixelFormat::BayerGr12
+            | IrisPixelFormat::BayerGb12
+            | IrisPixelFormat::BayerBg12
+            | IrisPixelFormat::BayerRg16
+            | IrisPixelFormat::BayerGr16
+            | IrisPixelFormat::BayerGb16
+            | IrisPixelFormat::BayerBg16 => width * 2,
+            IrisPixelFormat::Mono10Packed | IrisPixelFormat::Mono12Packed => width.div_ceil(2) * 3,
+            IrisPixelFormat::Mono10p => (width * 10).div_ceil(8),
+            IrisPixelFormat::Mono12p => (width * 12).div_ceil(8),
+            IrisPixelFormat::Rgb8 | IrisPixelFormat::Bgr8 => width * 3,
+            IrisPixelFormat::Rgba8 | IrisPixelFormat::Bgra8 => width * 4,
+            // 两个像素共享一组 UV，奇数宽度按偶数补齐
+            IrisPixelFormat::Yuv422Yuyv | IrisPixelFormat::Yuv422Uyvy => width.div_ceil(2) * 4,
+        }
+    }
+
+    // 每个通道的有效位数
+    pub fn bit_depth(self) -> u32 {
+        match self {
+            IrisPixelFormat::Mono10 | IrisPixelFormat::Mono10Packed | IrisPixelFormat::Mono10p => {
+                10
+            }
+            IrisPixelFormat::Mono12
+            | IrisPixelFormat::Mono12Packed
+            | IrisPixelFormat::Mono12p
+            | IrisPixelFormat::BayerRg12
+            | IrisPixelFormat::BayerGr12
+            | IrisPixelFormat::BayerGb12
+            | IrisPixelFormat::BayerBg12 => 12,
+            IrisPixelFormat::Mono16
+            | IrisPixelFormat::BayerRg16
+            | IrisPixelFormat::BayerGr16
+            | IrisPixelFormat::BayerGb16
+            | IrisPixelFormat::BayerBg16 => 16,
+            _ => 8,
+        }
+    }
+
+    // Bayer 格式返回 R 像素在 2x2 单元中的位置 (x 在 bit0，y 在 bit1)，其他格式返回 None
+    pub fn bayer_pattern(self) -> Option<u32> {
+        let code = self as u32;
+        if !(IrisPixelFormat::BayerRg8 as u32..=IrisPixelFormat::BayerBg16 as u32).contains(&code) {
+            return None;
+        }
+        // 每种位深按 RG、GR、GB、BG 顺序排列
+        Some(match (code - IrisPixelFormat::BayerRg8 as u32) % 4 {
+            0 => 0b00,
+            1 => 0b01,
+            2 => 0b10,
+            _ => 0b11,
+        })
+    }
+}
+
+// Bayer 去马赛克算法
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisDemosaicMode {
+    Bilinear = 0,
+    MalvarHeCutler = 1,
+}
+
+impl TryFrom<u32> for IrisDemosaicMode {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisDemosaicMode::Bilinear),
+            1 => Ok(IrisDemosaicMode::MalvarHeCutler),
+            _ => Err(invalid_enum("IrisDemosaicMode", value)),
+        }
+    }
+}
+
+// 图像层的伪彩色查找表，Gray 表示不做映射
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisColormap {
+    Gray = 0,
+    Jet = 1,
+    Viridis = 2,
+    Turbo = 3,
+    Inferno = 4,
+    Hot = 5,
+    Custom = 6,
+}
+
+impl TryFrom<u32> for IrisColormap {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisColormap::Gray),
+            1 => Ok(IrisColormap::Jet),
+            2 => Ok(IrisColormap::Viridis),
+            3 => Ok(IrisColormap::Turbo),
+            4 => Ok(IrisColormap::Inferno),
+            5 => Ok(IrisColormap::Hot),
+            6 => Ok(IrisColormap::Custom),
+            _ => Err(invalid_enum("IrisColormap", value)),
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisMouseButton {
+    Left = 0,
+    Right = 1,
+    Middle = 2,
+}
+
+impl TryFrom<u32> for IrisMouseButton {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisMouseButton::Left),
+            1 => Ok(IrisMouseButton::Right),
+            2 => Ok(IrisMouseButton::Middle),
+            _ => Err(invalid_enum("IrisMouseButton", value)),
+        }
+    }
+}
+
+// 鼠标事件的修饰键掩码
+pub const IRIS_MOD_SHIFT: u32 = 1;
+pub const IRIS_MOD_CTRL: u32 = 2;
+
+// 交互工具：Select 用于选择和编辑，其余工具拖动 (多边形为逐点单击) 创建对应形状
+// 测量工具：距离为拖动，角度为依次单击三个点 (第二个为顶点)，折线为逐点单击、右键或单击末点结束
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisRoiTool {
+    Select = 0,
+    Rect = 1,
+    RotatedRect = 2,
+    Circle = 3,
+    Ellipse = 4,
+    Annulus = 5,
+    Polygon = 6,
+    Distance = 7,
+    Angle = 8,
+    Polyline = 9,
+}
+
+impl TryFrom<u32> for IrisRoiTool {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisRoiTool::Select),
+            1 => Ok(IrisRoiTool::Rect),
+            2 => Ok(IrisRoiTool::RotatedRect),
+            3 => Ok(IrisRoiTool::Circle),
+            4 => Ok(IrisRoiTool::Ellipse),
+            5 => Ok(IrisRoiTool::Annulus),
+            6 => Ok(IrisRoiTool::Polygon),
+            7 => Ok(IrisRoiTool::Distance),
+            8 => Ok(IrisRoiTool::Angle),
+            9 => Ok(IrisRoiTool::Polyline),
+            _ => Err(invalid_enum("IrisRoiTool", value)),
+        }
+    }
+}
+
+// 光标提示，由宿主映射为 WPF 的 Cursors
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IrisCursor {
+    #[default]
+    Arrow = 0,
+    Crosshair = 1,
+    Move = 2,
+    SizeNS = 3,
+    SizeWE = 4,
+    SizeNWSE = 5,
+    SizeNESW = 6,
+    Rotate = 7,
+    Pan = 8,
+    // 拖动多边形顶点
+    Vertex = 9,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IrisRoiEvent {
+    #[default]
+    None = 0,
+    // 选中的 ROI 变化，roi_id 为新选中的 ROI (0 表示取消选择)
+    SelectionChanged = 1,
+    // 拖动中的实时变化
+    Changing = 2,
+    // 一次编辑结束 (鼠标抬起)
+    Changed = 3,
+    Created = 4,
+    Deleted = 5,
+}
+
+// 每个鼠标事件的处理结果
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IrisMouseResult {
+    pub cursor: IrisCursor,
+    pub event: IrisRoiEvent,
+    pub roi_id: u64,
+}
+
+// 场景图层，自下而上绘制；HUD 图层的对象使用屏幕坐标
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IrisLayer {
+    Image = 0,
+    Roi = 1,
+    Annotation = 2,
+    Hud = 3,
+}
+
+impl TryFrom<u32> for IrisLayer {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisLayer::Image),
+            1 => Ok(IrisLayer::Roi),
+            2 => Ok(IrisLayer::Annotation),
+            3 => Ok(IrisLayer::Hud),
+            _ => Err(invalid_enum("IrisLayer", value)),
+        }
+    }
+}
+
+impl IrisLayer {
+    pub const COUNT: usize = 4;
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisZOrder {
+    BringToFront = 0,
+    SendToBack = 1,
+    BringForward = 2,
+    SendBackward = 3,
+}
+
+impl TryFrom<u32> for IrisZOrder {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisZOrder::BringToFront),
+            1 => Ok(IrisZOrder::SendToBack),
+            2 => Ok(IrisZOrder::BringForward),
+            3 => Ok(IrisZOrder::SendBackward),
+            _ => Err(invalid_enum("IrisZOrder", value)),
+        }
+    }
+}
+
+// ROI 布尔组合：差集为第一个操作数减去其余全部
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IrisRoiBoolOp {
+    Union = 0,
+    Intersection = 1,
+    Difference = 2,
+    Xor = 3,
+}
+
+impl TryFrom<u32> for IrisRoiBoolOp {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisRoiBoolOp::Union),
+            1 => Ok(IrisRoiBoolOp::Intersection),
+            2 => Ok(IrisRoiBoolOp::Difference),
+            3 => Ok(IrisRoiBoolOp::Xor),
+            _ => Err(invalid_enum("IrisRoiBoolOp", value)),
+        }
+    }
+}
+
+// 文字的坐标空间
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisTextSpace {
+    // 位置和字号都是图像像素，随图像缩放、旋转
+    Image = 0,
+    // 位置和字号都是屏幕像素，画在 HUD 图层
+    Screen = 1,
+    // 位置是图像坐标，字号是屏幕像素：跟随图像移动但大小不变，适合 ROI 标签和测量值
+    Anchored = 2,
+}
+
+impl TryFrom<u32> for IrisTextSpace {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisTextSpace::Image),
+            1 => Ok(IrisTextSpace::Screen),
+            2 => Ok(IrisTextSpace::Anchored),
+            _ => Err(invalid_enum("IrisTextSpace", value)),
+        }
+    }
+}
+
+// 文字框的哪个点对齐到文字位置
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisTextAnchor {
+    TopLeft = 0,
+    Top = 1,
+    TopRight = 2,
+    Left = 3,
+    Center = 4,
+    Right = 5,
+    BottomLeft = 6,
+    Bottom = 7,
+    BottomRight = 8,
+}
+
+impl TryFrom<u32> for IrisTextAnchor {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisTextAnchor::TopLeft),
+            1 => Ok(IrisTextAnchor::Top),
+            2 => Ok(IrisTextAnchor::TopRight),
+            3 => Ok(IrisTextAnchor::Left),
+            4 => Ok(IrisTextAnchor::Center),
+            5 => Ok(IrisTextAnchor::Right),
+            6 => Ok(IrisTextAnchor::BottomLeft),
+            7 => Ok(IrisTextAnchor::Bottom),
+            8 => Ok(IrisTextAnchor::BottomRight),
+            _ => Err(invalid_enum("IrisTextAnchor", value)),
+        }
+    }
+}
+
+impl IrisTextAnchor {
+    // 对齐点在文字框内的相对位置 (0~1)
+    pub fn fraction(self) -> (f32, f32) {
+        let index = self as u32;
+        ((index % 3) as f32 * 0.5, (index / 3) as f32 * 0.5)
+    }
+}
+
+// 所有 iris_* 函数的返回值；非 Ok 时可用 iris_last_error_message 取得本线程最近一次失败的详细原因
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisStatus {
+    Ok = 0,
+    // 传入了空指针 (引擎、数据或输出参数)
+    NullArgument = 1,
+    // 数值参数超出范围，或几何、样式参数无效
+    InvalidArgument = 2,
+    // 字符串或路径不是合法的 UTF-8
+    InvalidUtf8 = 3,
+    // 对象、分组或文字 ID 不存在
+    NotFound = 4,
+    // 需要先上传图像
+    NoImage = 5,
+    // 当前状态下不能执行，例如没有可撤销的操作、拖动进行中、未设置标定
+    InvalidState = 6,
+    // 异步结果还没有准备好，稍后再查询
+    NotReady = 7,
+    // 文件读写失败
+    IoError = 8,
+    // JSON 语法错误或字段缺失 / 类型不符
+    SyntaxError = 9,
+    // 配方版本比当前库新
+    UnsupportedVersion = 10,
+    // 某个 ROI 的几何、样式或 ID 无效
+    InvalidRoi = 11,
+    // 标定参数非有限值或不可逆
+    InvalidCalibration = 12,
+    // 宿主缓冲区不够，需要的长度已写入 out_len (如有)
+    BufferTooSmall = 13,
+    // 适配器、设备、surface 或 GPU 回读失败
+    GpuError = 14,
+    // 引擎内部 panic 已被拦截，引擎可能处于不一致状态，建议销毁重建
+    Panic = 15,
+}
+
+// 适配器的设备类型 (与 wgpu::DeviceType 对应)
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisDeviceType {
+    Other = 0,
+    IntegratedGpu = 1,
+    DiscreteGpu = 2,
+    VirtualGpu = 3,
+    Cpu = 4,
+}
+
+// 适配器信息：name 为以 0 结尾的 UTF-8 (过长时截断)，vendor / device 为 PCI ID，
+// backend 为 IRIS_BACKEND_* 中的一位
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IrisAdapterInfo {
+    pub name: [u8; 256],
+    pub vendor: u32,
+    pub device: u32,
+    pub backend: u32,
+    pub device_type: IrisDeviceType,
+}
+
+impl IrisAdapterInfo {
+    pub fn from_wgpu(info: &wgpu::AdapterInfo) -> Self {
+        let mut name = [0u8; 256];
+        // 截断到字符边界，留一个字节给结尾的 0
+        let mut len = info.name.len().min(name.len() - 1);
+        while !info.name.is_char_boundary(len) {
+            len -= 1;
+        }
+        name[..len].copy_from_slice(&info.name.as_bytes()[..len]);
+        Self {
+            name,
+            vendor: info.vendor,
+            device: info.device,
+            backend: backend_to_mask(info.backend),
+            device_type: match info.device_type {
+                wgpu::DeviceType::Other => IrisDeviceType::Other,
+                wgpu::DeviceType::IntegratedGpu => IrisDeviceType::IntegratedGpu,
+                wgpu::DeviceType::DiscreteGpu => IrisDeviceType::DiscreteGpu,
+                wgpu::DeviceType::VirtualGpu => IrisDeviceType::VirtualGpu,
+                wgpu::DeviceType::Cpu => IrisDeviceType::Cpu,
+            },
+        }
+    }
+}
+
+// 当前适配器的常用上限，完整的上限和特性见 iris_engine_get_adapter_report
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IrisAdapterLimits {
+    pub max_texture_dimension_2d: u32,
+    pub max_buffer_size: u64,
+    pub max_storage_buffer_binding_size: u32,
+    pub max_uniform_buffer_binding_size: u32,
+    pub max_bind_groups: u32,
+    pub max_compute_invocations_per_workgroup: u32,
+    pub max_compute_workgroups_per_dimension: u32,
+}
+
+impl IrisAdapterLimits {
+    pub fn from_wgpu(limits: &wgpu::Limits) -> Self {
+        Self {
+            max_texture_dimension_2d: limits.max_texture_dimension_2d,
+            max_buffer_size: limits.max_buffer_size,
+            max_storage_buffer_binding_size: limits.max_storage_buffer_binding_size,
+            max_uniform_buffer_binding_size: limits.max_uniform_buffer_binding_size,
+            max_bind_groups: limits.max_bind_groups,
+            max_compute_invocations_per_workgroup: limits.max_compute_invocations_per_workgroup,
+            max_compute_workgroups_per_dimension: limits.max_compute_workgroups_per_dimension,
+        }
+    }
+}
+
+// 日志级别，数值越大越详细；设置为 Off 时关闭全部日志
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisLogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl TryFrom<u32> for IrisLogLevel {
+    type Error = IrisError;
+
+    fn try_from(value: u32) -> IrisResult<Self> {
+        match value {
+            0 => Ok(IrisLogLevel::Off),
+            1 => Ok(IrisLogLevel::Error),
+            2 => Ok(IrisLogLevel::Warn),
+            3 => Ok(IrisLogLevel::Info),
+            4 => Ok(IrisLogLevel::Debug),
+            5 => Ok(IrisLogLevel::Trace),
+            _ => Err(invalid_enum("IrisLogLevel", value)),
+        }
+    }
+}
+
+impl IrisLogLevel {
+    pub fn to_filter(self) -> log::LevelFilter {
+        match self {
+            IrisLogLevel::Off => log::LevelFilter::Off,
+            IrisLogLevel::Error => log::LevelFilter::Error,
+            IrisLogLevel::Warn => log::LevelFilter::Warn,
+            IrisLogLevel::Info => log::LevelFilter::Info,
+            IrisLogLevel::Debug => log::LevelFilter::Debug,
+            IrisLogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+
+    pub fn from_level(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => IrisLogLevel::Error,
+            log::Level::Warn => IrisLogLevel::Warn,
+            log::Level::Info => IrisLogLevel::Info,
+            log::Level::Debug => IrisLogLevel::Debug,
+            log::Level::Trace => IrisLogLevel::Trace,
+        }
+    }
+}
+
+pub const IRIS_STATS_BINS: usize = 256;
+
+// ROI 内的像素统计，数值为原始单位 (如 Mono12 为 0~4095)，彩色图按 BT.601 亮度统计
+// stddev 为总体标准差；histogram 把 [0, 位深最大值] 等分为 256 格
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IrisRoiStats {
+    pub count: u64,
+    pub mean: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub histogram: [u32; 256],
+}
+
+impl Default for IrisRoiStats {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            stddev: 0.0,
+            min: 0.0,
+            max: 0.0,
+            histogram: [0; IRIS_STATS_BINS],
+        }
+    }
+}
+
+// 测量对象的种类
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrisMeasureKind {
+    Distance = 0,
+    Angle = 1,
+    PolylineLength = 2,
+}
+
+// 测量结果：value 为图像像素 (角度为度)；设置了标定时 calibrated 为 true，
+// world_value 为世界坐标下的毫米数 (角度在世界坐标下计算)，否则与 value 相同
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IrisMeasurement {
+    pub kind: IrisMeasureKind,
+    pub value: f64,
+    pub world_value: f64,
+    pub calibrated: bool,
+}
+
+// C# 传来的枚举值越界时的错误
+fn invalid_enum(name: &str, value: u32) -> IrisError {
+    IrisError::new(
+        IrisStatus::InvalidArgument,
+        format!("{} 的取值无效: {}", name, value),
+    )
+}
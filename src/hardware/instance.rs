@@ -1,3 +1,7 @@
+use crate::common::error::{IrisError, IrisResult};
+use crate::common::types::IrisStatus;
+use crate::hardware::options::EngineOptions;
+use std::sync::{Arc, Mutex};
 use wgpu::{Adapter, Backends, Device, Instance, InstanceDescriptor, Queue};
 
 pub struct GpuContext {
@@ -5,34 +9,156 @@ pub struct GpuContext {
     pub adapter: Adapter,
     pub device: Device,
     pub queue: Queue,
+    // wgpu 回调里记下的第一条未处理错误，由 take_error 在 FFI 调用结束时取走
+    gpu_error: Arc<Mutex<Option<String>>>,
 }
 
 impl GpuContext {
-    pub async fn new() -> Self {
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::all(), // WPF 在 Windows 上与 DX11/12 兼容性最好
+    // 无窗口场景 (服务、CI、Linux 检测机) 直接从后端掩码创建
+    pub async fn new(backends: Backends, options: &EngineOptions) -> IrisResult<Self> {
+        let instance = Self::create_instance(backends);
+        Self::from_instance(instance, None, options).await
+    }
+
+    pub fn create_instance(backends: Backends) -> Instance {
+        Instance::new(&InstanceDescriptor {
+            backends, // WPF 在 Windows 上与 DX11/12 兼容性最好
             ..Default::default()
-        });
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions::default())
-            .await
-            .unwrap();
+        })
+    }
+
+    // 列出 backends 下的全部适配器；顺序与 iris_adapter_enumerate 一致，adapter_index 按这个顺序计
+    pub fn enumerate_adapters(backends: Backends) -> Vec<Adapter> {
+        Self::create_instance(backends).enumerate_adapters(Backends::all())
+    }
+
+    // 宿主按序号指定适配器 (双显卡机器上选核显或独显)
+    fn pick_adapter(
+        instance: &Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        index: u32,
+    ) -> IrisResult<Adapter> {
+        let mut adapters = instance.enumerate_adapters(Backends::all());
+        let count = adapters.len();
+        if index as usize >= count {
+            return Err(IrisError::new(
+                IrisStatus::InvalidArgument,
+                format!("适配器序号 {} 超出范围 (共 {} 个)", index, count),
+            ));
+        }
+        let adapter = adapters.swap_remove(index as usize);
+        if let Some(surface) = compatible_surface {
+            if !adapter.is_surface_supported(surface) {
+                return Err(IrisError::new(
+                    IrisStatus::GpuError,
+                    format!(
+                        "适配器 {} ({}) 不支持该窗口",
+                        index,
+                        adapter.get_info().name
+                    ),
+                ));
+            }
+        }
+        Ok(adapter)
+    }
+
+    // 窗口场景需要先用 instance 创建 surface，再带着 compatible_surface 请求适配器
+    // force_fallback_adapter 为 true 时只接受软件适配器 (如 llvmpipe / WARP)
+    pub async fn from_instance(
+        instance: Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        options: &EngineOptions,
+    ) -> IrisResult<Self> {
+        let adapter = if options.select_adapter {
+            Self::pick_adapter(&instance, compatible_surface, options.adapter_index)?
+        } else {
+            instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: options.power_preference.to_wgpu(),
+                    compatible_surface,
+                    force_fallback_adapter: options.force_fallback_adapter,
+                })
+                .await
+                .ok_or_else(|| IrisError::new(IrisStatus::GpuError, "找不到兼容的显卡适配器"))?
+        };
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("MogaIrisDevice"),
+                    // 有这个特性时 MSAA 可以用显卡实际支持的采样数，否则只保证 4 倍
+                    required_features: adapter.features()
+                        & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+                    // 工业相机分辨率常常超过默认的 8192 / 128MB 限制，直接用显卡的上限
+                    required_limits: adapter.limits(),
                     ..Default::default()
                 },
                 None,
             )
             .await
-            .unwrap();
+            .map_err(|e| IrisError::new(IrisStatus::GpuError, format!("Device 创建失败: {}", e)))?;
+        // wgpu 默认在校验错误时 panic；改为写日志并记下来，由本次 FFI 调用以 GpuError 返回
+        let gpu_error = Arc::new(Mutex::new(None));
+        let slot = gpu_error.clone();
+        device.on_uncaptured_error(Box::new(move |e| {
+            log::error!(target: "wgpu", "{}", e);
+            record_error(&slot, e.to_string());
+        }));
+        let slot = gpu_error.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            log::error!(target: "wgpu", "设备丢失 ({:?}): {}", reason, message);
+            record_error(&slot, format!("设备丢失 ({:?}): {}", reason, message));
+        });
+        let info = adapter.get_info();
+        log::info!("使用适配器 {} ({:?})", info.name, info.backend);
 
-        Self {
+        Ok(Self {
             instance,
             adapter,
             device,
             queue,
+            gpu_error,
+        })
+    }
+
+    // 取走上次调用以来 wgpu 上报的错误；校验错误在出错的那次调用里同步上报
+    pub fn take_error(&self) -> IrisResult {
+        let error = self
+            .gpu_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        match error {
+            Some(message) => Err(IrisError::new(
+                IrisStatus::GpuError,
+                format!("GPU 错误: {}", message),
+            )),
+            None => Ok(()),
         }
     }
+
+    // 适配器、驱动、设备特性和上限的文本报告
+    pub fn report(&self) -> String {
+        let info = self.adapter.get_info();
+        format!(
+            "适配器: {} ({:?}, {:?})\n厂商 ID: 0x{:04x}  设备 ID: 0x{:04x}\n驱动: {} {}\n\
+             适配器特性: {:?}\n设备特性: {:?}\n设备上限: {:#?}\n",
+            info.name,
+            info.backend,
+            info.device_type,
+            info.vendor,
+            info.device,
+            info.driver,
+            info.driver_info,
+            self.adapter.features(),
+            self.device.features(),
+            self.device.limits(),
+        )
+    }
+}
+
+// 只保留第一条：后续错误往往是它的连锁反应，完整内容都已写进日志
+fn record_error(slot: &Mutex<Option<String>>, message: String) {
+    slot.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert(message);
 }
@@ -1 +1,5 @@
 pub mod instance;
+pub mod options;
+pub mod readback;
+pub mod target;
+pub mod window;
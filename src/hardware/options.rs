@@ -0,0 +1,37 @@
+use crate::common::error::{IrisError, IrisResult};
+use crate::common::types::{
+    IrisEngineOptions, IrisPowerPreference, IrisPresentMode, IrisSurfaceFormat,
+};
+
+// 校验过的引擎创建选项：C# 结构体里的枚举字段按 u32 传入，在这里转换成枚举
+#[derive(Clone, Copy, Debug)]
+pub struct EngineOptions {
+    pub backends: u32,
+    pub power_preference: IrisPowerPreference,
+    pub present_mode: IrisPresentMode,
+    pub frame_latency: u32,
+    pub surface_format: IrisSurfaceFormat,
+    pub sample_count: u32,
+    pub force_fallback_adapter: bool,
+    pub select_adapter: bool,
+    pub adapter_index: u32,
+}
+
+impl TryFrom<&IrisEngineOptions> for EngineOptions {
+    type Error = IrisError;
+
+    // 枚举字段越界时返回 InvalidArgument
+    fn try_from(options: &IrisEngineOptions) -> IrisResult<Self> {
+        Ok(Self {
+            backends: options.backends,
+            power_preference: options.power_preference.try_into()?,
+            present_mode: options.present_mode.try_into()?,
+            frame_latency: options.frame_latency,
+            surface_format: options.surface_format.try_into()?,
+            sample_count: options.sample_count,
+            force_fallback_adapter: options.force_fallback_adapter,
+            select_adapter: options.select_adapter,
+            adapter_index: options.adapter_index,
+        })
+    }
+}
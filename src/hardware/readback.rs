@@ -0,0 +1,217 @@
+use crate::common::error::{ensure, IrisError, IrisResult};
+use crate::common::types::{IrisReadbackFormat, IrisReadbackStatus, IrisStatus};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MapState {
+    Idle,
+    // 已经请求，窗口模式下要等下一次 render_frame 才会真正拷贝
+    Requested,
+    // 拷贝命令已提交，等待 map_async 回调
+    Mapping,
+    Mapped,
+    Failed,
+}
+
+// 把渲染结果从 GPU 拷回主机内存
+// wgpu 要求 copy_texture_to_buffer 的行跨度按 256 字节对齐，拷出时再去掉填充
+pub struct FrameReadback {
+    buffer: Option<wgpu::Buffer>,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    src_format: wgpu::TextureFormat,
+    state: Arc<Mutex<MapState>>,
+    // Failed 状态的原因，poll 时交给调用方
+    failure: Option<IrisError>,
+}
+
+impl FrameReadback {
+    pub fn new() -> Self {
+        Self {
+            buffer: None,
+            width: 0,
+            height: 0,
+            padded_bytes_per_row: 0,
+            src_format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            state: Arc::new(Mutex::new(MapState::Idle)),
+            failure: None,
+        }
+    }
+
+    fn state(&self) -> MapState {
+        *self.state.lock().unwrap()
+    }
+
+    fn set_state(&self, state: MapState) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    // 标记需要回读；正在映射中的请求不会被打断
+    pub fn request(&mut self) {
+        if matches!(self.state(), MapState::Idle | MapState::Failed) {
+            self.set_state(MapState::Requested);
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.state() == MapState::Requested
+    }
+
+    // 在渲染命令之后追加一次纹理到缓冲区的拷贝，需要纹理带 COPY_SRC
+    pub fn encode_copy(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        if !self.is_requested() {
+            return;
+        }
+        let format = texture.format();
+        let supported = matches!(
+            format,
+            wgpu::TextureFormat::Bgra8Unorm
+                | wgpu::TextureFormat::Bgra8UnormSrgb
+                | wgpu::TextureFormat::Rgba8Unorm
+                | wgpu::TextureFormat::Rgba8UnormSrgb
+        );
+        if !supported || !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            self.failure = Some(IrisError::new(
+                IrisStatus::GpuError,
+                format!("当前渲染目标不支持回读: {:?}", format),
+            ));
+            self.set_state(MapState::Failed);
+            return;
+        }
+
+        let (width, height) = (texture.width(), texture.height());
+        let unpadded = width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded = unpadded.div_ceil(align) * align;
+
+        // 同尺寸重复回读时复用缓冲区
+        if self.buffer.is_none() || self.width != width || self.height != height {
+            self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Frame_Readback_Buffer"),
+                size: padded as u64 * height as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }));
+            self.width = width;
+            self.height = height;
+            self.padded_bytes_per_row = padded;
+        }
+        self.src_format = format;
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: self.buffer.as_ref().unwrap(),
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.set_state(MapState::Mapping);
+    }
+
+    // 必须在包含拷贝的命令提交之后调用
+    pub fn map_after_submit(&self) {
+        if self.state() != MapState::Mapping {
+            return;
+        }
+        let state = self.state.clone();
+        self.buffer
+            .as_ref()
+            .unwrap()
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *state.lock().unwrap() = match result {
+                    Ok(()) => MapState::Mapped,
+                    Err(_) => MapState::Failed,
+                };
+            });
+    }
+
+    // 非阻塞查询；映射完成时把像素写进 dst 并返回 Ready，失败时返回原因
+    pub fn poll(
+        &mut self,
+        device: &wgpu::Device,
+        wait: bool,
+        dst: &mut [u8],
+        dst_stride: u32,
+        format: IrisReadbackFormat,
+    ) -> IrisResult<IrisReadbackStatus> {
+        let maintain = if wait {
+            wgpu::Maintain::Wait
+        } else {
+            wgpu::Maintain::Poll
+        };
+        let _ = device.poll(maintain);
+
+        match self.state() {
+            MapState::Idle => Ok(IrisReadbackStatus::Idle),
+            MapState::Requested | MapState::Mapping => Ok(IrisReadbackStatus::Pending),
+            MapState::Failed => {
+                self.set_state(MapState::Idle);
+                Err(self
+                    .failure
+                    .take()
+                    .unwrap_or_else(|| IrisError::new(IrisStatus::GpuError, "回读缓冲区映射失败")))
+            }
+            MapState::Mapped => {
+                let result = self.copy_out(dst, dst_stride, format);
+                self.buffer.as_ref().unwrap().unmap();
+                self.set_state(MapState::Idle);
+                result.map(|_| IrisReadbackStatus::Ready)
+            }
+        }
+    }
+
+    fn copy_out(&self, dst: &mut [u8], dst_stride: u32, format: IrisReadbackFormat) -> IrisResult {
+        let row_bytes = self.width as usize * 4;
+        let dst_stride = if dst_stride == 0 {
+            row_bytes
+        } else {
+            dst_stride as usize
+        };
+        ensure(
+            dst_stride >= row_bytes
+                && dst.len() >= dst_stride * (self.height as usize - 1) + row_bytes,
+            IrisStatus::BufferTooSmall,
+            || format!("回读缓冲区太小: {} 字节", dst.len()),
+        )?;
+
+        let src_is_bgra = matches!(
+            self.src_format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        let swap_rb = src_is_bgra != (format == IrisReadbackFormat::Bgra8);
+
+        let data = self.buffer.as_ref().unwrap().slice(..).get_mapped_range();
+        for y in 0..self.height as usize {
+            let src_row = &data[y * self.padded_bytes_per_row as usize..][..row_bytes];
+            let dst_row = &mut dst[y * dst_stride..][..row_bytes];
+            dst_row.copy_from_slice(src_row);
+            if swap_rb {
+                for px in dst_row.chunks_exact_mut(4) {
+                    px.swap(0, 2);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use crate::common::error::{IrisError, IrisResult};
+use crate::common::types::{IrisPresentMode, IrisStatus, IrisSurfaceFormat};
+use crate::hardware::instance::GpuContext;
+use crate::hardware::options::EngineOptions;
+
+// 引擎的渲染目标：窗口 Surface 或者引擎自己持有的离屏纹理
+pub enum RenderTarget {
+    Window {
+        surface: wgpu::Surface<'static>,
+        config: wgpu::SurfaceConfiguration,
+    },
+    Offscreen(OffscreenTarget),
+}
+
+pub struct OffscreenTarget {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub format: wgpu::TextureFormat,
+}
+
+impl OffscreenTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen_Back_Buffer"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            // COPY_SRC 留给回读，TEXTURE_BINDING 方便宿主再拿去采样
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            format,
+        }
+    }
+}
+
+// 一帧的渲染目标：窗口模式下持有 SurfaceTexture，离屏模式下直接引用自有纹理
+pub struct TargetFrame {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    surface_texture: Option<wgpu::SurfaceTexture>,
+}
+
+impl TargetFrame {
+    pub fn present(self) {
+        if let Some(output) = self.surface_texture {
+            output.present();
+        }
+    }
+}
+
+impl RenderTarget {
+    // 按显卡对该 surface 的支持情况和创建选项配置窗口目标
+    pub fn window(
+        ctx: &GpuContext,
+        surface: wgpu::Surface<'static>,
+        width: u32,
+        height: u32,
+        options: &EngineOptions,
+    ) -> Self {
+        let caps = surface.get_capabilities(&ctx.adapter);
+        let want_srgb = options.surface_format == IrisSurfaceFormat::Srgb;
+        let format = caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb() == want_srgb)
+            .unwrap_or_else(|| {
+                caps.formats
+                    .first()
+                    .copied()
+                    .unwrap_or(wgpu::TextureFormat::Bgra8UnormSrgb)
+            });
+
+        let alpha_mode = caps
+            .alpha_modes
+            .first()
+            .copied()
+            .unwrap_or(wgpu::CompositeAlphaMode::Auto);
+
+        let config = wgpu::SurfaceConfiguration {
+            // 显卡支持时带上 COPY_SRC，窗口模式也能回读
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | (caps.usages & wgpu::TextureUsages::COPY_SRC),
+            format,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: present_mode(&caps, options.present_mode),
+            alpha_mode,
+            view_formats: vec![],
+            // 帧积压默认降到 1，保证缩放时画面绝对最新
+            desired_maximum_frame_latency: options.frame_latency.max(1),
+        };
+        surface.configure(&ctx.device, &config);
+        RenderTarget::Window { surface, config }
+    }
+
+    // 运行时切换呈现模式 (如测量时关 VSync、回放时开 VSync)，离屏目标没有呈现模式
+    pub fn set_present_mode(&mut self, ctx: &GpuContext, mode: IrisPresentMode) -> IrisResult {
+        match self {
+            RenderTarget::Window { surface, config } => {
+                let caps = surface.get_capabilities(&ctx.adapter);
+                config.present_mode = present_mode(&caps, mode);
+                surface.configure(&ctx.device, config);
+                Ok(())
+            }
+            RenderTarget::Offscreen(_) => Err(IrisError::new(
+                IrisStatus::InvalidState,
+                "离屏引擎没有呈现模式",
+            )),
+        }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        match self {
+            RenderTarget::Window { config, .. } => config.format,
+            RenderTarget::Offscreen(target) => target.format,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        match self {
+            RenderTarget::Window { config, .. } => (config.width, config.height),
+            RenderTarget::Offscreen(target) => (target.texture.width(), target.texture.height()),
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        match self {
+            RenderTarget::Window { surface, config } => {
+                config.width = width.max(1);
+                config.height = height.max(1);
+                surface.configure(device, config);
+            }
+            RenderTarget::Offscreen(target) => {
+                // 尺寸没变就不重建，避免宿主频繁 resize 时反复分配显存
+                if target.texture.width() == width.max(1)
+                    && target.texture.height() == height.max(1)
+                {
+                    return;
+                }
+                *target = OffscreenTarget::new(device, width, height, target.format);
+            }
+        }
+    }
+
+    pub fn acquire(&self) -> Result<TargetFrame, wgpu::SurfaceError> {
+        match self {
+            RenderTarget::Window { surface, .. } => {
+                let output = surface.get_current_texture()?;
+                let view = output
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                Ok(TargetFrame {
+                    texture: output.texture.clone(),
+                    view,
+                    surface_texture: Some(output),
+                })
+            }
+            RenderTarget::Offscreen(target) => Ok(TargetFrame {
+                texture: target.texture.clone(),
+                view: target.view.clone(),
+                surface_texture: None,
+            }),
+        }
+    }
+}
+
+// 选项里的呈现模式 -> 显卡实际支持的模式
+fn present_mode(caps: &wgpu::SurfaceCapabilities, mode: IrisPresentMode) -> wgpu::PresentMode {
+    let supported = |m: &wgpu::PresentMode| caps.present_modes.contains(m);
+    match mode {
+        // 寻找 Mailbox 或 Immediate 模式，彻底解除缩放时的 VSync 阻塞
+        IrisPresentMode::LowLatency => [wgpu::PresentMode::Mailbox, wgpu::PresentMode::Immediate]
+            .into_iter()
+            .find(supported)
+            .unwrap_or(wgpu::PresentMode::AutoNoVsync), // 如果都不支持，强制不等待
+        IrisPresentMode::Fifo => wgpu::PresentMode::Fifo,
+        IrisPresentMode::FifoRelaxed => Some(wgpu::PresentMode::FifoRelaxed)
+            .filter(supported)
+            .unwrap_or(wgpu::PresentMode::Fifo),
+        IrisPresentMode::Mailbox => Some(wgpu::PresentMode::Mailbox)
+            .filter(supported)
+            .unwrap_or(wgpu::PresentMode::Fifo),
+        IrisPresentMode::Immediate => Some(wgpu::PresentMode::Immediate)
+            .filter(supported)
+            .unwrap_or(wgpu::PresentMode::Fifo),
+    }
+}
+
+// MSAA 的多重采样颜色缓冲：场景画到这里，渲染通道结束时解析到一帧的目标纹理上
+pub struct MsaaTarget {
+    pub view: wgpu::TextureView,
+    texture: wgpu::Texture,
+}
+
+impl MsaaTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA_Color_Buffer"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { view, texture }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.texture.width() == width.max(1) && self.texture.height() == height.max(1) {
+            return;
+        }
+        *self = Self::new(
+            device,
+            width,
+            height,
+            self.texture.format(),
+            self.texture.sample_count(),
+        );
+    }
+
+    // 校验请求的采样数：0/1 表示不开 MSAA；显卡不支持时逐级降低并写警告日志
+    pub fn sample_count(
+        ctx: &GpuContext,
+        format: wgpu::TextureFormat,
+        requested: u32,
+    ) -> IrisResult<u32> {
+        if requested <= 1 {
+            return Ok(1);
+        }
+        if ![2, 4, 8, 16].contains(&requested) {
+            return Err(IrisError::new(
+                IrisStatus::InvalidArgument,
+                format!("MSAA 采样数只支持 1/2/4/8/16: {}", requested),
+            ));
+        }
+        let features = ctx.device.features();
+        let flags = if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            ctx.adapter.get_texture_format_features(format).flags
+        } else {
+            format.guaranteed_format_features(features).flags
+        };
+        let resolve = flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE);
+        let count = [16, 8, 4, 2]
+            .into_iter()
+            .filter(|&n| resolve && n <= requested)
+            .find(|&n| flags.sample_count_supported(n))
+            .unwrap_or(1);
+        if count != requested {
+            log::warn!(
+                "显卡不支持 {:?} 的 {} 倍 MSAA，改用 {} 倍",
+                format,
+                requested,
+                count
+            );
+        }
+        Ok(count)
+    }
+}
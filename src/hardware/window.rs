@@ -0,0 +1,95 @@
+use crate::common::error::{IrisError, IrisResult};
+use crate::common::types::{backends_from_mask, IrisStatus, IrisWindowHandle, IrisWindowKind};
+use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
+
+// 窗口模式下后端掩码为 0 时的默认后端：Windows 沿用 DX12 (与 WPF 的 D3D 互操作最稳)，
+// Linux 用 Vulkan 并以 GL 兜底，Apple 平台只有 Metal
+pub fn window_backends(mask: u32) -> wgpu::Backends {
+    if mask != 0 {
+        backends_from_mask(mask)
+    } else if cfg!(windows) {
+        wgpu::Backends::DX12
+    } else if cfg!(target_vendor = "apple") {
+        wgpu::Backends::METAL
+    } else {
+        wgpu::Backends::VULKAN | wgpu::Backends::GL
+    }
+}
+
+// 宿主句柄 -> wgpu 的 surface 目标；当前平台不支持的窗口种类返回 InvalidArgument
+pub fn surface_target(handle: &IrisWindowHandle) -> IrisResult<wgpu::SurfaceTargetUnsafe> {
+    let (raw_display_handle, raw_window_handle) = raw_handles(handle)?;
+    Ok(wgpu::SurfaceTargetUnsafe::RawHandle {
+        raw_display_handle,
+        raw_window_handle,
+    })
+}
+
+fn raw_handles(handle: &IrisWindowHandle) -> IrisResult<(RawDisplayHandle, RawWindowHandle)> {
+    match IrisWindowKind::try_from(handle.kind)? {
+        #[cfg(windows)]
+        IrisWindowKind::Win32 => {
+            use raw_window_handle::{Win32WindowHandle, WindowsDisplayHandle};
+            use std::num::NonZeroIsize;
+            let hwnd = NonZeroIsize::new(handle.window as isize).ok_or_else(|| null("HWND"))?;
+            let mut window = Win32WindowHandle::new(hwnd);
+            window.hinstance = NonZeroIsize::new(handle.display as isize);
+            Ok((
+                RawDisplayHandle::Windows(WindowsDisplayHandle::new()),
+                RawWindowHandle::Win32(window),
+            ))
+        }
+        #[cfg(all(unix, not(any(target_vendor = "apple", target_os = "android"))))]
+        IrisWindowKind::Xlib => {
+            use raw_window_handle::{XlibDisplayHandle, XlibWindowHandle};
+            let display = std::ptr::NonNull::new(handle.display).ok_or_else(|| null("Display"))?;
+            if handle.window_id == 0 {
+                return Err(null("Window"));
+            }
+            Ok((
+                RawDisplayHandle::Xlib(XlibDisplayHandle::new(Some(display), handle.screen)),
+                RawWindowHandle::Xlib(XlibWindowHandle::new(handle.window_id as _)),
+            ))
+        }
+        #[cfg(all(unix, not(any(target_vendor = "apple", target_os = "android"))))]
+        IrisWindowKind::Xcb => {
+            use raw_window_handle::{XcbDisplayHandle, XcbWindowHandle};
+            let connection =
+                std::ptr::NonNull::new(handle.display).ok_or_else(|| null("xcb_connection_t"))?;
+            let window = u32::try_from(handle.window_id)
+                .ok()
+                .and_then(std::num::NonZeroU32::new)
+                .ok_or_else(|| {
+                    IrisError::new(
+                        IrisStatus::InvalidArgument,
+                        format!("xcb_window_t 无效: {}", handle.window_id),
+                    )
+                })?;
+            Ok((
+                RawDisplayHandle::Xcb(XcbDisplayHandle::new(Some(connection), handle.screen)),
+                RawWindowHandle::Xcb(XcbWindowHandle::new(window)),
+            ))
+        }
+        #[cfg(all(unix, not(any(target_vendor = "apple", target_os = "android"))))]
+        IrisWindowKind::Wayland => {
+            use raw_window_handle::{WaylandDisplayHandle, WaylandWindowHandle};
+            let display =
+                std::ptr::NonNull::new(handle.display).ok_or_else(|| null("wl_display"))?;
+            let surface =
+                std::ptr::NonNull::new(handle.window).ok_or_else(|| null("wl_surface"))?;
+            Ok((
+                RawDisplayHandle::Wayland(WaylandDisplayHandle::new(display)),
+                RawWindowHandle::Wayland(WaylandWindowHandle::new(surface)),
+            ))
+        }
+        #[allow(unreachable_patterns)]
+        kind => Err(IrisError::new(
+            IrisStatus::InvalidArgument,
+            format!("当前平台不支持 {:?} 窗口", kind),
+        )),
+    }
+}
+
+fn null(name: &str) -> IrisError {
+    IrisError::new(IrisStatus::NullArgument, format!("窗口句柄 {} 为空", name))
+}
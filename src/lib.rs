@@ -1,204 +1,2392 @@
+// FFI 入口的指针有效性由 C# 端保证，这里统一放行
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+mod common;
 mod hardware;
+mod pipeline;
+mod scene;
 
-use crate::hardware::instance::GpuContext;
-use raw_window_handle::{
-    RawDisplayHandle, RawWindowHandle, Win32WindowHandle, WindowsDisplayHandle,
+use crate::common::error::{ensure, last_error, set_last_error, IrisError, IrisResult};
+use crate::common::logging;
+use crate::common::math::{Calibration, ViewTransform};
+use crate::common::types::{
+    backends_from_mask, IrisAdapterInfo, IrisAdapterLimits, IrisColormap, IrisDemosaicMode,
+    IrisEngineOptions, IrisLayer, IrisLogLevel, IrisMeasurement, IrisMouseButton, IrisMouseResult,
+    IrisPixelFormat, IrisPresentMode, IrisReadbackFormat, IrisReadbackStatus, IrisRoiBoolOp,
+    IrisRoiStats, IrisRoiTool, IrisStatus, IrisTextAnchor, IrisTextSpace, IrisTextureFormat,
+    IrisWindowHandle, IrisWindowKind, IrisZOrder,
 };
-use std::{fs, panic};
+use crate::hardware::instance::GpuContext;
+use crate::hardware::options::EngineOptions;
+use crate::hardware::readback::FrameReadback;
+use crate::hardware::target::{MsaaTarget, OffscreenTarget, RenderTarget};
+use crate::hardware::window;
+use crate::pipeline::image_layer::ImageLayer;
+use crate::pipeline::roi_2d_shader::RoiRenderer;
+use crate::pipeline::roi_mask::{MaskKind, RoiMaskRenderer};
+use crate::pipeline::roi_stats::{LiveStats, RoiStatsComputer, StatsInput};
+use crate::pipeline::text::TextRenderer;
+use crate::scene::manager::SceneManager;
+use crate::scene::measure;
+use crate::scene::roi::{argb_to_rgba, Roi, RoiShape, RoiStyle};
+use crate::scene::text::{TextItem, TextSet, TextStyle};
+use glam::DVec2;
+use std::ffi::{c_char, CStr};
+use std::path::Path;
+use std::{any::Any, fs, panic};
 
 pub struct IrisEngine {
     pub context: GpuContext,
-    pub surface: wgpu::Surface<'static>,
-    pub config: wgpu::SurfaceConfiguration,
+    pub target: RenderTarget,
+    pub readback: FrameReadback,
+    pub image: ImageLayer,
+    pub view: ViewTransform,
+    pub scene: SceneManager,
+    pub roi_renderer: RoiRenderer,
+    pub mask_renderer: RoiMaskRenderer,
+    pub stats: RoiStatsComputer,
+    pub live_stats: LiveStats,
+    pub texts: TextSet,
+    pub text_renderer: TextRenderer,
+    // 开启 MSAA 时的多重采样缓冲，每帧解析到目标纹理
+    pub msaa: Option<MsaaTarget>,
+}
+
+// 约定：所有 iris_* 函数都返回 IrisStatus，结果通过 out_* 参数带回；
+// 失败时 out_* 参数不保证被写入，原因可用 iris_last_error_message 读取
+// 枚举参数按 u32 传入 (取值见同名的 Iris* 枚举)，越界时返回 InvalidArgument
+
+// 窗口引擎：成功创建后 *out_engine 为引擎指针，失败时为空指针
+// options 可为空指针，表示全部取默认值；其中后端掩码为 0 时用平台默认后端 (Windows 为 DX12，Linux 为 Vulkan + GL)
+// 当前平台不支持的窗口种类返回 InvalidArgument
+#[no_mangle]
+pub extern "C" fn iris_create_engine_window(
+    handle: *const IrisWindowHandle,
+    width: u32,
+    height: u32,
+    options: *const IrisEngineOptions,
+    out_engine: *mut *mut IrisEngine,
+) -> IrisStatus {
+    ffi(|| {
+        let handle = *unsafe { handle.as_ref() }.ok_or_else(|| null_arg("handle"))?;
+        create_window_engine(&handle, width, height, options, out_engine)
+    })
 }
 
+// Win32：hwnd 为 WPF HwndHost / WinForms 控件的窗口句柄
 #[no_mangle]
 pub extern "C" fn iris_create_engine(
     hwnd: *mut std::ffi::c_void,
     width: u32,
     height: u32,
-) -> *mut IrisEngine {
-    let result = panic::catch_unwind(|| {
-        //1、创建基础实例
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::DX12,
-            ..Default::default()
-        });
-        // 2、构建window handle
-        let target = wgpu::SurfaceTargetUnsafe::RawHandle {
-            raw_display_handle: RawDisplayHandle::Windows(WindowsDisplayHandle::new()),
-            raw_window_handle: RawWindowHandle::Win32(Win32WindowHandle::new(
-                std::num::NonZeroIsize::new(hwnd as isize).expect("HWND 不能为空"),
-            )),
-        };
+    options: *const IrisEngineOptions,
+    out_engine: *mut *mut IrisEngine,
+) -> IrisStatus {
+    let handle = IrisWindowHandle {
+        kind: IrisWindowKind::Win32 as u32,
+        display: std::ptr::null_mut(),
+        window: hwnd,
+        window_id: 0,
+        screen: 0,
+    };
+    ffi(|| create_window_engine(&handle, width, height, options, out_engine))
+}
 
-        //3、 创建surface
-        let surface =
-            unsafe { instance.create_surface_unsafe(target) }.expect("Failed to create window");
-        // 4. 请求 Adapter 时，传入 compatible_surface，确保显卡支持这个窗口！
-        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            compatible_surface: Some(&surface), // 告诉系统我们要在这个 surface 上渲染
-            force_fallback_adapter: false,
-        }))
-        .expect("找不到兼容的显卡适配器");
+// Xlib：display 为 Display*，window 为 Window (XID)
+#[no_mangle]
+pub extern "C" fn iris_create_engine_xlib(
+    display: *mut std::ffi::c_void,
+    window: u64,
+    screen: i32,
+    width: u32,
+    height: u32,
+    options: *const IrisEngineOptions,
+    out_engine: *mut *mut IrisEngine,
+) -> IrisStatus {
+    let handle = IrisWindowHandle {
+        kind: IrisWindowKind::Xlib as u32,
+        display,
+        window: std::ptr::null_mut(),
+        window_id: window,
+        screen,
+    };
+    ffi(|| create_window_engine(&handle, width, height, options, out_engine))
+}
 
-        // 5. 获取 Device 和 Queue
-        let (device, queue) = pollster::block_on(adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                label: Some("MogaIrisDevice"),
-                ..Default::default()
-            },
-            None,
-        ))
-        .expect("Device 创建失败");
-
-        // 6. 获取 Surface 支持的配置
-        let caps = surface.get_capabilities(&adapter);
-        let format = caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or_else(|| {
-                caps.formats
-                    .first()
-                    .copied()
-                    .unwrap_or(wgpu::TextureFormat::Bgra8UnormSrgb)
-            });
+// XCB：connection 为 xcb_connection_t*，window 为 xcb_window_t
+#[no_mangle]
+pub extern "C" fn iris_create_engine_xcb(
+    connection: *mut std::ffi::c_void,
+    window: u32,
+    screen: i32,
+    width: u32,
+    height: u32,
+    options: *const IrisEngineOptions,
+    out_engine: *mut *mut IrisEngine,
+) -> IrisStatus {
+    let handle = IrisWindowHandle {
+        kind: IrisWindowKind::Xcb as u32,
+        display: connection,
+        window: std::ptr::null_mut(),
+        window_id: window as u64,
+        screen,
+    };
+    ffi(|| create_window_engine(&handle, width, height, options, out_engine))
+}
 
-        let alpha_mode = caps
-            .alpha_modes
-            .first()
-            .copied()
-            .unwrap_or(wgpu::CompositeAlphaMode::Auto);
-
-        // 【关键优化 1】：寻找 Mailbox 或 Immediate 模式，彻底解除缩放时的 VSync 阻塞
-        let present_mode = caps
-            .present_modes
-            .iter()
-            .copied()
-            .find(|&m| m == wgpu::PresentMode::Mailbox || m == wgpu::PresentMode::Immediate)
-            .unwrap_or(wgpu::PresentMode::AutoNoVsync); // 如果都不支持，强制不等待
-
-        // 7. 配置 Surface
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format,
-            width: width.max(1),
-            height: height.max(1),
-            present_mode: wgpu::PresentMode::AutoNoVsync, // 使用无阻塞模式
-            alpha_mode,
-            view_formats: vec![],
-            desired_maximum_frame_latency: 1, // 【关键优化 2】：将帧积压降到 1，保证缩放时画面绝对最新
-        };
-        surface.configure(&device, &config);
-
-        // 8. 手动组装 GpuContext (不再调用 GpuContext::new，以适配新的初始化顺序)
-        let context = GpuContext {
-            instance,
-            adapter,
-            device,
-            queue,
-        };
+// Wayland：display 为 wl_display*，surface 为 wl_surface*
+#[no_mangle]
+pub extern "C" fn iris_create_engine_wayland(
+    display: *mut std::ffi::c_void,
+    surface: *mut std::ffi::c_void,
+    width: u32,
+    height: u32,
+    options: *const IrisEngineOptions,
+    out_engine: *mut *mut IrisEngine,
+) -> IrisStatus {
+    let handle = IrisWindowHandle {
+        kind: IrisWindowKind::Wayland as u32,
+        display,
+        window: surface,
+        window_id: 0,
+        screen: 0,
+    };
+    ffi(|| create_window_engine(&handle, width, height, options, out_engine))
+}
 
-        let engine = Box::new(IrisEngine {
-            context,
-            surface,
-            config,
-        });
-        Box::into_raw(engine)
-    });
-    result.unwrap_or_else(|err| {
-        // 如果发生 Panic，提取具体的报错信息
-        let msg = if let Some(s) = err.downcast_ref::<&str>() {
-            s.to_string()
-        } else if let Some(s) = err.downcast_ref::<String>() {
-            s.clone()
-        } else {
-            "Unknown panic".to_string()
-        };
+fn create_window_engine(
+    handle: &IrisWindowHandle,
+    width: u32,
+    height: u32,
+    options: *const IrisEngineOptions,
+    out_engine: *mut *mut IrisEngine,
+) -> IrisResult {
+    let out = out_mut(out_engine, "out_engine")?;
+    *out = std::ptr::null_mut();
+    let options = options_arg(options)?;
 
-        // 把崩溃原因写到当前运行目录下的日志文件里！
-        let _ = fs::write("rust_crash_log.txt", format!("Rust引擎启动崩溃: {}", msg));
+    //1、创建基础实例；按序号选适配器时后端与 iris_adapter_enumerate 的掩码含义一致
+    let backends = if options.select_adapter {
+        backends_from_mask(options.backends)
+    } else {
+        window::window_backends(options.backends)
+    };
+    let instance = GpuContext::create_instance(backends);
+    //2、 用宿主的窗口句柄创建surface
+    let target = window::surface_target(handle)?;
+    let surface = unsafe { instance.create_surface_unsafe(target) }
+        .map_err(|e| IrisError::new(IrisStatus::GpuError, format!("创建 surface 失败: {}", e)))?;
+    // 3. 请求 Adapter 时，传入 compatible_surface，确保显卡支持这个窗口！
+    // 4. 同时获取 Device 和 Queue
+    let context = pollster::block_on(GpuContext::from_instance(
+        instance,
+        Some(&surface), // 告诉系统我们要在这个 surface 上渲染
+        &options,
+    ))?;
+    // 5. 按 Surface 支持的格式和模式配置
+    let target = RenderTarget::window(&context, surface, width, height, &options);
+    *out = Box::into_raw(IrisEngine::new(context, target, width, height, &options)?);
+    Ok(())
+}
 
-        // 返回空指针给 C#，而不是让程序闪退
-        std::ptr::null_mut()
+// 无窗口引擎：渲染到引擎自己持有的离屏纹理，供服务、CI 和 Linux 检测机使用
+// options 可为空指针；其中后端掩码为 0 时用全部后端，force_fallback_adapter 适合没有显卡的 CI
+#[no_mangle]
+pub extern "C" fn iris_create_engine_headless(
+    width: u32,
+    height: u32,
+    format: u32,
+    options: *const IrisEngineOptions,
+    out_engine: *mut *mut IrisEngine,
+) -> IrisStatus {
+    ffi(|| {
+        let format = IrisTextureFormat::try_from(format)?;
+        let out = out_mut(out_engine, "out_engine")?;
+        *out = std::ptr::null_mut();
+        let options = options_arg(options)?;
+        let context = pollster::block_on(GpuContext::new(
+            backends_from_mask(options.backends),
+            &options,
+        ))?;
+        let target = OffscreenTarget::new(&context.device, width, height, format.to_wgpu());
+        let target = RenderTarget::Offscreen(target);
+        *out = Box::into_raw(IrisEngine::new(context, target, width, height, &options)?);
+        Ok(())
     })
 }
 
+// 空指针视为已销毁，返回 Ok
 #[no_mangle]
-pub extern "C" fn iris_destroy_engine(engine_ptr: *mut IrisEngine) {
-    if !engine_ptr.is_null() {
-        unsafe {
-            drop(Box::from_raw(engine_ptr));
+pub extern "C" fn iris_destroy_engine(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    ffi(|| {
+        if !engine_ptr.is_null() {
+            unsafe {
+                drop(Box::from_raw(engine_ptr));
+            }
         }
-    }
+        Ok(())
+    })
+}
+
+// 列出 backends 掩码 (0 表示全部后端) 下的适配器，写入 out_adapters[0..count]
+// *out_count 总是写入适配器总数；capacity 不够时返回 BufferTooSmall，可先传空指针查询数量
+#[no_mangle]
+pub extern "C" fn iris_adapter_enumerate(
+    backends: u32,
+    out_adapters: *mut IrisAdapterInfo,
+    capacity: u32,
+    out_count: *mut u32,
+) -> IrisStatus {
+    ffi(|| {
+        let adapters = GpuContext::enumerate_adapters(backends_from_mask(backends));
+        *out_mut(out_count, "out_count")? = adapters.len() as u32;
+        if adapters.is_empty() {
+            return Ok(());
+        }
+        ensure(
+            !out_adapters.is_null() && capacity as usize >= adapters.len(),
+            IrisStatus::BufferTooSmall,
+            || format!("缓冲区太小: 需要 {} 项", adapters.len()),
+        )?;
+        let dst = slice_mut_arg(out_adapters, adapters.len(), "out_adapters")?;
+        for (dst, adapter) in dst.iter_mut().zip(&adapters) {
+            *dst = IrisAdapterInfo::from_wgpu(&adapter.get_info());
+        }
+        Ok(())
+    })
 }
+
+// 引擎实际使用的适配器
 #[no_mangle]
-pub extern "C" fn iris_resize_engine(engine_ptr: *mut IrisEngine, width: u32, height: u32) {
-    if engine_ptr.is_null() {
-        return;
-    }
-    let engine = unsafe { &mut *engine_ptr };
-    engine.config.width = width.max(1);
-    engine.config.height = height.max(1);
-    engine
-        .surface
-        .configure(&engine.context.device, &engine.config);
+pub extern "C" fn iris_engine_get_adapter(
+    engine_ptr: *mut IrisEngine,
+    out: *mut IrisAdapterInfo,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        *out_mut(out, "out")? = IrisAdapterInfo::from_wgpu(&engine.context.adapter.get_info());
+        Ok(())
+    })
+}
+
+// 引擎设备的常用上限 (创建设备时直接取用了适配器的上限)
+#[no_mangle]
+pub extern "C" fn iris_engine_get_adapter_limits(
+    engine_ptr: *mut IrisEngine,
+    out: *mut IrisAdapterLimits,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        *out_mut(out, "out")? = IrisAdapterLimits::from_wgpu(&engine.context.device.limits());
+        Ok(())
+    })
+}
+
+// 适配器、驱动、全部特性和上限的文本报告，供技术支持排查客户机器；缓冲区约定同 iris_roi_export_json
+#[no_mangle]
+pub extern "C" fn iris_engine_get_adapter_report(
+    engine_ptr: *mut IrisEngine,
+    buffer: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        write_utf8(&engine.context.report(), buffer, capacity, out_len)
+    })
 }
 
+// 本线程最近一次失败的详细原因 (UTF-8，带结尾的 0)，缓冲区约定同 iris_roi_export_json
+// 只有失败的调用会改写这条消息，成功的调用不会清空它
 #[no_mangle]
-pub extern "C" fn iris_render_frame(engine_ptr: *mut IrisEngine) {
-    // 增加一个空指针保护，防止 C# 端传错导致 Rust 崩溃
-    if engine_ptr.is_null() {
-        return;
+pub extern "C" fn iris_last_error_message(
+    buffer: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> IrisStatus {
+    // 不经过 ffi()：查询自身的失败不能覆盖要读取的那条消息
+    let message = last_error();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        write_utf8(&message, buffer, capacity, out_len)
+    }));
+    match result {
+        Ok(Ok(())) => IrisStatus::Ok,
+        Ok(Err(e)) => e.status,
+        Err(_) => IrisStatus::Panic,
     }
-    let engine = unsafe { &mut *engine_ptr };
-    let ctx = &engine.context;
-
-    //1、从surface 拿到当前帧可以用来渲染的纹理
-    let output = match engine.surface.get_current_texture() {
-        Ok(texture) => texture,
-        Err(e) => {
-            eprintln!("获取surface纹理失败:{:?}", e);
-            return;
+}
+
+// 日志：引擎和 wgpu 的日志 (含校验错误、设备丢失) 统一经过这里，不依赖任何引擎实例
+// 默认级别为 Warn；既没有回调也没有日志文件时写到 stderr
+#[no_mangle]
+pub extern "C" fn iris_log_set_level(level: u32) -> IrisStatus {
+    ffi(|| {
+        let level = IrisLogLevel::try_from(level)?;
+        logging::set_level(level);
+        Ok(())
+    })
+}
+
+// 注册日志回调 (level, target, message)，传空指针注销；回调可能在任意线程上被调用
+// 字符串为 UTF-8、以 0 结尾，只在回调期间有效
+#[no_mangle]
+pub extern "C" fn iris_log_set_callback(
+    callback: Option<extern "C" fn(IrisLogLevel, *const c_char, *const c_char)>,
+) -> IrisStatus {
+    ffi(|| {
+        logging::set_callback(callback);
+        Ok(())
+    })
+}
+
+// 同时写入日志文件 (UTF-8，追加)，path 为空指针时关闭
+// 超过 max_bytes 时滚动为 path.1、path.2 …，最多保留 max_files 个；max_bytes 为 0 表示不滚动
+#[no_mangle]
+pub extern "C" fn iris_log_set_file(
+    path: *const c_char,
+    max_bytes: u64,
+    max_files: u32,
+) -> IrisStatus {
+    ffi(|| {
+        let path = if path.is_null() {
+            None
+        } else {
+            Some(utf8_arg(path, "path")?.into())
+        };
+        logging::set_file(path, max_bytes, max_files)
+            .map_err(|e| IrisError::new(IrisStatus::IoError, format!("打开日志文件失败: {}", e)))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_resize_engine(
+    engine_ptr: *mut IrisEngine,
+    width: u32,
+    height: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.target.resize(&engine.context.device, width, height);
+        let (width, height) = engine.target.size();
+        if let Some(msaa) = &mut engine.msaa {
+            msaa.resize(&engine.context.device, width, height);
         }
-    };
-    let view = output
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
-
-    // 2. 开始渲染编码
-    let mut encoder = ctx
-        .device
-        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-    {
-        let mut _rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Main Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
+        engine.view.set_viewport(width, height);
+        Ok(())
+    })
+}
+
+// 运行时切换窗口的呈现模式；显卡不支持时退回 Fifo，离屏引擎返回 InvalidState
+#[no_mangle]
+pub extern "C" fn iris_set_present_mode(engine_ptr: *mut IrisEngine, mode: u32) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let mode = IrisPresentMode::try_from(mode)?;
+        engine.target.set_present_mode(&engine.context, mode)
+    })
+}
+
+// 获取 surface 纹理失败 (如窗口最小化、surface 过期) 时返回 GpuError，这一帧不绘制
+#[no_mangle]
+pub extern "C" fn iris_render_frame(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| engine.render_frame())
+}
+
+// 上传一帧相机图像作为背景层；stride 为源数据行跨度 (字节)，传 0 表示紧密排列
+// 同尺寸的重复上传会复用已有的 GPU 纹理
+#[no_mangle]
+pub extern "C" fn iris_upload_image(
+    engine_ptr: *mut IrisEngine,
+    data: *const u8,
+    width: u32,
+    height: u32,
+    stride: u32,
+    format: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let format = IrisPixelFormat::try_from(format)?;
+        ensure(width > 0 && height > 0, IrisStatus::InvalidArgument, || {
+            format!("图像尺寸无效: {}x{}", width, height)
+        })?;
+        let row_bytes = format.row_bytes(width);
+        let stride_bytes = if stride == 0 {
+            row_bytes
+        } else {
+            stride as u64
+        };
+        // 尺寸来自宿主，数据长度可能超出地址范围，先于纹理尺寸检查算好
+        let len = stride_bytes
+            .checked_mul(height as u64 - 1)
+            .and_then(|n| n.checked_add(row_bytes))
+            .and_then(|n| usize::try_from(n).ok())
+            .ok_or_else(|| {
+                IrisError::new(
+                    IrisStatus::InvalidArgument,
+                    format!("图像尺寸过大: {}x{}", width, height),
+                )
+            })?;
+        let data = slice_arg(data, len, "data")?;
+        engine
+            .image
+            .upload(&engine.context, data, width, height, stride, format)?;
+        engine.view.set_image_size(width, height);
+        Ok(())
+    })
+}
+
+// 移除背景图像，恢复纯色背景
+#[no_mangle]
+pub extern "C" fn iris_clear_image(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.image.clear();
+        Ok(())
+    })
+}
+
+// Bayer 图像的去马赛克算法，修改后立即用已上传的原始数据重新解码
+#[no_mangle]
+pub extern "C" fn iris_set_demosaic_mode(engine_ptr: *mut IrisEngine, mode: u32) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let mode = IrisDemosaicMode::try_from(mode)?;
+        engine.image.set_demosaic_mode(&engine.context, mode);
+        Ok(())
+    })
+}
+
+// Bayer 图像的白平衡增益 (R, G, B)，在去马赛克之后逐通道相乘
+#[no_mangle]
+pub extern "C" fn iris_set_white_balance(
+    engine_ptr: *mut IrisEngine,
+    r: f32,
+    g: f32,
+    b: f32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.image.set_white_balance(&engine.context, r, g, b);
+        Ok(())
+    })
+}
+
+// 窗宽窗位，单位为图像原始值 (例如 Mono12 为 0~4095)；设置后关闭自动模式
+// min、max 须为有限值且 min < max，否则返回 InvalidArgument
+#[no_mangle]
+pub extern "C" fn iris_set_window_level(
+    engine_ptr: *mut IrisEngine,
+    min: f32,
+    max: f32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        ensure(
+            min.is_finite() && max.is_finite() && min < max,
+            IrisStatus::InvalidArgument,
+            || format!("窗口须为有限值且下限小于上限: {} / {}", min, max),
+        )?;
+        engine.image.set_window(min, max);
+        Ok(())
+    })
+}
+
+// 读取当前生效的窗口，自动模式下宿主可据此刷新界面上的滑块
+#[no_mangle]
+pub extern "C" fn iris_get_window_level(
+    engine_ptr: *mut IrisEngine,
+    out_min: *mut f32,
+    out_max: *mut f32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let (out_min, out_max) = (out_mut(out_min, "out_min")?, out_mut(out_max, "out_max")?);
+        (*out_min, *out_max) = engine.image.window();
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_set_gamma(engine_ptr: *mut IrisEngine, gamma: f32) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.image.set_gamma(gamma);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_set_invert(engine_ptr: *mut IrisEngine, invert: bool) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.image.set_invert(invert);
+        Ok(())
+    })
+}
+
+// 自动窗口：按 GPU 直方图的百分位裁剪 (例如 0.5 / 99.5)，每次上传后更新
+// 开启时百分位须在 0~100 之间且 low_percent < high_percent，否则返回 InvalidArgument；关闭时忽略百分位
+#[no_mangle]
+pub extern "C" fn iris_set_auto_window(
+    engine_ptr: *mut IrisEngine,
+    enabled: bool,
+    low_percent: f32,
+    high_percent: f32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let in_range = |p: f32| (0.0..=100.0).contains(&p);
+        ensure(
+            !enabled
+                || (in_range(low_percent) && in_range(high_percent) && low_percent < high_percent),
+            IrisStatus::InvalidArgument,
+            || {
+                format!(
+                    "百分位须在 0~100 之间且下限小于上限: {} / {}",
+                    low_percent, high_percent
+                )
+            },
+        )?;
+        engine
+            .image
+            .set_auto_window(&engine.context, enabled, low_percent, high_percent);
+        Ok(())
+    })
+}
+
+// 伪彩色查找表，在窗宽窗位之后生效；Gray 表示关闭
+#[no_mangle]
+pub extern "C" fn iris_set_colormap(engine_ptr: *mut IrisEngine, colormap: u32) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let colormap = IrisColormap::try_from(colormap)?;
+        engine.image.set_colormap(&engine.context.queue, colormap);
+        Ok(())
+    })
+}
+
+// 自定义查找表：rgba 为 entries 项 RGBA8，entries 只能是 256 或 4096
+#[no_mangle]
+pub extern "C" fn iris_set_custom_colormap(
+    engine_ptr: *mut IrisEngine,
+    rgba: *const u8,
+    entries: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let rgba = slice_arg(rgba, entries as usize * 4, "rgba")?;
+        engine
+            .image
+            .set_custom_colormap(&engine.context.queue, rgba, entries)
+    })
+}
+
+// 在视口右侧显示色带图例，两端标注当前窗口的最大 / 最小值
+#[no_mangle]
+pub extern "C" fn iris_set_color_bar_visible(
+    engine_ptr: *mut IrisEngine,
+    visible: bool,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.image.set_color_bar_visible(visible);
+        Ok(())
+    })
+}
+
+// 平移视图，dx / dy 为屏幕像素 (鼠标拖动的增量)
+#[no_mangle]
+pub extern "C" fn iris_view_pan(engine_ptr: *mut IrisEngine, dx: f64, dy: f64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.view.pan(dx, dy);
+        Ok(())
+    })
+}
+
+// 以屏幕点 (鼠标位置) 为锚点缩放，factor > 1 放大
+#[no_mangle]
+pub extern "C" fn iris_view_zoom_at(
+    engine_ptr: *mut IrisEngine,
+    screen_x: f64,
+    screen_y: f64,
+    factor: f64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.view.zoom_at(screen_x, screen_y, factor);
+        Ok(())
+    })
+}
+
+// 缩放为屏幕像素 / 图像像素，保持视口中心不变
+#[no_mangle]
+pub extern "C" fn iris_view_set_zoom(engine_ptr: *mut IrisEngine, zoom: f64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.view.set_zoom(zoom);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_view_get_zoom(
+    engine_ptr: *mut IrisEngine,
+    out_zoom: *mut f64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        *out_mut(out_zoom, "out_zoom")? = engine.view.zoom();
+        Ok(())
+    })
+}
+
+// 铺满视口并居中，之后窗口缩放时自动保持铺满，直到下一次平移 / 缩放
+#[no_mangle]
+pub extern "C" fn iris_view_zoom_to_fit(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.view.zoom_to_fit();
+        Ok(())
+    })
+}
+
+// 1:1 显示
+#[no_mangle]
+pub extern "C" fn iris_view_zoom_actual(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.view.zoom_actual();
+        Ok(())
+    })
+}
+
+// 按 90° 步进旋转显示，正数顺时针
+#[no_mangle]
+pub extern "C" fn iris_view_rotate(engine_ptr: *mut IrisEngine, quarter_turns: i32) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.view.rotate(quarter_turns);
+        Ok(())
+    })
+}
+
+// 屏幕坐标 (视口左上角为原点) -> 图像坐标 (像素 (i, j) 的中心为 (i + 0.5, j + 0.5))
+#[no_mangle]
+pub extern "C" fn iris_screen_to_image(
+    engine_ptr: *mut IrisEngine,
+    screen_x: f64,
+    screen_y: f64,
+    out_x: *mut f64,
+    out_y: *mut f64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let p = engine.view.screen_to_image(DVec2::new(screen_x, screen_y));
+        write_point(p, out_x, out_y)
+    })
+}
+
+// 图像坐标 -> 屏幕坐标，宿主据此在 WinForms / WPF 控件上摆放自己的覆盖物
+#[no_mangle]
+pub extern "C" fn iris_image_to_screen(
+    engine_ptr: *mut IrisEngine,
+    image_x: f64,
+    image_y: f64,
+    out_x: *mut f64,
+    out_y: *mut f64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let p = engine.view.image_to_screen(DVec2::new(image_x, image_y));
+        write_point(p, out_x, out_y)
+    })
+}
+
+// 像素检查的缩放阈值：缩放 >= nearest_zoom 时改为最近邻采样并可显示像素网格，
+// >= value_zoom 时在每个像素中心显示原始值 (彩色图像显示 R / G / B 三行)
+#[no_mangle]
+pub extern "C" fn iris_set_pixel_zoom_thresholds(
+    engine_ptr: *mut IrisEngine,
+    nearest_zoom: f32,
+    value_zoom: f32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine
+            .image
+            .set_pixel_zoom_thresholds(nearest_zoom, value_zoom);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_set_pixel_grid_visible(
+    engine_ptr: *mut IrisEngine,
+    visible: bool,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.image.set_pixel_grid_visible(visible);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_set_pixel_values_visible(
+    engine_ptr: *mut IrisEngine,
+    visible: bool,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.image.set_pixel_values_visible(visible);
+        Ok(())
+    })
+}
+
+// ROI：坐标为图像像素，角度为弧度 (正值顺时针)，新 ROI 的 ID 写入 out_id
+// 参数无效时返回 InvalidArgument
+#[no_mangle]
+pub extern "C" fn iris_roi_add_rect(
+    engine_ptr: *mut IrisEngine,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Rect {
+            x,
+            y,
+            width,
+            height,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_add_rotated_rect(
+    engine_ptr: *mut IrisEngine,
+    cx: f64,
+    cy: f64,
+    half_width: f64,
+    half_height: f64,
+    angle: f64,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::RotatedRect {
+            cx,
+            cy,
+            half_width,
+            half_height,
+            angle,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_add_circle(
+    engine_ptr: *mut IrisEngine,
+    cx: f64,
+    cy: f64,
+    radius: f64,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Circle { cx, cy, radius })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_add_ellipse(
+    engine_ptr: *mut IrisEngine,
+    cx: f64,
+    cy: f64,
+    rx: f64,
+    ry: f64,
+    angle: f64,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Ellipse {
+            cx,
+            cy,
+            rx,
+            ry,
+            angle,
+        })
+    })
+}
+
+// 圆环 / 扇环：sweep_angle 绝对值 >= 2π 为完整圆环，inner_radius 为 0 时退化为扇形
+#[no_mangle]
+pub extern "C" fn iris_roi_add_annulus(
+    engine_ptr: *mut IrisEngine,
+    cx: f64,
+    cy: f64,
+    inner_radius: f64,
+    outer_radius: f64,
+    start_angle: f64,
+    sweep_angle: f64,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Annulus {
+            cx,
+            cy,
+            inner_radius,
+            outer_radius,
+            start_angle,
+            sweep_angle,
+        })
+    })
+}
+
+// points 为 count 个 (x, y) 交错排列的 double，至少 3 个点
+#[no_mangle]
+pub extern "C" fn iris_roi_add_polygon(
+    engine_ptr: *mut IrisEngine,
+    points: *const f64,
+    count: u32,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Polygon {
+            points: points_arg(points, count)?,
+        })
+    })
+}
+
+fn add_roi(
+    engine_ptr: *mut IrisEngine,
+    out_id: *mut u64,
+    shape: impl FnOnce() -> IrisResult<RoiShape>,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out = out_mut(out_id, "out_id")?;
+        let shape = valid_shape(shape()?)?;
+        *out = engine.scene.add_roi(Roi {
+            shape,
+            style: RoiStyle::default(),
         });
-        // TODO: 这里之后调用 ROI 等几何绘制逻辑
-    }
+        Ok(())
+    })
+}
 
-    // 3. 提交渲染命令给 GPU
-    ctx.queue.submit(std::iter::once(encoder.finish()));
+// 按 ID 更新已有对象的几何，参数与对应的 iris_roi_add_* 相同，样式和图层等属性保持不变
+#[no_mangle]
+pub extern "C" fn iris_roi_update_rect(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> IrisStatus {
+    update_roi(engine_ptr, id, || {
+        Ok(RoiShape::Rect {
+            x,
+            y,
+            width,
+            height,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_update_rotated_rect(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    cx: f64,
+    cy: f64,
+    half_width: f64,
+    half_height: f64,
+    angle: f64,
+) -> IrisStatus {
+    update_roi(engine_ptr, id, || {
+        Ok(RoiShape::RotatedRect {
+            cx,
+            cy,
+            half_width,
+            half_height,
+            angle,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_update_circle(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    cx: f64,
+    cy: f64,
+    radius: f64,
+) -> IrisStatus {
+    update_roi(engine_ptr, id, || Ok(RoiShape::Circle { cx, cy, radius }))
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_update_ellipse(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    cx: f64,
+    cy: f64,
+    rx: f64,
+    ry: f64,
+    angle: f64,
+) -> IrisStatus {
+    update_roi(engine_ptr, id, || {
+        Ok(RoiShape::Ellipse {
+            cx,
+            cy,
+            rx,
+            ry,
+            angle,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_update_annulus(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    cx: f64,
+    cy: f64,
+    inner_radius: f64,
+    outer_radius: f64,
+    start_angle: f64,
+    sweep_angle: f64,
+) -> IrisStatus {
+    update_roi(engine_ptr, id, || {
+        Ok(RoiShape::Annulus {
+            cx,
+            cy,
+            inner_radius,
+            outer_radius,
+            start_angle,
+            sweep_angle,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_update_polygon(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    points: *const f64,
+    count: u32,
+) -> IrisStatus {
+    update_roi(engine_ptr, id, || {
+        Ok(RoiShape::Polygon {
+            points: points_arg(points, count)?,
+        })
+    })
+}
+
+// ROI 布尔组合：ids[0] 的形状换成 ids 全部形状按 op 运算的结果，其余 ROI 被删除
+// 差集为 ids[0] 减去其余全部；ROI 须在同一图层且未锁定、可见，运算结果为空时返回 InvalidArgument
+// 组合后的 ID (即 ids[0]) 写入 out_id
+#[no_mangle]
+pub extern "C" fn iris_roi_combine(
+    engine_ptr: *mut IrisEngine,
+    op: u32,
+    ids: *const u64,
+    count: u32,
+    out_id: *mut u64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let op = IrisRoiBoolOp::try_from(op)?;
+        let out = out_mut(out_id, "out_id")?;
+        let ids = slice_arg(ids, count as usize, "ids")?;
+        ensure(!ids.is_empty(), IrisStatus::InvalidArgument, || {
+            "组合至少需要一个 ROI".to_owned()
+        })?;
+        for &id in ids {
+            object_exists(engine, id)?;
+        }
+        let id = engine.scene.combine(op, ids);
+        ensure(id != 0, IrisStatus::InvalidArgument, || {
+            "组合失败: ROI 须在同一图层、互不相同且可编辑 (未锁定、可见)，不能是测量对象，结果不能为空"
+                .to_owned()
+        })?;
+        *out = id;
+        Ok(())
+    })
+}
+
+fn update_roi(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    shape: impl FnOnce() -> IrisResult<RoiShape>,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let shape = valid_shape(shape()?)?;
+        found(engine.scene.set_shape(id, shape), id)
+    })
+}
+
+fn valid_shape(shape: RoiShape) -> IrisResult<RoiShape> {
+    ensure(shape.is_valid(), IrisStatus::InvalidArgument, || {
+        format!("几何参数无效: {:?}", shape)
+    })?;
+    Ok(shape)
+}
 
-    // 4. 将画面呈现在 HWND 的屏幕上！
-    output.present();
+// 颜色为 0xAARRGGBB；stroke_width 为屏幕像素，0 表示不描边；填充 alpha 为 0 表示不填充
+// 只修改单个对象，分组 ID 返回 NotFound
+#[no_mangle]
+pub extern "C" fn iris_roi_set_style(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    stroke_argb: u32,
+    fill_argb: u32,
+    stroke_width: f32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let style = RoiStyle {
+            stroke_color: argb_to_rgba(stroke_argb),
+            fill_color: argb_to_rgba(fill_argb),
+            stroke_width: stroke_width.max(0.0),
+        };
+        found(engine.scene.set_style(id, style), id)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_remove(engine_ptr: *mut IrisEngine, id: u64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| found(engine.scene.remove_roi(id), id))
+}
+
+// 删除 ROI 图层上的全部对象，标注和 HUD 图层不受影响
+#[no_mangle]
+pub extern "C" fn iris_roi_clear(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.scene.clear_rois();
+        Ok(())
+    })
+}
+
+// 名称和标签为 UTF-8 字符串，随配方 JSON 保存；名称非空时在 ROI 上方画出 (需已加载字体)
+#[no_mangle]
+pub extern "C" fn iris_roi_set_name(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    name: *const c_char,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let name = utf8_arg(name, "name")?;
+        found(engine.scene.set_name(id, name), id)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_add_tag(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    tag: *const c_char,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let tag = utf8_arg(tag, "tag")?;
+        found(engine.scene.add_tag(id, tag), id)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_clear_tags(engine_ptr: *mut IrisEngine, id: u64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| found(engine.scene.clear_tags(id), id))
+}
+
+// 导出全部 ROI 为配方 JSON (UTF-8，带结尾的 0)
+// out_len 总是写入 JSON 的字节数 (不含结尾的 0)；buffer 为空或容量不足时返回 BufferTooSmall，
+// 宿主可先传空缓冲区查询长度
+#[no_mangle]
+pub extern "C" fn iris_roi_export_json(
+    engine_ptr: *mut IrisEngine,
+    buffer: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        write_utf8(&engine.scene.to_json(), buffer, capacity, out_len)
+    })
+}
+
+// 导出全部 ROI 和测量对象在世界坐标下的几何 (只用于导出，不能再导入)：
+// 曲线展平为折线，未标定时坐标仍为图像像素；缓冲区约定同 iris_roi_export_json
+#[no_mangle]
+pub extern "C" fn iris_roi_export_world_json(
+    engine_ptr: *mut IrisEngine,
+    buffer: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let json = engine.scene.to_world_json(engine.view.calibration());
+        write_utf8(&json, buffer, capacity, out_len)
+    })
+}
+
+// 从配方 JSON 加载，替换当前全部 ROI 并清空撤销历史；出错时场景保持不变
+#[no_mangle]
+pub extern "C" fn iris_roi_import_json(
+    engine_ptr: *mut IrisEngine,
+    json: *const c_char,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let json = utf8_arg(json, "json")?;
+        Ok(engine.scene.load_json(json)?)
+    })
+}
+
+// path 为 UTF-8 编码的文件路径
+#[no_mangle]
+pub extern "C" fn iris_roi_save_file(
+    engine_ptr: *mut IrisEngine,
+    path: *const c_char,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let path = utf8_arg(path, "path")?;
+        Ok(engine.scene.save_file(Path::new(path))?)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_roi_load_file(
+    engine_ptr: *mut IrisEngine,
+    path: *const c_char,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let path = utf8_arg(path, "path")?;
+        Ok(engine.scene.load_file(Path::new(path))?)
+    })
+}
+
+// ROI 掩膜：把 ids 中的 ROI (分组 ID 展开为成员) 光栅化成与图像同尺寸的掩膜并拷回 dst
+// ids 为空指针或 count 为 0 时取 ROI 图层的全部对象；HUD 对象和无效 ID 会使调用失败
+// 二值掩膜每像素 1 字节，ROI 并集内部为 255；dst_stride 为 0 表示紧密排列
+#[no_mangle]
+pub extern "C" fn iris_roi_render_mask(
+    engine_ptr: *mut IrisEngine,
+    ids: *const u64,
+    count: u32,
+    dst: *mut u8,
+    dst_len: usize,
+    dst_stride: u32,
+) -> IrisStatus {
+    render_mask(
+        engine_ptr,
+        ids,
+        count,
+        dst,
+        dst_len,
+        dst_stride,
+        MaskKind::Binary,
+    )
+}
+
+// 标签图：每像素 2 字节 (u16，主机字节序)，值为覆盖该像素的 ROI 的 ID，背景为 0
+// 重叠处以 ids 中靠后的 ROI 为准；ID 超过 65535 的 ROI 无法写入，返回 InvalidArgument
+#[no_mangle]
+pub extern "C" fn iris_roi_render_label_map(
+    engine_ptr: *mut IrisEngine,
+    ids: *const u64,
+    count: u32,
+    dst: *mut u16,
+    dst_len: usize,
+    dst_stride: u32,
+) -> IrisStatus {
+    render_mask(
+        engine_ptr,
+        ids,
+        count,
+        dst.cast(),
+        dst_len,
+        dst_stride,
+        MaskKind::Label,
+    )
+}
+
+// dst_len 和 dst_stride 都以字节计
+fn render_mask(
+    engine_ptr: *mut IrisEngine,
+    ids: *const u64,
+    count: u32,
+    dst: *mut u8,
+    dst_len: usize,
+    dst_stride: u32,
+    kind: MaskKind,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let dst = slice_mut_arg(dst, dst_len, "dst")?;
+        let (width, height) = engine.image.size().ok_or_else(no_image)?;
+        let ids = if ids.is_null() {
+            &[][..]
+        } else {
+            slice_arg(ids, count as usize, "ids")?
+        };
+        let shapes = engine.scene.mask_shapes(ids).ok_or_else(|| {
+            IrisError::new(
+                IrisStatus::InvalidArgument,
+                "ID 不存在、属于 HUD 图层或是测量对象",
+            )
+        })?;
+        // 标签图的像素值即对象 ID，只能表示到 65535；二值掩膜不用标签
+        let shapes = shapes
+            .into_iter()
+            .map(|(shape, id)| match kind {
+                MaskKind::Binary => Ok((shape, 1)),
+                MaskKind::Label => u16::try_from(id).map(|label| (shape, label)).map_err(|_| {
+                    IrisError::new(
+                        IrisStatus::InvalidArgument,
+                        format!("对象 ID {} 超出标签图的上限 65535", id),
+                    )
+                }),
+            })
+            .collect::<IrisResult<Vec<_>>>()?;
+        engine
+            .mask_renderer
+            .render(&engine.context, &shapes, width, height, kind);
+        engine
+            .mask_renderer
+            .read(&engine.context, kind, dst, dst_stride)
+    })
+}
+
+// ROI 统计：id 可以是对象 ID 或分组 ID (统计成员的并集)，需要已上传图像
+// 阻塞地统计一次，结果写入 out
+#[no_mangle]
+pub extern "C" fn iris_roi_get_stats(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    out: *mut IrisRoiStats,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out = out_mut(out, "out")?;
+        *out = engine.compute_roi_stats(id)?;
+        Ok(())
+    })
+}
+
+// 设置实时统计跟踪的 ROI，0 表示关闭；之后每帧渲染时按需异步刷新
+#[no_mangle]
+pub extern "C" fn iris_roi_stats_set_live(engine_ptr: *mut IrisEngine, id: u64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.live_stats.set_id(id);
+        Ok(())
+    })
+}
+
+// 取走实时统计的最新结果；自上次调用以来没有新结果时返回 NotReady
+#[no_mangle]
+pub extern "C" fn iris_roi_stats_poll(
+    engine_ptr: *mut IrisEngine,
+    out: *mut IrisRoiStats,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out = out_mut(out, "out")?;
+        if let Some(stats) = engine.stats.poll(&engine.context.device, false) {
+            engine.live_stats.deliver(stats);
+        }
+        *out = engine
+            .live_stats
+            .latest
+            .take()
+            .ok_or_else(|| IrisError::new(IrisStatus::NotReady, "没有新的实时统计结果"))?;
+        Ok(())
+    })
+}
+
+// 测量对象：放在注释图层，可以像 ROI 一样选中、拖动顶点、撤销，并用 iris_roi_* 设置样式和删除
+// 标签随几何实时更新，设置像素尺寸后以毫米显示
+#[no_mangle]
+pub extern "C" fn iris_measure_add_distance(
+    engine_ptr: *mut IrisEngine,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Distance {
+            points: vec![[x1, y1], [x2, y2]],
+        })
+    })
+}
+
+// (vx, vy) 为角的顶点
+#[no_mangle]
+pub extern "C" fn iris_measure_add_angle(
+    engine_ptr: *mut IrisEngine,
+    x1: f64,
+    y1: f64,
+    vx: f64,
+    vy: f64,
+    x2: f64,
+    y2: f64,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Angle {
+            points: vec![[x1, y1], [vx, vy], [x2, y2]],
+        })
+    })
+}
+
+// points 为 count 个 (x, y) 交错排列的 double，至少 2 个点
+#[no_mangle]
+pub extern "C" fn iris_measure_add_polyline(
+    engine_ptr: *mut IrisEngine,
+    points: *const f64,
+    count: u32,
+    out_id: *mut u64,
+) -> IrisStatus {
+    add_roi(engine_ptr, out_id, || {
+        Ok(RoiShape::Polyline {
+            points: points_arg(points, count)?,
+        })
+    })
+}
+
+// 按 ID 替换测量对象的点，种类保持不变，点数须与种类相符 (距离 2 个，角度 3 个)
+#[no_mangle]
+pub extern "C" fn iris_measure_update_points(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    points: *const f64,
+    count: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let points = points_arg(points, count)?;
+        let shape = match &measured_roi(engine, id)?.shape {
+            RoiShape::Distance { .. } => RoiShape::Distance { points },
+            RoiShape::Angle { .. } => RoiShape::Angle { points },
+            _ => RoiShape::Polyline { points },
+        };
+        let shape = valid_shape(shape)?;
+        found(engine.scene.set_shape(id, shape), id)
+    })
+}
+
+// 读取测量值；id 不是测量对象时返回 InvalidArgument
+#[no_mangle]
+pub extern "C" fn iris_measure_get(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    out: *mut IrisMeasurement,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out = out_mut(out, "out")?;
+        let roi = measured_roi(engine, id)?;
+        *out = measure::measurement(&roi.shape, engine.view.calibration()).ok_or_else(|| {
+            IrisError::new(IrisStatus::InvalidArgument, format!("对象 {} 无法测量", id))
+        })?;
+        Ok(())
+    })
+}
+
+fn measured_roi(engine: &IrisEngine, id: u64) -> IrisResult<&Roi> {
+    let roi = engine.scene.roi(id).ok_or_else(|| not_found(id))?;
+    ensure(
+        roi.shape.is_measurement(),
+        IrisStatus::InvalidArgument,
+        || format!("对象 {} 不是测量对象", id),
+    )?;
+    Ok(roi)
+}
+
+// 标定：把图像坐标换算为世界坐标 (毫米)，测量值、世界坐标读数和几何导出都据此计算
+// 像素尺寸为毫米 / 图像像素，0 清除标定；负数或非有限值返回 InvalidArgument
+#[no_mangle]
+pub extern "C" fn iris_set_pixel_size(
+    engine_ptr: *mut IrisEngine,
+    mm_per_pixel: f64,
+) -> IrisStatus {
+    set_calibration(engine_ptr, || {
+        ensure(
+            mm_per_pixel.is_finite() && mm_per_pixel >= 0.0,
+            IrisStatus::InvalidArgument,
+            || format!("像素尺寸须为有限的非负数: {}", mm_per_pixel),
+        )?;
+        Ok((mm_per_pixel > 0.0).then_some(Calibration::PixelSize { mm_per_pixel }))
+    })
+}
+
+// 未标定或标定不是均匀像素尺寸时写入 0
+#[no_mangle]
+pub extern "C" fn iris_get_pixel_size(
+    engine_ptr: *mut IrisEngine,
+    out_mm_per_pixel: *mut f64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        *out_mut(out_mm_per_pixel, "out_mm_per_pixel")? = match engine.view.calibration() {
+            Some(&Calibration::PixelSize { mm_per_pixel }) => mm_per_pixel,
+            _ => 0.0,
+        };
+        Ok(())
+    })
+}
+
+// matrix 为 6 个 double [a, b, c, d, e, f]：X = a x + b y + c，Y = d x + e y + f
+#[no_mangle]
+pub extern "C" fn iris_set_calibration_affine(
+    engine_ptr: *mut IrisEngine,
+    matrix: *const f64,
+) -> IrisStatus {
+    set_calibration(engine_ptr, || {
+        let matrix = slice_arg(matrix, 6, "matrix")?.try_into().unwrap();
+        Ok(Some(Calibration::Affine { matrix }))
+    })
+}
+
+// matrix 为按行排列的 3x3 单应矩阵 (9 个 double)，作用于齐次图像坐标 (x, y, 1)
+#[no_mangle]
+pub extern "C" fn iris_set_calibration_homography(
+    engine_ptr: *mut IrisEngine,
+    matrix: *const f64,
+) -> IrisStatus {
+    set_calibration(engine_ptr, || {
+        let matrix = slice_arg(matrix, 9, "matrix")?.try_into().unwrap();
+        Ok(Some(Calibration::Homography { matrix }))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_clear_calibration(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    set_calibration(engine_ptr, || Ok(None))
+}
+
+fn set_calibration(
+    engine_ptr: *mut IrisEngine,
+    calibration: impl FnOnce() -> IrisResult<Option<Calibration>>,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let calibration = calibration()?;
+        ensure(
+            engine.view.set_calibration(calibration),
+            IrisStatus::InvalidCalibration,
+            || format!("标定参数无效: {:?}", calibration),
+        )
+    })
+}
+
+// 导出当前标定为 JSON (见 Calibration 的注释)，未标定时为 null；缓冲区约定同 iris_roi_export_json
+#[no_mangle]
+pub extern "C" fn iris_calibration_export_json(
+    engine_ptr: *mut IrisEngine,
+    buffer: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let json =
+            serde_json::to_string_pretty(&engine.view.calibration()).expect("标定序列化不会失败");
+        write_utf8(&json, buffer, capacity, out_len)
+    })
+}
+
+// 从 JSON 加载标定，null 清除标定；出错时标定保持不变
+#[no_mangle]
+pub extern "C" fn iris_calibration_import_json(
+    engine_ptr: *mut IrisEngine,
+    json: *const c_char,
+) -> IrisStatus {
+    set_calibration(engine_ptr, || {
+        serde_json::from_str(utf8_arg(json, "json")?).map_err(|e| {
+            IrisError::new(
+                IrisStatus::SyntaxError,
+                format!("标定 JSON 格式错误: {}", e),
+            )
+        })
+    })
+}
+
+// 图像坐标 -> 世界坐标 (毫米)；未标定时返回 InvalidState，点在单应的消失线上时返回 InvalidArgument
+#[no_mangle]
+pub extern "C" fn iris_image_to_world(
+    engine_ptr: *mut IrisEngine,
+    image_x: f64,
+    image_y: f64,
+    out_x: *mut f64,
+    out_y: *mut f64,
+) -> IrisStatus {
+    map_calibrated(engine_ptr, out_x, out_y, |_, c| {
+        c.image_to_world(DVec2::new(image_x, image_y))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_world_to_image(
+    engine_ptr: *mut IrisEngine,
+    world_x: f64,
+    world_y: f64,
+    out_x: *mut f64,
+    out_y: *mut f64,
+) -> IrisStatus {
+    map_calibrated(engine_ptr, out_x, out_y, |_, c| {
+        c.world_to_image(DVec2::new(world_x, world_y))
+    })
+}
+
+// 光标读数：屏幕坐标直接换算到世界坐标
+#[no_mangle]
+pub extern "C" fn iris_screen_to_world(
+    engine_ptr: *mut IrisEngine,
+    screen_x: f64,
+    screen_y: f64,
+    out_x: *mut f64,
+    out_y: *mut f64,
+) -> IrisStatus {
+    map_calibrated(engine_ptr, out_x, out_y, |view, c| {
+        c.image_to_world(view.screen_to_image(DVec2::new(screen_x, screen_y)))
+    })
+}
+
+fn map_calibrated(
+    engine_ptr: *mut IrisEngine,
+    out_x: *mut f64,
+    out_y: *mut f64,
+    map: impl FnOnce(&ViewTransform, &Calibration) -> DVec2,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let calibration = engine
+            .view
+            .calibration()
+            .ok_or_else(|| IrisError::new(IrisStatus::InvalidState, "未设置标定"))?;
+        let p = map(&engine.view, calibration);
+        ensure(p.is_finite(), IrisStatus::InvalidArgument, || {
+            "点在单应的消失线上，无法换算".to_owned()
+        })?;
+        write_point(p, out_x, out_y)
+    })
+}
+
+// 字体：按加载顺序组成回退链，每个字符取第一个包含它的字体
+// 通常先加载西文字体，再加载中文字体 (如思源黑体) 作为回退
+#[no_mangle]
+pub extern "C" fn iris_font_load_bytes(
+    engine_ptr: *mut IrisEngine,
+    data: *const u8,
+    len: usize,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let data = slice_arg(data, len, "data")?;
+        engine.text_renderer.load_font(data.to_vec())
+    })
+}
+
+// path 为 UTF-8 编码、以 NUL 结尾的 TTF/OTF 文件路径
+#[no_mangle]
+pub extern "C" fn iris_font_load_file(
+    engine_ptr: *mut IrisEngine,
+    path: *const c_char,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let path = utf8_arg(path, "path")?;
+        let data = fs::read(path).map_err(|e| {
+            IrisError::new(
+                IrisStatus::IoError,
+                format!("读取字体文件失败: {}: {}", path, e),
+            )
+        })?;
+        engine.text_renderer.load_font(data)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_font_clear(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.text_renderer.clear_fonts();
+        Ok(())
+    })
+}
+
+// 文字对象：text 为 UTF-8、以 NUL 结尾，'\n' 换行；文字 ID 写入 out_id
+// (x, y) 的含义由 space 决定，见 IrisTextSpace
+#[no_mangle]
+pub extern "C" fn iris_text_add(
+    engine_ptr: *mut IrisEngine,
+    text: *const c_char,
+    x: f64,
+    y: f64,
+    space: u32,
+    out_id: *mut u64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let space = IrisTextSpace::try_from(space)?;
+        let out = out_mut(out_id, "out_id")?;
+        let text = utf8_arg(text, "text")?;
+        *out = engine.texts.add(text, DVec2::new(x, y), space);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_text_set_text(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    text: *const c_char,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let text = utf8_arg(text, "text")?;
+        text_item(engine, id)?.text = text.to_owned();
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_text_set_position(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    x: f64,
+    y: f64,
+    space: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let space = IrisTextSpace::try_from(space)?;
+        let item = text_item(engine, id)?;
+        item.position = DVec2::new(x, y);
+        item.space = space;
+        Ok(())
+    })
+}
+
+// size 和 padding 的单位随文字所在空间 (Image 为图像像素，其余为屏幕像素)
+// 颜色为 0xAARRGGBB，背景 alpha 为 0 时不画背景框
+#[no_mangle]
+pub extern "C" fn iris_text_set_style(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    size: f32,
+    color: u32,
+    background: u32,
+    padding: f32,
+    anchor: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let anchor = IrisTextAnchor::try_from(anchor)?;
+        ensure(
+            size.is_finite() && size > 0.0 && padding.is_finite() && padding >= 0.0,
+            IrisStatus::InvalidArgument,
+            || {
+                format!(
+                    "字号须为正数、留白不能为负: size {}, padding {}",
+                    size, padding
+                )
+            },
+        )?;
+        text_item(engine, id)?.style = TextStyle {
+            size,
+            color: argb_to_rgba(color),
+            background: argb_to_rgba(background),
+            padding,
+            anchor,
+        };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_text_remove(engine_ptr: *mut IrisEngine, id: u64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        ensure(engine.texts.remove(id), IrisStatus::NotFound, || {
+            format!("文字 {} 不存在", id)
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_text_clear(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.texts.clear();
+        Ok(())
+    })
+}
+
+fn text_item(engine: &mut IrisEngine, id: u64) -> IrisResult<&mut TextItem> {
+    engine
+        .texts
+        .get_mut(id)
+        .ok_or_else(|| IrisError::new(IrisStatus::NotFound, format!("文字 {} 不存在", id)))
+}
+
+// 场景对象属性：id 可以是对象 ID 或分组 ID，分组时作用于全部成员
+#[no_mangle]
+pub extern "C" fn iris_object_set_visible(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    visible: bool,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        found(engine.scene.set_visible(id, visible), id)
+    })
+}
+
+// 锁定的对象照常显示，但不能被鼠标选中和编辑
+#[no_mangle]
+pub extern "C" fn iris_object_set_locked(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    locked: bool,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        found(engine.scene.set_locked(id, locked), id)
+    })
+}
+
+// 不透明度 0~1，乘到描边和填充颜色的 alpha 上
+#[no_mangle]
+pub extern "C" fn iris_object_set_opacity(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    opacity: f32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        found(engine.scene.set_opacity(id, opacity), id)
+    })
+}
+
+// 移到其他图层 (不能是 Image)；移到 HUD 后坐标按屏幕像素解释
+#[no_mangle]
+pub extern "C" fn iris_object_set_layer(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    layer: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let layer = IrisLayer::try_from(layer)?;
+        ensure(
+            layer != IrisLayer::Image,
+            IrisStatus::InvalidArgument,
+            || "Image 图层只放相机图像，不接受对象".to_owned(),
+        )?;
+        found(engine.scene.set_layer(id, layer), id)
+    })
+}
+
+// 调整对象在所在图层内的叠放顺序
+#[no_mangle]
+pub extern "C" fn iris_object_set_z_order(
+    engine_ptr: *mut IrisEngine,
+    id: u64,
+    order: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let order = IrisZOrder::try_from(order)?;
+        found(engine.scene.set_z_order(id, order), id)
+    })
+}
+
+// 把 count 个对象编为一组，分组 ID 写入 out_id
+// 拖动分组中任一成员会整体移动，删除分组 ID 会删除全部成员
+#[no_mangle]
+pub extern "C" fn iris_group_create(
+    engine_ptr: *mut IrisEngine,
+    ids: *const u64,
+    count: u32,
+    out_id: *mut u64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out = out_mut(out_id, "out_id")?;
+        let ids = slice_arg(ids, count as usize, "ids")?;
+        ensure(!ids.is_empty(), IrisStatus::InvalidArgument, || {
+            "分组至少需要一个对象".to_owned()
+        })?;
+        for &id in ids {
+            object_exists(engine, id)?;
+        }
+        *out = engine.scene.group(ids);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_group_ungroup(engine_ptr: *mut IrisEngine, group_id: u64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        ensure(engine.scene.ungroup(group_id), IrisStatus::NotFound, || {
+            format!("分组 {} 不存在", group_id)
+        })
+    })
+}
+
+// 整个图层的显示 / 锁定；隐藏 HUD 图层同时隐藏色标
+#[no_mangle]
+pub extern "C" fn iris_layer_set_visible(
+    engine_ptr: *mut IrisEngine,
+    layer: u32,
+    visible: bool,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let layer = IrisLayer::try_from(layer)?;
+        engine.scene.set_layer_visible(layer, visible);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_layer_set_locked(
+    engine_ptr: *mut IrisEngine,
+    layer: u32,
+    locked: bool,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let layer = IrisLayer::try_from(layer)?;
+        engine.scene.set_layer_locked(layer, locked);
+        Ok(())
+    })
+}
+
+// 撤销 / 重做：覆盖对象的增删、几何编辑 (移动、缩放、旋转、顶点) 和属性修改
+// 一次鼠标拖动只记一条历史；没有可撤销的操作或拖动、创建进行中时返回 InvalidState
+#[no_mangle]
+pub extern "C" fn iris_undo(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        ensure(engine.scene.undo(), IrisStatus::InvalidState, || {
+            "没有可撤销的操作，或拖动 / 创建进行中".to_owned()
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_redo(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        ensure(engine.scene.redo(), IrisStatus::InvalidState, || {
+            "没有可重做的操作，或拖动 / 创建进行中".to_owned()
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_can_undo(engine_ptr: *mut IrisEngine, out_can: *mut bool) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        *out_mut(out_can, "out_can")? = engine.scene.can_undo();
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_can_redo(engine_ptr: *mut IrisEngine, out_can: *mut bool) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        *out_mut(out_can, "out_can")? = engine.scene.can_redo();
+        Ok(())
+    })
+}
+
+// 最多保留的历史条数 (默认 100)，超出时丢弃最早的记录，0 表示不记录
+#[no_mangle]
+pub extern "C" fn iris_set_history_depth(engine_ptr: *mut IrisEngine, depth: u32) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.scene.set_history_depth(depth as usize);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_clear_history(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.scene.clear_history();
+        Ok(())
+    })
+}
+
+// 鼠标交互：x / y 为视口内的屏幕坐标，modifiers 为 IRIS_MOD_* 掩码
+// 光标提示和 ROI 事件写入 out，宿主据此设置光标并在 Changing / Changed 时刷新界面
+#[no_mangle]
+pub extern "C" fn iris_mouse_down(
+    engine_ptr: *mut IrisEngine,
+    x: f64,
+    y: f64,
+    button: u32,
+    modifiers: u32,
+    out: *mut IrisMouseResult,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let button = IrisMouseButton::try_from(button)?;
+        let out = out_mut(out, "out")?;
+        *out = engine
+            .scene
+            .mouse_down(&mut engine.view, DVec2::new(x, y), button, modifiers);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_mouse_move(
+    engine_ptr: *mut IrisEngine,
+    x: f64,
+    y: f64,
+    out: *mut IrisMouseResult,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out = out_mut(out, "out")?;
+        *out = engine.scene.mouse_move(&mut engine.view, DVec2::new(x, y));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn iris_mouse_up(
+    engine_ptr: *mut IrisEngine,
+    x: f64,
+    y: f64,
+    button: u32,
+    out: *mut IrisMouseResult,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let button = IrisMouseButton::try_from(button)?;
+        let out = out_mut(out, "out")?;
+        *out = engine
+            .scene
+            .mouse_up(&mut engine.view, DVec2::new(x, y), button);
+        Ok(())
+    })
+}
+
+// delta 为滚轮增量 (WPF 一格为 120)，正值放大
+#[no_mangle]
+pub extern "C" fn iris_mouse_wheel(
+    engine_ptr: *mut IrisEngine,
+    x: f64,
+    y: f64,
+    delta: f64,
+    out: *mut IrisMouseResult,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out = out_mut(out, "out")?;
+        *out = engine
+            .scene
+            .mouse_wheel(&mut engine.view, DVec2::new(x, y), delta);
+        Ok(())
+    })
+}
+
+// 交互工具：创建完一个形状后自动回到 Select
+#[no_mangle]
+pub extern "C" fn iris_set_roi_tool(engine_ptr: *mut IrisEngine, tool: u32) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let tool = IrisRoiTool::try_from(tool)?;
+        engine.scene.set_tool(tool);
+        Ok(())
+    })
+}
+
+// 选中 ROI 并显示编辑手柄，传 0 取消选择；锁定、隐藏或 HUD 上的对象返回 InvalidState
+#[no_mangle]
+pub extern "C" fn iris_roi_select(engine_ptr: *mut IrisEngine, id: u64) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        if id != 0 {
+            object_exists(engine, id)?;
+        }
+        ensure(engine.scene.select(id), IrisStatus::InvalidState, || {
+            format!("对象 {} 已锁定、隐藏或位于 HUD 图层，不能选中", id)
+        })
+    })
+}
+
+// 当前选中的 ROI，没有时写入 0
+#[no_mangle]
+pub extern "C" fn iris_roi_get_selected(
+    engine_ptr: *mut IrisEngine,
+    out_id: *mut u64,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        *out_mut(out_id, "out_id")? = engine.scene.selected().unwrap_or(0);
+        Ok(())
+    })
+}
+
+// 查询当前渲染目标的尺寸，宿主据此分配回读缓冲区 (width * 4 * height)
+#[no_mangle]
+pub extern "C" fn iris_get_frame_size(
+    engine_ptr: *mut IrisEngine,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let out_width = out_mut(out_width, "out_width")?;
+        let out_height = out_mut(out_height, "out_height")?;
+        (*out_width, *out_height) = engine.target.size();
+        Ok(())
+    })
+}
+
+// 阻塞回读：把最近一帧拷进调用方提供的缓冲区
+// dst_stride 为目标行跨度 (字节)，传 0 表示紧密排列 width * 4
+#[no_mangle]
+pub extern "C" fn iris_readback_frame(
+    engine_ptr: *mut IrisEngine,
+    dst: *mut u8,
+    dst_len: usize,
+    dst_stride: u32,
+    format: u32,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let format = IrisReadbackFormat::try_from(format)?;
+        let dst = slice_mut_arg(dst, dst_len, "dst")?;
+        engine.request_readback();
+        if engine.readback.is_requested() {
+            // 窗口模式下 SurfaceTexture 在 present 之后就不可用了，只能重新画一帧并在提交前拷贝
+            engine.render_frame()?;
+        }
+        let status = engine
+            .readback
+            .poll(&engine.context.device, true, dst, dst_stride, format)?;
+        ensure(
+            status == IrisReadbackStatus::Ready,
+            IrisStatus::NotReady,
+            || format!("回读没有完成: {:?}", status),
+        )
+    })
+}
+
+// 异步回读第一步：发起请求，离屏模式立即拷贝，窗口模式在下一次 iris_render_frame 时拷贝
+#[no_mangle]
+pub extern "C" fn iris_readback_request(engine_ptr: *mut IrisEngine) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        engine.request_readback();
+        Ok(())
+    })
+}
+
+// 异步回读第二步：不阻塞地轮询，*out_state 为 Ready 时 dst 中已是完整的一帧
+// 回读失败时 *out_state 为 Failed，并返回失败原因对应的状态
+#[no_mangle]
+pub extern "C" fn iris_readback_poll(
+    engine_ptr: *mut IrisEngine,
+    dst: *mut u8,
+    dst_len: usize,
+    dst_stride: u32,
+    format: u32,
+    out_state: *mut IrisReadbackStatus,
+) -> IrisStatus {
+    with_engine(engine_ptr, |engine| {
+        let format = IrisReadbackFormat::try_from(format)?;
+        let out = out_mut(out_state, "out_state")?;
+        let dst = slice_mut_arg(dst, dst_len, "dst")?;
+        let result = engine
+            .readback
+            .poll(&engine.context.device, false, dst, dst_stride, format);
+        *out = *result.as_ref().unwrap_or(&IrisReadbackStatus::Failed);
+        result.map(|_| ())
+    })
+}
+
+// 每个 FFI 入口都经过这里：拦截 panic，失败时把原因记为本线程的最后一条错误
+fn ffi(f: impl FnOnce() -> IrisResult) -> IrisStatus {
+    logging::init();
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(Ok(())) => IrisStatus::Ok,
+        Ok(Err(e)) => {
+            // 失败已经通过返回值告知宿主，这里只留调试记录
+            log::debug!("{}", e);
+            set_last_error(&e.message);
+            e.status
+        }
+        Err(payload) => {
+            set_last_error(&format!("引擎内部错误: {}", panic_message(&*payload)));
+            IrisStatus::Panic
+        }
+    }
+}
+
+fn with_engine(
+    engine_ptr: *mut IrisEngine,
+    f: impl FnOnce(&mut IrisEngine) -> IrisResult,
+) -> IrisStatus {
+    ffi(|| {
+        let engine = unsafe { engine_ptr.as_mut() }.ok_or_else(|| null_arg("engine"))?;
+        let result = f(engine);
+        // 调用期间 wgpu 拒绝的 GPU 操作也算本次调用失败，不能返回 Ok 留下空白画面
+        let gpu = engine.context.take_error();
+        result.and(gpu)
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+fn null_arg(name: &str) -> IrisError {
+    IrisError::new(IrisStatus::NullArgument, format!("参数 {} 为空指针", name))
+}
+
+fn no_image() -> IrisError {
+    IrisError::new(IrisStatus::NoImage, "还没有上传图像")
+}
+
+fn not_found(id: u64) -> IrisError {
+    IrisError::new(IrisStatus::NotFound, format!("对象或分组 {} 不存在", id))
+}
+
+// 场景方法返回 false 即表示 ID 不存在 (其余失败原因已在调用前检查)
+fn found(ok: bool, id: u64) -> IrisResult {
+    if ok {
+        Ok(())
+    } else {
+        Err(not_found(id))
+    }
+}
+
+fn object_exists(engine: &IrisEngine, id: u64) -> IrisResult {
+    found(engine.scene.object(id).is_some(), id)
+}
+
+// 引擎创建选项，空指针表示全部取默认值；枚举字段越界时返回 InvalidArgument
+fn options_arg(ptr: *const IrisEngineOptions) -> IrisResult<EngineOptions> {
+    EngineOptions::try_from(&unsafe { ptr.as_ref() }.copied().unwrap_or_default())
+}
+
+// 宿主提供的输出参数
+fn out_mut<'a, T>(ptr: *mut T, name: &str) -> IrisResult<&'a mut T> {
+    unsafe { ptr.as_mut() }.ok_or_else(|| null_arg(name))
+}
+
+fn write_point(p: DVec2, out_x: *mut f64, out_y: *mut f64) -> IrisResult {
+    let (out_x, out_y) = (out_mut(out_x, "out_x")?, out_mut(out_y, "out_y")?);
+    (*out_x, *out_y) = (p.x, p.y);
+    Ok(())
+}
+
+fn slice_arg<'a, T>(ptr: *const T, len: usize, name: &str) -> IrisResult<&'a [T]> {
+    ensure(!ptr.is_null(), IrisStatus::NullArgument, || {
+        format!("参数 {} 为空指针", name)
+    })?;
+    Ok(unsafe { std::slice::from_raw_parts(ptr, len) })
+}
+
+fn slice_mut_arg<'a, T>(ptr: *mut T, len: usize, name: &str) -> IrisResult<&'a mut [T]> {
+    ensure(!ptr.is_null(), IrisStatus::NullArgument, || {
+        format!("参数 {} 为空指针", name)
+    })?;
+    Ok(unsafe { std::slice::from_raw_parts_mut(ptr, len) })
+}
+
+// count 个 (x, y) 交错排列的 double
+fn points_arg(points: *const f64, count: u32) -> IrisResult<Vec<[f64; 2]>> {
+    let coords = slice_arg(points, count as usize * 2, "points")?;
+    Ok(coords.chunks_exact(2).map(|p| [p[0], p[1]]).collect())
+}
+
+// 宿主传入的以 0 结尾的 UTF-8 字符串
+fn utf8_arg<'a>(ptr: *const c_char, name: &str) -> IrisResult<&'a str> {
+    ensure(!ptr.is_null(), IrisStatus::NullArgument, || {
+        format!("参数 {} 为空指针", name)
+    })?;
+    unsafe { CStr::from_ptr(ptr) }.to_str().map_err(|_| {
+        IrisError::new(
+            IrisStatus::InvalidUtf8,
+            format!("参数 {} 不是合法的 UTF-8", name),
+        )
+    })
+}
+
+// 把字符串连同结尾的 0 写进宿主缓冲区，out_len 总是写入不含结尾 0 的字节数
+fn write_utf8(text: &str, buffer: *mut u8, capacity: usize, out_len: *mut usize) -> IrisResult {
+    *out_mut(out_len, "out_len")? = text.len();
+    ensure(
+        !buffer.is_null() && capacity > text.len(),
+        IrisStatus::BufferTooSmall,
+        || format!("缓冲区太小: 需要 {} 字节", text.len() + 1),
+    )?;
+    let dst = unsafe { std::slice::from_raw_parts_mut(buffer, text.len() + 1) };
+    dst[..text.len()].copy_from_slice(text.as_bytes());
+    dst[text.len()] = 0;
+    Ok(())
+}
+
+impl IrisEngine {
+    fn new(
+        context: GpuContext,
+        target: RenderTarget,
+        width: u32,
+        height: u32,
+        options: &EngineOptions,
+    ) -> IrisResult<Box<Self>> {
+        let format = target.format();
+        let samples = MsaaTarget::sample_count(&context, format, options.sample_count)?;
+        let (target_width, target_height) = target.size();
+        let msaa = (samples > 1).then(|| {
+            MsaaTarget::new(
+                &context.device,
+                target_width,
+                target_height,
+                format,
+                samples,
+            )
+        });
+        let image = ImageLayer::new(&context, format, samples);
+        let roi_renderer = RoiRenderer::new(&context.device, format, samples);
+        let text_renderer = TextRenderer::new(&context, format, samples);
+        let mask_renderer = RoiMaskRenderer::new(&context.device);
+        let stats = RoiStatsComputer::new(&context.device);
+        // 管线创建阶段的校验错误同样要让创建失败
+        context.take_error()?;
+        Ok(Box::new(IrisEngine {
+            context,
+            target,
+            readback: FrameReadback::new(),
+            image,
+            view: ViewTransform::new(width, height),
+            scene: SceneManager::new(),
+            roi_renderer,
+            mask_renderer,
+            stats,
+            live_stats: LiveStats::default(),
+            texts: TextSet::new(),
+            text_renderer,
+            msaa,
+        }))
+    }
+
+    pub fn render_frame(&mut self) -> IrisResult {
+        self.update_live_stats();
+        let ctx = &self.context;
+
+        //1、从surface (或离屏纹理) 拿到当前帧可以用来渲染的纹理
+        let frame = self.target.acquire().map_err(|e| {
+            IrisError::new(
+                IrisStatus::GpuError,
+                format!("获取surface纹理失败: {:?}", e),
+            )
+        })?;
+
+        self.image.prepare(ctx, &self.view);
+        self.roi_renderer.prepare(ctx, &self.scene, &self.view);
+        // ROI 名称和测量标签每帧随几何重新生成，画在宿主添加的文字下面
+        let mut texts = measure::name_labels(&self.scene, &self.view);
+        texts.extend(measure::labels(&self.scene, &self.view));
+        texts.extend_from_slice(self.texts.items());
+        self.text_renderer.prepare(ctx, &texts, &self.view);
+
+        // 2. 开始渲染编码
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        // 开启 MSAA 时画到多重采样缓冲，通道结束时解析到这一帧的纹理
+        let (view, resolve_target) = match &self.msaa {
+            Some(msaa) => (&msaa.view, Some(&frame.view)),
+            None => (&frame.view, None),
+        };
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Main Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.1,
+                            g: 0.2,
+                            b: 0.3,
+                            a: 1.0,
+                        }),
+                        // MSAA 缓冲解析完就没用了，不必写回显存
+                        store: if resolve_target.is_some() {
+                            wgpu::StoreOp::Discard
+                        } else {
+                            wgpu::StoreOp::Store
+                        },
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            // 按图层自下而上：相机图像、ROI 与注释、HUD (色标和屏幕空间对象)
+            // 图像空间的文字属于注释图层，屏幕空间的文字属于 HUD 图层
+            if self.scene.layer_visible(IrisLayer::Image) {
+                self.image.draw(&mut rpass);
+            }
+            self.roi_renderer.draw(&mut rpass);
+            if self.scene.layer_visible(IrisLayer::Annotation) {
+                self.text_renderer.draw(&mut rpass);
+            }
+            if self.scene.layer_visible(IrisLayer::Hud) {
+                self.image.draw_color_bar(&mut rpass);
+                self.roi_renderer.draw_hud(&mut rpass);
+                self.text_renderer.draw_hud(&mut rpass);
+            }
+        }
+
+        // 回读请求挂在这一帧上：拷贝必须在 present 之前编码
+        self.readback
+            .encode_copy(&ctx.device, &mut encoder, &frame.texture);
+
+        // 3. 提交渲染命令给 GPU
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+        self.readback.map_after_submit();
+
+        // 4. 将画面呈现在 HWND 的屏幕上！(离屏模式下什么也不做)
+        frame.present();
+        Ok(())
+    }
+
+    // 渲染 ROI 的二值掩膜，按 ROI 外接矩形提交统计
+    fn dispatch_roi_stats(&mut self, id: u64) -> IrisResult {
+        let (Some((width, height)), Some(image)) = (self.image.size(), self.image.decoded_view())
+        else {
+            return Err(no_image());
+        };
+        let shapes = self.scene.mask_shapes(&[id]).ok_or_else(|| {
+            IrisError::new(
+                IrisStatus::InvalidArgument,
+                format!("ID {} 不存在、属于 HUD 图层或是测量对象", id),
+            )
+        })?;
+        // 统计只用二值掩膜，标签按成员序号给，与对象 ID 无关
+        let shapes: Vec<(&RoiShape, u16)> = shapes
+            .into_iter()
+            .enumerate()
+            .map(|(i, (shape, _))| (shape, (i + 1).min(u16::MAX as usize) as u16))
+            .collect();
+        let (mut lo, mut hi) = (DVec2::splat(f64::INFINITY), DVec2::splat(f64::NEG_INFINITY));
+        for (shape, _) in &shapes {
+            let (a, b) = shape.bounds(0.25);
+            lo = lo.min(a);
+            hi = hi.max(b);
+        }
+        // 外扩一个像素，抵消展平误差，再裁剪到图像内
+        let size = DVec2::new(width as f64, height as f64);
+        let lo = (lo.floor() - 1.0).clamp(DVec2::ZERO, size);
+        let hi = (hi.ceil() + 1.0).clamp(DVec2::ZERO, size).max(lo);
+        let mask =
+            self.mask_renderer
+                .render(&self.context, &shapes, width, height, MaskKind::Binary);
+        let input = StatsInput {
+            image,
+            mask: &mask.view,
+            origin: [lo.x as u32, lo.y as u32],
+            size: [(hi.x - lo.x) as u32, (hi.y - lo.y) as u32],
+            mono: self.image.is_mono(),
+            max_value: self.image.max_value(),
+        };
+        ensure(
+            self.stats.dispatch(&self.context, &input),
+            IrisStatus::InvalidState,
+            || "上一次统计还没有完成".to_owned(),
+        )
+    }
+
+    // 阻塞统计：先收掉在途的实时统计，再单独统计一次
+    pub fn compute_roi_stats(&mut self, id: u64) -> IrisResult<IrisRoiStats> {
+        if let Some(stats) = self.stats.poll(&self.context.device, true) {
+            self.live_stats.deliver(stats);
+        }
+        self.dispatch_roi_stats(id)?;
+        self.stats
+            .poll(&self.context.device, true)
+            .ok_or_else(|| IrisError::new(IrisStatus::GpuError, "ROI 统计回读失败"))
+    }
+
+    // 收取实时统计的结果，跟踪的 ROI 或图像变过时重新提交 (不阻塞)
+    fn update_live_stats(&mut self) {
+        if let Some(stats) = self.stats.poll(&self.context.device, false) {
+            self.live_stats.deliver(stats);
+        }
+        let id = self.live_stats.id;
+        let key = Some((self.scene.revision(), self.image.generation()));
+        if id == 0 || self.stats.is_busy() || self.live_stats.key == key {
+            return;
+        }
+        self.live_stats.key = key;
+        if self.dispatch_roi_stats(id).is_ok() {
+            self.live_stats.in_flight = Some(id);
+        }
+    }
+
+    pub fn request_readback(&mut self) {
+        self.readback.request();
+        // 离屏纹理一直存在，不必等下一帧，直接拷贝
+        if let RenderTarget::Offscreen(target) = &self.target {
+            let ctx = &self.context;
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Readback Encoder"),
+                });
+            self.readback
+                .encode_copy(&ctx.device, &mut encoder, &target.texture);
+            ctx.queue.submit(std::iter::once(encoder.finish()));
+            self.readback.map_after_submit();
+        }
+    }
 }
@@ -0,0 +1,212 @@
+// 与 shaders/color_bar.wgsl 中的 ColorBarUniforms 一致
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ColorBarUniforms {
+    viewport: [f32; 2],
+    glyph_scale: f32,
+    lut_enabled: u32,
+    panel: [f32; 4],
+    bar: [f32; 4],
+    top_label_pos: [f32; 2],
+    bottom_label_pos: [f32; 2],
+    srgb_target: u32,
+    _pad: [u32; 3],
+    top_label: [[u32; 4]; 4],
+    bottom_label: [[u32; 4]; 4],
+}
+
+// 布局参数 (屏幕像素)
+const GLYPH_SCALE: f32 = 2.0;
+const CHAR_ADVANCE: f32 = 4.0 * GLYPH_SCALE;
+const LABEL_HEIGHT: f32 = 5.0 * GLYPH_SCALE;
+const BAR_WIDTH: f32 = 16.0;
+const MARGIN: f32 = 12.0;
+const PADDING: f32 = 6.0;
+const MAX_LABEL_CHARS: usize = 16;
+
+// 色带图例：固定在视口右侧，随窗宽窗位和查找表实时更新
+pub struct ColorBar {
+    pipeline: wgpu::RenderPipeline,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    srgb_target: bool,
+    pub visible: bool,
+}
+
+impl ColorBar {
+    pub fn new(
+        device: &wgpu::Device,
+        target_format: wgpu::TextureFormat,
+        sample_count: u32,
+        lut_view: &wgpu::TextureView,
+    ) -> Self {
+        let source = [
+            include_str!("../shaders/srgb.wgsl"),
+            include_str!("../shaders/digit_font.wgsl"),
+            include_str!("../shaders/color_bar.wgsl"),
+        ]
+        .concat();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Color Bar Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Color Bar Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Color Bar Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Color Bar Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Color Bar Uniforms"),
+            size: std::mem::size_of::<ColorBarUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Color Bar Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(lut_view),
+                },
+            ],
+        });
+
+        Self {
+            pipeline,
+            uniforms,
+            bind_group,
+            srgb_target: target_format.is_srgb(),
+            visible: false,
+        }
+    }
+
+    // top / bottom 为色带两端对应的原始值 (反相时由调用方交换)
+    pub fn prepare(
+        &self,
+        queue: &wgpu::Queue,
+        viewport: (u32, u32),
+        top: f32,
+        bottom: f32,
+        lut_enabled: bool,
+    ) {
+        if !self.visible {
+            return;
+        }
+        let (vw, vh) = (viewport.0.max(1) as f32, viewport.1.max(1) as f32);
+        let top_text = format_label(top);
+        let bottom_text = format_label(bottom);
+        let label_width = top_text.len().max(bottom_text.len()) as f32 * CHAR_ADVANCE;
+
+        let bar_height = (vh * 0.5).max(40.0);
+        let panel_w = BAR_WIDTH.max(label_width) + PADDING * 2.0;
+        let panel_h = bar_height + LABEL_HEIGHT * 2.0 + PADDING * 4.0;
+        let panel_x = vw - MARGIN - panel_w;
+        let panel_y = (vh - panel_h) * 0.5;
+        let left = panel_x + PADDING;
+        let bar_y = panel_y + PADDING * 2.0 + LABEL_HEIGHT;
+
+        let uniforms = ColorBarUniforms {
+            viewport: [vw, vh],
+            glyph_scale: GLYPH_SCALE,
+            lut_enabled: lut_enabled as u32,
+            panel: [panel_x, panel_y, panel_x + panel_w, panel_y + panel_h],
+            bar: [left, bar_y, left + BAR_WIDTH, bar_y + bar_height],
+            top_label_pos: [left, panel_y + PADDING],
+            bottom_label_pos: [left, bar_y + bar_height + PADDING],
+            srgb_target: self.srgb_target as u32,
+            _pad: [0; 3],
+            top_label: encode_label(&top_text),
+            bottom_label: encode_label(&bottom_text),
+        };
+        queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        if !self.visible {
+            return;
+        }
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..6, 0..1);
+    }
+}
+
+// 图像原始值都是整数，窗口值取整显示即可
+fn format_label(value: f32) -> String {
+    let mut text = format!("{:.0}", value);
+    text.truncate(MAX_LABEL_CHARS);
+    text
+}
+
+// 按 digit_font.wgsl 的编码打包成 16 个字符
+fn encode_label(text: &str) -> [[u32; 4]; 4] {
+    let mut codes = [[15u32; 4]; 4];
+    for (i, ch) in text.chars().take(MAX_LABEL_CHARS).enumerate() {
+        codes[i / 4][i % 4] = match ch {
+            '0'..='9' => ch as u32 - '0' as u32,
+            '-' => 10,
+            '.' => 11,
+            _ => 15,
+        };
+    }
+    codes
+}
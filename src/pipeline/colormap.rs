@@ -0,0 +1,178 @@
+use crate::common::error::{ensure, IrisResult};
+use crate::common::types::{IrisColormap, IrisStatus};
+
+// GPU 上的查找表固定为 4096 项，256 项的自定义表在上传时线性插值展开
+pub const LUT_SIZE: u32 = 4096;
+
+// 伪彩色查找表 (LUT_SIZE x 1 的 RGBA8 纹理；GL 后端对 1D 纹理支持不稳定，统一用 2D)，在窗宽窗位之后按亮度查表
+pub struct Colormap {
+    texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub kind: IrisColormap,
+}
+
+impl Colormap {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Colormap LUT"),
+            size: wgpu::Extent3d {
+                width: LUT_SIZE,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut colormap = Self {
+            texture,
+            view,
+            kind: IrisColormap::Gray,
+        };
+        colormap.set_builtin(queue, IrisColormap::Gray);
+        colormap
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.kind != IrisColormap::Gray
+    }
+
+    pub fn set_builtin(&mut self, queue: &wgpu::Queue, kind: IrisColormap) {
+        if kind == IrisColormap::Custom {
+            return;
+        }
+        let table: Vec<[u8; 4]> = (0..LUT_SIZE)
+            .map(|i| {
+                let t = i as f32 / (LUT_SIZE - 1) as f32;
+                let [r, g, b] = builtin_color(kind, t);
+                [to_u8(r), to_u8(g), to_u8(b), 255]
+            })
+            .collect();
+        self.write(queue, &table);
+        self.kind = kind;
+    }
+
+    // 自定义表：entries 必须为 256 或 4096，每项 RGBA8
+    pub fn set_custom(&mut self, queue: &wgpu::Queue, rgba: &[u8], entries: u32) -> IrisResult {
+        ensure(
+            (entries == 256 || entries == LUT_SIZE) && rgba.len() >= entries as usize * 4,
+            IrisStatus::InvalidArgument,
+            || format!("自定义查找表只支持 256 或 4096 项: {}", entries),
+        )?;
+        let src: Vec<[u8; 4]> = rgba
+            .chunks_exact(4)
+            .take(entries as usize)
+            .map(|c| [c[0], c[1], c[2], c[3]])
+            .collect();
+        let table: Vec<[u8; 4]> = if entries == LUT_SIZE {
+            src
+        } else {
+            (0..LUT_SIZE)
+                .map(|i| {
+                    let pos = i as f32 * (entries - 1) as f32 / (LUT_SIZE - 1) as f32;
+                    let lo = pos.floor() as usize;
+                    let hi = (lo + 1).min(entries as usize - 1);
+                    let f = pos - lo as f32;
+                    let mut out = [0u8; 4];
+                    for c in 0..4 {
+                        let v = src[lo][c] as f32 * (1.0 - f) + src[hi][c] as f32 * f;
+                        out[c] = v.round() as u8;
+                    }
+                    out
+                })
+                .collect()
+        };
+        self.write(queue, &table);
+        self.kind = IrisColormap::Custom;
+        Ok(())
+    }
+
+    fn write(&self, queue: &wgpu::Queue, table: &[[u8; 4]]) {
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(table),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(LUT_SIZE * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: LUT_SIZE,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+fn to_u8(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// 多项式求值：c[0] + t * (c[1] + t * (...))
+fn poly(coeffs: &[[f32; 3]], t: f32) -> [f32; 3] {
+    let mut acc = [0.0f32; 3];
+    for c in coeffs.iter().rev() {
+        for i in 0..3 {
+            acc[i] = acc[i] * t + c[i];
+        }
+    }
+    acc
+}
+
+// Viridis / Inferno 使用 6 阶多项式拟合 (matplotlib 配色)，Turbo 使用 Google 给出的 5 阶近似
+fn builtin_color(kind: IrisColormap, t: f32) -> [f32; 3] {
+    match kind {
+        IrisColormap::Jet => [
+            1.5 - (4.0 * t - 3.0).abs(),
+            1.5 - (4.0 * t - 2.0).abs(),
+            1.5 - (4.0 * t - 1.0).abs(),
+        ],
+        IrisColormap::Hot => [3.0 * t, 3.0 * t - 1.0, 3.0 * t - 2.0],
+        IrisColormap::Turbo => poly(
+            &[
+                [0.135_721_38, 0.091_402_61, 0.106_673_3],
+                [4.615_392_6, 2.194_188_4, 12.641_946],
+                [-42.660_323, 4.842_966_6, -60.582_05],
+                [132.131_08, -14.185_033, 110.362_77],
+                [-152.942_4, 4.277_298_5, -89.903_11],
+                [59.286_38, 2.829_566, 27.348_25],
+            ],
+            t,
+        ),
+        IrisColormap::Viridis => poly(
+            &[
+                [0.277_727_33, 0.005_407_344_5, 0.334_099_8],
+                [0.105_093_04, 1.404_613_5, 1.384_590_1],
+                [-0.330_861_84, 0.214_847_56, 0.095_095_16],
+                [-4.634_230_4, -5.799_101, -19.332_441],
+                [6.228_27, 14.179_933, 56.690_55],
+                [4.776_385, -13.745_145, -65.353_03],
+                [-5.435_456, 4.645_852_6, 26.312_435],
+            ],
+            t,
+        ),
+        IrisColormap::Inferno => poly(
+            &[
+                [0.000_218_940_37, 0.001_651_004_6, -0.019_480_899],
+                [0.106_513_42, 0.563_956_44, 3.932_712_4],
+                [11.602_493, -3.972_854, -15.942_394],
+                [-41.703_996, 17.436_4, 44.354_145],
+                [77.162_94, -33.402_36, -81.807_31],
+                [-71.319_43, 32.626_064, 73.209_52],
+                [25.131_126, -12.242_669, -23.070_325],
+            ],
+            t,
+        ),
+        IrisColormap::Gray | IrisColormap::Custom => [t, t, t],
+    }
+}
@@ -0,0 +1,236 @@
+use crate::hardware::instance::GpuContext;
+use std::sync::{Arc, Mutex};
+
+// 与 shaders/histogram.wgsl 中的 BIN_COUNT 一致
+pub const HISTOGRAM_BINS: usize = 4096;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MapState {
+    Idle,
+    Mapping,
+    Mapped,
+}
+
+// 图像亮度直方图：计算着色器统计，异步映射回 CPU
+// 结果比图像晚一到两帧到达，对自动窗宽窗位来说足够
+pub struct GpuHistogram {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    bins: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    state: Arc<Mutex<MapState>>,
+}
+
+impl GpuHistogram {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Histogram Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/histogram.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Histogram Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Histogram Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Histogram Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let size = (HISTOGRAM_BINS * std::mem::size_of::<u32>()) as u64;
+        let bins = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Bins"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            layout,
+            bins,
+            readback,
+            state: Arc::new(Mutex::new(MapState::Idle)),
+        }
+    }
+
+    // 上一次结果还没取走时直接跳过，相机连续出图时只统计来得及的那些帧
+    pub fn dispatch(&self, ctx: &GpuContext, image: &wgpu::TextureView, width: u32, height: u32) {
+        if *self.state.lock().unwrap() != MapState::Idle {
+            return;
+        }
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Histogram Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(image),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.bins.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Histogram Encoder"),
+            });
+        encoder.clear_buffer(&self.bins, 0, None);
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Histogram Pass"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(&self.pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.bins, 0, &self.readback, 0, self.bins.size());
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        *self.state.lock().unwrap() = MapState::Mapping;
+        let state = self.state.clone();
+        self.readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *state.lock().unwrap() = match result {
+                    Ok(()) => MapState::Mapped,
+                    Err(_) => MapState::Idle,
+                };
+            });
+    }
+
+    // 非阻塞地取回最近一次统计结果
+    pub fn poll(&self, device: &wgpu::Device) -> Option<Vec<u32>> {
+        if *self.state.lock().unwrap() == MapState::Idle {
+            return None;
+        }
+        let _ = device.poll(wgpu::Maintain::Poll);
+        if *self.state.lock().unwrap() != MapState::Mapped {
+            return None;
+        }
+        let bins = {
+            let data = self.readback.slice(..).get_mapped_range();
+            bytemuck::cast_slice::<u8, u32>(&data).to_vec()
+        };
+        self.readback.unmap();
+        *self.state.lock().unwrap() = MapState::Idle;
+        Some(bins)
+    }
+}
+
+// 按百分位裁剪得到归一化的 [low, high]，percent 取值 0~100
+pub fn percentile_range(bins: &[u32], low_percent: f32, high_percent: f32) -> Option<(f32, f32)> {
+    let total: u64 = bins.iter().map(|&c| c as u64).sum();
+    if total == 0 {
+        return None;
+    }
+    let find = |percent: f32| {
+        let target = (total as f64 * (percent.clamp(0.0, 100.0) as f64 / 100.0)).ceil() as u64;
+        let mut acc = 0u64;
+        for (i, &count) in bins.iter().enumerate() {
+            acc += count as u64;
+            if acc >= target.max(1) {
+                return i;
+            }
+        }
+        bins.len() - 1
+    };
+    let low = find(low_percent) as f32 / bins.len() as f32;
+    let high = (find(high_percent) + 1) as f32 / bins.len() as f32;
+    Some((low, high.max(low + 1.0 / bins.len() as f32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::percentile_range;
+
+    const BIN: f32 = 1.0 / 8.0;
+
+    #[test]
+    fn empty_histogram_has_no_range() {
+        assert_eq!(percentile_range(&[0; 8], 0.5, 99.5), None);
+    }
+
+    // 所有像素相同时窗口仍保留一个 bin 的宽度
+    #[test]
+    fn all_equal_pixels() {
+        let bins = [0, 0, 0, 500, 0, 0, 0, 0];
+        assert_eq!(
+            percentile_range(&bins, 0.5, 99.5),
+            Some((3.0 * BIN, 4.0 * BIN))
+        );
+        assert_eq!(
+            percentile_range(&bins, 0.0, 100.0),
+            Some((3.0 * BIN, 4.0 * BIN))
+        );
+    }
+
+    #[test]
+    fn equal_percentiles_keep_one_bin() {
+        let bins = [10; 8];
+        assert_eq!(
+            percentile_range(&bins, 50.0, 50.0),
+            Some((3.0 * BIN, 4.0 * BIN))
+        );
+    }
+
+    // 0 / 100 取第一个和最后一个非空 bin
+    #[test]
+    fn full_range_percentiles() {
+        let bins = [0, 4, 10, 10, 10, 10, 6, 0];
+        assert_eq!(percentile_range(&bins, 0.0, 100.0), Some((BIN, 7.0 * BIN)));
+    }
+
+    #[test]
+    fn clips_tails() {
+        // 100 个像素，两端各 1 个离群值
+        let bins = [1, 0, 49, 0, 0, 49, 0, 1];
+        assert_eq!(percentile_range(&bins, 1.0, 99.0), Some((0.0, 6.0 * BIN)));
+        assert_eq!(
+            percentile_range(&bins, 1.5, 98.5),
+            Some((2.0 * BIN, 6.0 * BIN))
+        );
+    }
+}
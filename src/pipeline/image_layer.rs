@@ -0,0 +1,699 @@
+use crate::common::error::{ensure, IrisResult};
+use crate::common::math::ViewTransform;
+use crate::common::types::{IrisColormap, IrisDemosaicMode, IrisPixelFormat, IrisStatus};
+use crate::hardware::instance::GpuContext;
+use crate::pipeline::color_bar::ColorBar;
+use crate::pipeline::colormap::Colormap;
+use crate::pipeline::histogram::{percentile_range, GpuHistogram};
+
+// 图像层的 GPU 侧常量，布局与 shaders/image.wgsl 中的 ImageUniforms 一致
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ImageUniforms {
+    image_to_clip: [[f32; 4]; 4],
+    image_size: [f32; 2],
+    srgb_target: u32,
+    _pad: u32,
+    // 窗宽窗位已归一化到 0~1
+    window_min: f32,
+    window_max: f32,
+    gamma: f32,
+    invert: u32,
+    lut_enabled: u32,
+    nearest: u32,
+    grid: u32,
+    values: u32,
+    mono: u32,
+    zoom: f32,
+    max_value: f32,
+    _pad1: u32,
+    viewport: [f32; 2],
+    _pad2: [u32; 2],
+}
+
+// 图像层的显示参数，全部在片元着色器里生效，修改时不需要重新上传
+// 窗口值以图像原始单位表示 (例如 Mono12 为 0~4095)，None 表示整个位深范围
+struct DisplaySettings {
+    window: Option<(f32, f32)>,
+    gamma: f32,
+    invert: bool,
+    auto_window: bool,
+    auto_low_percent: f32,
+    auto_high_percent: f32,
+    // 缩放达到 nearest_zoom 后改为最近邻采样，达到 value_zoom 后显示像素值
+    nearest_zoom: f32,
+    value_zoom: f32,
+    pixel_grid: bool,
+    pixel_values: bool,
+}
+
+// 与 shaders/raw_input.wgsl 中的 DecodeParams 一致
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct DecodeParams {
+    width: u32,
+    height: u32,
+    row_words: u32,
+    format: u32,
+    bayer_pattern: u32,
+    bit_depth: u32,
+    demosaic_mode: u32,
+    _pad: u32,
+    wb_gains: [f32; 4],
+}
+
+impl DecodeParams {
+    fn is_bayer(&self) -> bool {
+        self.format >= IrisPixelFormat::BayerRg8 as u32
+    }
+
+    fn is_mono(&self) -> bool {
+        self.format == IrisPixelFormat::Mono8 as u32
+            || (IrisPixelFormat::Mono10 as u32..=IrisPixelFormat::Mono12p as u32)
+                .contains(&self.format)
+    }
+}
+
+// 按图像尺寸分配的资源，同尺寸重复上传时直接复用
+struct ImageTextures {
+    width: u32,
+    height: u32,
+    raw: wgpu::Buffer,
+    view: wgpu::TextureView,
+    decode_bind_group: wgpu::BindGroup,
+    display_bind_group: wgpu::BindGroup,
+}
+
+// 相机图像背景层：原始字节 -> 计算着色器解码 -> rgba32float 纹理 -> 四边形
+pub struct ImageLayer {
+    decode_pipeline: wgpu::ComputePipeline,
+    demosaic_pipeline: wgpu::ComputePipeline,
+    decode_layout: wgpu::BindGroupLayout,
+    display_pipeline: wgpu::RenderPipeline,
+    display_layout: wgpu::BindGroupLayout,
+    decode_params: wgpu::Buffer,
+    uniforms: wgpu::Buffer,
+    textures: Option<ImageTextures>,
+    generation: u64,
+    // 最近一次上传的解码参数，调整去马赛克参数时不必重新上传即可重新解码
+    last_decode: Option<DecodeParams>,
+    demosaic_mode: IrisDemosaicMode,
+    white_balance: [f32; 3],
+    display: DisplaySettings,
+    histogram: GpuHistogram,
+    colormap: Colormap,
+    color_bar: ColorBar,
+    // CPU 侧的行对齐暂存区，避免每帧重新分配
+    staging: Vec<u8>,
+    srgb_target: bool,
+}
+
+impl ImageLayer {
+    pub fn new(ctx: &GpuContext, target_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let device = &ctx.device;
+        // 1. 解码 / 去马赛克管线，共用 raw_input.wgsl 中的输入绑定
+        let decode_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Image Decode Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let decode_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Image Decode Pipeline Layout"),
+                bind_group_layouts: &[&decode_layout],
+                push_constant_ranges: &[],
+            });
+        let decode_pipeline = create_raw_pipeline(
+            device,
+            &decode_pipeline_layout,
+            "Image Decode",
+            include_str!("../shaders/image_decode.wgsl"),
+        );
+        let demosaic_pipeline = create_raw_pipeline(
+            device,
+            &decode_pipeline_layout,
+            "Bayer Demosaic",
+            include_str!("../shaders/demosaic.wgsl"),
+        );
+
+        // 2. 显示管线，像素值叠加复用色带的点阵字体
+        let display_source = [
+            include_str!("../shaders/srgb.wgsl"),
+            include_str!("../shaders/digit_font.wgsl"),
+            include_str!("../shaders/image.wgsl"),
+        ]
+        .concat();
+        let display_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Image Shader"),
+            source: wgpu::ShaderSource::Wgsl(display_source.into()),
+        });
+        let display_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Image Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let display_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Image Pipeline Layout"),
+                bind_group_layouts: &[&display_layout],
+                push_constant_ranges: &[],
+            });
+        let display_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Image Pipeline"),
+            layout: Some(&display_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &display_shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &display_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let decode_params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Image Decode Params"),
+            size: std::mem::size_of::<DecodeParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Image Uniforms"),
+            size: std::mem::size_of::<ImageUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let colormap = Colormap::new(device, &ctx.queue);
+        let color_bar = ColorBar::new(device, target_format, sample_count, &colormap.view);
+
+        Self {
+            decode_pipeline,
+            demosaic_pipeline,
+            decode_layout,
+            display_pipeline,
+            display_layout,
+            decode_params,
+            uniforms,
+            textures: None,
+            generation: 0,
+            last_decode: None,
+            demosaic_mode: IrisDemosaicMode::Bilinear,
+            white_balance: [1.0, 1.0, 1.0],
+            display: DisplaySettings {
+                window: None,
+                gamma: 1.0,
+                invert: false,
+                auto_window: false,
+                auto_low_percent: 0.5,
+                auto_high_percent: 99.5,
+                nearest_zoom: 8.0,
+                value_zoom: 40.0,
+                pixel_grid: false,
+                pixel_values: true,
+            },
+            histogram: GpuHistogram::new(device),
+            colormap,
+            color_bar,
+            staging: Vec::new(),
+            srgb_target: target_format.is_srgb(),
+        }
+    }
+
+    // 当前图像的尺寸，尚未上传时为 None
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.textures.as_ref().map(|t| (t.width, t.height))
+    }
+
+    // 解码后的 rgba32float 纹理 (值已归一化到 0~1)
+    pub fn decoded_view(&self) -> Option<&wgpu::TextureView> {
+        self.textures.as_ref().map(|t| &t.view)
+    }
+
+    pub fn is_mono(&self) -> bool {
+        self.last_decode.is_some_and(|p| p.is_mono())
+    }
+
+    // 每次重新解码都会递增，依赖图像内容的统计据此判断是否需要刷新
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn clear(&mut self) {
+        self.textures = None;
+        self.last_decode = None;
+        self.generation += 1;
+    }
+
+    // 去马赛克参数只影响解码阶段，已有 Bayer 图像时用保留的原始数据重新解码
+    pub fn set_demosaic_mode(&mut self, ctx: &GpuContext, mode: IrisDemosaicMode) {
+        self.demosaic_mode = mode;
+        self.redecode_bayer(ctx);
+    }
+
+    pub fn set_white_balance(&mut self, ctx: &GpuContext, r: f32, g: f32, b: f32) {
+        self.white_balance = [r.max(0.0), g.max(0.0), b.max(0.0)];
+        self.redecode_bayer(ctx);
+    }
+
+    fn redecode_bayer(&mut self, ctx: &GpuContext) {
+        let Some(mut params) = self.last_decode else {
+            return;
+        };
+        if !params.is_bayer() {
+            return;
+        }
+        params.demosaic_mode = self.demosaic_mode as u32;
+        params.wb_gains = self.wb_gains();
+        self.dispatch_decode(ctx, params);
+    }
+
+    // 当前图像位深对应的最大原始值，没有图像时按 8 位处理
+    pub fn max_value(&self) -> f32 {
+        let bits = self.last_decode.map_or(8, |p| p.bit_depth);
+        ((1u32 << bits) - 1) as f32
+    }
+
+    // 手动设置窗口会关闭自动模式
+    pub fn set_window(&mut self, min: f32, max: f32) {
+        self.display.window = Some((min, max));
+        self.display.auto_window = false;
+    }
+
+    // 返回当前生效的窗口 (原始单位)，自动模式下即最近一次统计的结果
+    pub fn window(&self) -> (f32, f32) {
+        self.display.window.unwrap_or((0.0, self.max_value()))
+    }
+
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.display.gamma = if gamma > 0.0 { gamma } else { 1.0 };
+    }
+
+    pub fn set_invert(&mut self, invert: bool) {
+        self.display.invert = invert;
+    }
+
+    pub fn set_auto_window(
+        &mut self,
+        ctx: &GpuContext,
+        enabled: bool,
+        low_percent: f32,
+        high_percent: f32,
+    ) {
+        self.display.auto_window = enabled;
+        if !enabled {
+            return;
+        }
+        self.display.auto_low_percent = low_percent;
+        self.display.auto_high_percent = high_percent;
+        // 已有图像时立即统计一次，不必等下一帧
+        if let Some(textures) = &self.textures {
+            self.histogram
+                .dispatch(ctx, &textures.view, textures.width, textures.height);
+        }
+    }
+
+    // 像素检查的缩放阈值 (屏幕像素 / 图像像素)，value_zoom 不低于 nearest_zoom
+    pub fn set_pixel_zoom_thresholds(&mut self, nearest_zoom: f32, value_zoom: f32) {
+        self.display.nearest_zoom = nearest_zoom.max(1.0);
+        self.display.value_zoom = value_zoom.max(self.display.nearest_zoom);
+    }
+
+    pub fn set_pixel_grid_visible(&mut self, visible: bool) {
+        self.display.pixel_grid = visible;
+    }
+
+    pub fn set_pixel_values_visible(&mut self, visible: bool) {
+        self.display.pixel_values = visible;
+    }
+
+    pub fn set_colormap(&mut self, queue: &wgpu::Queue, kind: IrisColormap) {
+        self.colormap.set_builtin(queue, kind);
+    }
+
+    pub fn set_custom_colormap(
+        &mut self,
+        queue: &wgpu::Queue,
+        rgba: &[u8],
+        entries: u32,
+    ) -> IrisResult {
+        self.colormap.set_custom(queue, rgba, entries)
+    }
+
+    pub fn set_color_bar_visible(&mut self, visible: bool) {
+        self.color_bar.visible = visible;
+    }
+
+    fn wb_gains(&self) -> [f32; 4] {
+        let [r, g, b] = self.white_balance;
+        [r, g, b, 1.0]
+    }
+
+    // 上传一帧原始图像；stride 为源数据的行跨度 (字节)，0 表示紧密排列
+    pub fn upload(
+        &mut self,
+        ctx: &GpuContext,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+        format: IrisPixelFormat,
+    ) -> IrisResult {
+        ensure(width > 0 && height > 0, IrisStatus::InvalidArgument, || {
+            format!("图像尺寸无效: {}x{}", width, height)
+        })?;
+        // 超过显卡上限时 wgpu 会建出无效纹理，之后每一帧都失败，所以上传前就拒绝
+        let max = ctx.device.limits().max_texture_dimension_2d;
+        ensure(
+            width <= max && height <= max,
+            IrisStatus::InvalidArgument,
+            || format!("图像尺寸 {}x{} 超过显卡上限 {}", width, height, max),
+        )?;
+        let row_bytes = format.row_bytes(width) as usize;
+        let stride = if stride == 0 {
+            row_bytes
+        } else {
+            stride as usize
+        };
+        ensure(
+            stride >= row_bytes && data.len() >= stride * (height as usize - 1) + row_bytes,
+            IrisStatus::InvalidArgument,
+            || format!("图像数据长度不足: {} 字节", data.len()),
+        )?;
+
+        // 1. 每行补齐到 4 字节，方便着色器按 u32 读取
+        let row_words = row_bytes.div_ceil(4);
+        let padded_row = row_words * 4;
+        self.staging.clear();
+        self.staging.resize(padded_row * height as usize, 0);
+        for y in 0..height as usize {
+            self.staging[y * padded_row..][..row_bytes]
+                .copy_from_slice(&data[y * stride..][..row_bytes]);
+        }
+
+        // 2. 尺寸或原始缓冲区容量变化时才重新分配
+        let (device, queue) = (&ctx.device, &ctx.queue);
+        self.ensure_textures(device, width, height, self.staging.len() as u64);
+        let textures = self.textures.as_ref().unwrap();
+        queue.write_buffer(&textures.raw, 0, &self.staging);
+
+        // 3. 计算着色器解码 (Bayer 格式走去马赛克)
+        let params = DecodeParams {
+            width,
+            height,
+            row_words: row_words as u32,
+            format: format as u32,
+            bayer_pattern: format.bayer_pattern().unwrap_or(0),
+            bit_depth: format.bit_depth(),
+            demosaic_mode: self.demosaic_mode as u32,
+            _pad: 0,
+            wb_gains: self.wb_gains(),
+        };
+        self.dispatch_decode(ctx, params);
+        if self.display.auto_window {
+            let textures = self.textures.as_ref().unwrap();
+            self.histogram
+                .dispatch(ctx, &textures.view, textures.width, textures.height);
+        }
+        Ok(())
+    }
+
+    fn dispatch_decode(&mut self, ctx: &GpuContext, params: DecodeParams) {
+        let Some(textures) = &self.textures else {
+            return;
+        };
+        let (device, queue) = (&ctx.device, &ctx.queue);
+        queue.write_buffer(&self.decode_params, 0, bytemuck::bytes_of(&params));
+        let pipeline = if params.is_bayer() {
+            &self.demosaic_pipeline
+        } else {
+            &self.decode_pipeline
+        };
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Image Decode Encoder"),
+        });
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Image Decode Pass"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(pipeline);
+            cpass.set_bind_group(0, &textures.decode_bind_group, &[]);
+            cpass.dispatch_workgroups(params.width.div_ceil(8), params.height.div_ceil(8), 1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        self.last_decode = Some(params);
+        self.generation += 1;
+    }
+
+    fn ensure_textures(&mut self, device: &wgpu::Device, width: u32, height: u32, raw_size: u64) {
+        if let Some(textures) = &self.textures {
+            if textures.width == width
+                && textures.height == height
+                && textures.raw.size() >= raw_size
+            {
+                return;
+            }
+        }
+
+        let raw = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Image Raw Buffer"),
+            size: raw_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Image Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let decode_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Image Decode Bind Group"),
+            layout: &self.decode_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.decode_params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: raw.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+            ],
+        });
+        let display_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Image Display Bind Group"),
+            layout: &self.display_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&self.colormap.view),
+                },
+            ],
+        });
+
+        self.textures = Some(ImageTextures {
+            width,
+            height,
+            raw,
+            view,
+            decode_bind_group,
+            display_bind_group,
+        });
+    }
+
+    // 每帧绘制前更新变换和显示参数
+    pub fn prepare(&mut self, ctx: &GpuContext, view: &ViewTransform) {
+        if self.textures.is_none() {
+            return;
+        }
+        // 自动模式下取回直方图并按百分位更新窗口
+        if self.display.auto_window {
+            if let Some(bins) = self.histogram.poll(&ctx.device) {
+                let d = &self.display;
+                if let Some((low, high)) =
+                    percentile_range(&bins, d.auto_low_percent, d.auto_high_percent)
+                {
+                    let max_value = self.max_value();
+                    self.display.window = Some((low * max_value, high * max_value));
+                }
+            }
+        }
+        let textures = self.textures.as_ref().unwrap();
+        let (iw, ih) = (textures.width as f32, textures.height as f32);
+        let viewport = view.viewport_size();
+        let max_value = self.max_value();
+        let (window_min, window_max) = self.window();
+        let zoom = view.zoom() as f32;
+        let nearest = zoom >= self.display.nearest_zoom;
+        let uniforms = ImageUniforms {
+            image_to_clip: view.image_to_clip().to_cols_array_2d(),
+            image_size: [iw, ih],
+            srgb_target: self.srgb_target as u32,
+            _pad: 0,
+            window_min: window_min / max_value,
+            window_max: window_max / max_value,
+            gamma: self.display.gamma,
+            invert: self.display.invert as u32,
+            lut_enabled: self.colormap.enabled() as u32,
+            nearest: nearest as u32,
+            grid: (nearest && self.display.pixel_grid) as u32,
+            values: (self.display.pixel_values && zoom >= self.display.value_zoom) as u32,
+            mono: self.last_decode.is_some_and(|p| p.is_mono()) as u32,
+            zoom,
+            max_value,
+            _pad1: 0,
+            viewport: [viewport.0 as f32, viewport.1 as f32],
+            _pad2: [0; 2],
+        };
+        ctx.queue
+            .write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+
+        // 反相时色带上端对应窗口下限
+        let (top, bottom) = if self.display.invert {
+            (window_min, window_max)
+        } else {
+            (window_max, window_min)
+        };
+        self.color_bar
+            .prepare(&ctx.queue, viewport, top, bottom, self.colormap.enabled());
+    }
+
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        let Some(textures) = &self.textures else {
+            return;
+        };
+        rpass.set_pipeline(&self.display_pipeline);
+        rpass.set_bind_group(0, &textures.display_bind_group, &[]);
+        rpass.draw(0..6, 0..1);
+    }
+
+    // 色带属于 HUD，需要在所有叠加层之后绘制
+    pub fn draw_color_bar(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        if self.textures.is_none() {
+            return;
+        }
+        self.color_bar.draw(rpass);
+    }
+}
+
+fn create_raw_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    label: &str,
+    body: &str,
+) -> wgpu::ComputePipeline {
+    let source = [include_str!("../shaders/raw_input.wgsl"), body].concat();
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        module: &shader,
+        entry_point: Some("cs_main"),
+        compilation_options: Default::default(),
+        cache: None,
+    })
+}
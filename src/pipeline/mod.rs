@@ -1 +1,8 @@
-
+pub mod color_bar;
+pub mod colormap;
+pub mod histogram;
+pub mod image_layer;
+pub mod roi_2d_shader;
+pub mod roi_mask;
+pub mod roi_stats;
+pub mod text;
@@ -1 +1,390 @@
+use crate::common::math::ViewTransform;
+use crate::common::types::IrisLayer;
+use crate::hardware::instance::GpuContext;
+use crate::scene::interaction::{handles, HANDLE_SIZE};
+use crate::scene::manager::SceneManager;
+use crate::scene::roi::{Roi, RoiShape};
+use glam::DVec2;
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, LineJoin, StrokeOptions,
+    StrokeTessellator, StrokeVertex, VertexBuffers,
+};
 
+// 与 shaders/roi.wgsl 中的 RoiUniforms 一致
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct RoiUniforms {
+    image_to_clip: [[f32; 4]; 4],
+    zoom: f32,
+    srgb_target: u32,
+    _pad: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RoiVertex {
+    position: [f32; 2],
+    normal: [f32; 2],
+    stroke_width: f32,
+    color: [f32; 4],
+}
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+    wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32, 3 => Float32x4];
+
+// 曲线展平精度 (屏幕像素)；缩放变化超过一倍时按新的精度重新细分
+const SCREEN_TOLERANCE: f32 = 0.25;
+// 距离测量两端刻度线的半长 (屏幕像素)
+const TICK_HALF_LENGTH: f64 = 6.0;
+
+// 把一个 ROI 细分成三角形追加到 buffers：先填充后描边，描边压在填充上面
+// tolerance 为图像像素单位的展平精度，opacity 乘到填充和描边的 alpha 上
+pub fn tessellate_roi(
+    roi: &Roi,
+    tolerance: f32,
+    opacity: f32,
+    buffers: &mut VertexBuffers<RoiVertex, u32>,
+) {
+    let path = roi.shape.build_path();
+    let mut style = roi.style;
+    style.fill_color[3] *= opacity;
+    style.stroke_color[3] *= opacity;
+
+    // 测量形状是不闭合的线，只描边
+    if style.fill_color[3] > 0.0 && !roi.shape.is_measurement() {
+        let options = FillOptions::tolerance(tolerance).with_fill_rule(roi.shape.fill_rule());
+        let result = FillTessellator::new().tessellate_path(
+            &path,
+            &options,
+            &mut BuffersBuilder::new(buffers, |v: FillVertex| RoiVertex {
+                position: v.position().to_array(),
+                normal: [0.0, 0.0],
+                stroke_width: 0.0,
+                color: style.fill_color,
+            }),
+        );
+        if let Err(e) = result {
+            log::warn!("ROI 填充细分失败: {:?}", e);
+        }
+    }
+
+    if style.stroke_width > 0.0 && style.stroke_color[3] > 0.0 {
+        // 线宽固定为 1，真实宽度由着色器沿法线按屏幕像素外扩
+        let options = StrokeOptions::tolerance(tolerance)
+            .with_line_width(1.0)
+            .with_line_join(LineJoin::Miter);
+        let result = StrokeTessellator::new().tessellate_path(
+            &path,
+            &options,
+            &mut BuffersBuilder::new(buffers, |v: StrokeVertex| RoiVertex {
+                position: v.position_on_path().to_array(),
+                normal: v.normal().to_array(),
+                stroke_width: style.stroke_width,
+                color: style.stroke_color,
+            }),
+        );
+        if let Err(e) = result {
+            log::warn!("ROI 描边细分失败: {:?}", e);
+        }
+        if let RoiShape::Distance { ref points } = roi.shape {
+            push_ticks(points, style.stroke_width, style.stroke_color, buffers);
+        }
+    }
+}
+
+// 距离测量两端垂直于测量线的刻度，长度和线宽都按屏幕像素固定，做法同 push_handles
+fn push_ticks(
+    points: &[[f64; 2]],
+    stroke_width: f32,
+    color: [f32; 4],
+    buffers: &mut VertexBuffers<RoiVertex, u32>,
+) {
+    let (a, b) = (DVec2::from(points[0]), DVec2::from(points[1]));
+    let along = (b - a).normalize_or_zero();
+    let across = along.perp() * TICK_HALF_LENGTH;
+    let along = along * (stroke_width as f64 * 0.5);
+    for anchor in [a, b] {
+        let base = buffers.vertices.len() as u32;
+        for normal in [
+            -across - along,
+            across - along,
+            across + along,
+            -across + along,
+        ] {
+            buffers.vertices.push(RoiVertex {
+                position: anchor.as_vec2().to_array(),
+                normal: normal.as_vec2().to_array(),
+                stroke_width: 2.0,
+                color,
+            });
+        }
+        buffers
+            .indices
+            .extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+// 选中 ROI 的编辑手柄：深色边框的白色方块，大小固定为 HANDLE_SIZE 屏幕像素
+// 顶点都放在手柄锚点上，方块的形状写进法线，stroke_width 取 2 使法线即为屏幕像素偏移
+fn push_handles(roi: &Roi, buffers: &mut VertexBuffers<RoiVertex, u32>) {
+    for handle in handles(&roi.shape) {
+        for (half, color) in [
+            (HANDLE_SIZE * 0.5 + 1.0, [0.1, 0.1, 0.1, 1.0]),
+            (HANDLE_SIZE * 0.5, [1.0, 1.0, 1.0, 1.0]),
+        ] {
+            let base = buffers.vertices.len() as u32;
+            for corner in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let normal = handle.offset + DVec2::new(corner.0, corner.1) * half;
+                buffers.vertices.push(RoiVertex {
+                    position: handle.anchor.as_vec2().to_array(),
+                    normal: normal.as_vec2().to_array(),
+                    stroke_width: 2.0,
+                    color,
+                });
+            }
+            buffers.indices.extend_from_slice(&[
+                base,
+                base + 1,
+                base + 2,
+                base,
+                base + 2,
+                base + 3,
+            ]);
+        }
+    }
+}
+
+// ROI 渲染器：场景版本或缩放级别变化时整体重新细分，其余帧只更新变换
+// 图像空间的对象 (ROI、注释图层) 与 HUD 对象共用顶点缓冲，按索引区间分两次绘制，
+// HUD 对象使用单独的一组屏幕空间 uniform
+pub struct RoiRenderer {
+    pipeline: wgpu::RenderPipeline,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    hud_uniforms: wgpu::Buffer,
+    hud_bind_group: wgpu::BindGroup,
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    // 前 scene_index_count 个索引属于图像空间，其余属于 HUD
+    scene_index_count: u32,
+    index_count: u32,
+    // (场景版本, log2 缩放级别)，与上次细分时相同则跳过
+    tessellated: Option<(u64, i32)>,
+    srgb_target: bool,
+}
+
+impl RoiRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        target_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let source = [
+            include_str!("../shaders/srgb.wgsl"),
+            include_str!("../shaders/roi.wgsl"),
+        ]
+        .concat();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("ROI Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("ROI Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("ROI Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("ROI Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<RoiVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &VERTEX_ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let create_uniforms = |label: &str| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: std::mem::size_of::<RoiUniforms>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            (buffer, bind_group)
+        };
+        let (uniforms, bind_group) = create_uniforms("ROI Uniforms");
+        let (hud_uniforms, hud_bind_group) = create_uniforms("HUD Uniforms");
+
+        Self {
+            pipeline,
+            uniforms,
+            bind_group,
+            hud_uniforms,
+            hud_bind_group,
+            vertex_buffer: None,
+            index_buffer: None,
+            scene_index_count: 0,
+            index_count: 0,
+            tessellated: None,
+            srgb_target: target_format.is_srgb(),
+        }
+    }
+
+    pub fn prepare(&mut self, ctx: &GpuContext, scene: &SceneManager, view: &ViewTransform) {
+        let zoom = view.zoom() as f32;
+        let uniforms = RoiUniforms {
+            image_to_clip: view.image_to_clip().to_cols_array_2d(),
+            zoom,
+            srgb_target: self.srgb_target as u32,
+            _pad: [0; 2],
+        };
+        ctx.queue
+            .write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+        // HUD 对象的坐标就是屏幕像素，缩放恒为 1
+        let hud_uniforms = RoiUniforms {
+            image_to_clip: view.screen_to_clip().to_cols_array_2d(),
+            zoom: 1.0,
+            ..uniforms
+        };
+        ctx.queue
+            .write_buffer(&self.hud_uniforms, 0, bytemuck::bytes_of(&hud_uniforms));
+
+        let zoom_level = zoom.log2().floor() as i32;
+        let key = (scene.revision(), zoom_level);
+        if self.tessellated == Some(key) {
+            return;
+        }
+        self.tessellated = Some(key);
+
+        // 按该级别的最大缩放换算展平精度，保证屏幕上的误差不超过 SCREEN_TOLERANCE
+        let tolerance = (SCREEN_TOLERANCE / 2f32.powi(zoom_level + 1)).clamp(1e-3, 10.0);
+        let mut buffers = VertexBuffers::new();
+        for layer in [IrisLayer::Roi, IrisLayer::Annotation] {
+            for object in scene.visible_objects(layer) {
+                tessellate_roi(&object.roi, tolerance, object.opacity, &mut buffers);
+            }
+        }
+        if let Some(roi) = scene.selected().and_then(|id| scene.roi(id)) {
+            push_handles(roi, &mut buffers);
+        }
+        self.scene_index_count = buffers.indices.len() as u32;
+        for object in scene.visible_objects(IrisLayer::Hud) {
+            tessellate_roi(&object.roi, SCREEN_TOLERANCE, object.opacity, &mut buffers);
+        }
+        self.index_count = buffers.indices.len() as u32;
+        if self.index_count == 0 {
+            return;
+        }
+        write_growable(
+            ctx,
+            &mut self.vertex_buffer,
+            "ROI Vertices",
+            wgpu::BufferUsages::VERTEX,
+            bytemuck::cast_slice(&buffers.vertices),
+        );
+        write_growable(
+            ctx,
+            &mut self.index_buffer,
+            "ROI Indices",
+            wgpu::BufferUsages::INDEX,
+            bytemuck::cast_slice(&buffers.indices),
+        );
+    }
+
+    // 绘制 ROI 和注释图层 (图像空间)
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        self.draw_range(rpass, &self.bind_group, 0..self.scene_index_count);
+    }
+
+    // 绘制 HUD 图层 (屏幕空间)
+    pub fn draw_hud(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        self.draw_range(
+            rpass,
+            &self.hud_bind_group,
+            self.scene_index_count..self.index_count,
+        );
+    }
+
+    fn draw_range(
+        &self,
+        rpass: &mut wgpu::RenderPass<'_>,
+        bind_group: &wgpu::BindGroup,
+        range: std::ops::Range<u32>,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let (Some(vertices), Some(indices)) = (&self.vertex_buffer, &self.index_buffer) else {
+            return;
+        };
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.set_vertex_buffer(0, vertices.slice(..));
+        rpass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+        rpass.draw_indexed(range, 0, 0..1);
+    }
+}
+
+// 缓冲区不够大时按 2 倍重新分配，够大则直接覆盖写入
+pub fn write_growable(
+    ctx: &GpuContext,
+    buffer: &mut Option<wgpu::Buffer>,
+    label: &str,
+    usage: wgpu::BufferUsages,
+    data: &[u8],
+) {
+    let size = data.len() as u64;
+    if buffer.as_ref().is_none_or(|b| b.size() < size) {
+        *buffer = Some(ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size.next_power_of_two().max(256),
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+    }
+    ctx.queue.write_buffer(buffer.as_ref().unwrap(), 0, data);
+}
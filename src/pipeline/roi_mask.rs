@@ -0,0 +1,326 @@
+use crate::common::error::{ensure, IrisError, IrisResult};
+use crate::common::types::IrisStatus;
+use crate::hardware::instance::GpuContext;
+use crate::hardware::target::OffscreenTarget;
+use crate::scene::roi::RoiShape;
+use lyon::tessellation::{BuffersBuilder, FillOptions, FillTessellator, FillVertex, VertexBuffers};
+use wgpu::util::DeviceExt;
+
+// 与 shaders/roi_mask.wgsl 中的 MaskUniforms 一致
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct MaskUniforms {
+    image_size: [f32; 2],
+    _pad: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct MaskVertex {
+    position: [f32; 2],
+    label: u32,
+}
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+    wgpu::vertex_attr_array![0 => Float32x2, 1 => Uint32];
+
+// 掩膜按图像像素展平曲线，误差远小于半个像素
+const MASK_TOLERANCE: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaskKind {
+    // R8Unorm：ROI 并集内部为 255，外部为 0
+    Binary,
+    // R16Uint：每个像素为覆盖它的 ROI 的标签，重叠处后画的覆盖先画的，背景为 0
+    Label,
+}
+
+impl MaskKind {
+    fn format(self) -> wgpu::TextureFormat {
+        match self {
+            MaskKind::Binary => wgpu::TextureFormat::R8Unorm,
+            MaskKind::Label => wgpu::TextureFormat::R16Uint,
+        }
+    }
+
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            MaskKind::Binary => 1,
+            MaskKind::Label => 2,
+        }
+    }
+}
+
+// 把 ROI 光栅化成与图像同尺寸的掩膜：复用 lyon 的填充细分，画进离屏纹理，
+// 按像素中心判定内外，与 RoiShape::contains 的填充规则一致
+pub struct RoiMaskRenderer {
+    mask_pipeline: wgpu::RenderPipeline,
+    label_pipeline: wgpu::RenderPipeline,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // 按种类缓存的离屏目标，尺寸不变时复用
+    mask: Option<OffscreenTarget>,
+    labels: Option<OffscreenTarget>,
+}
+
+impl RoiMaskRenderer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("ROI Mask Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/roi_mask.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("ROI Mask Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("ROI Mask Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |kind: MaskKind, entry_point: &str| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("ROI Mask Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<MaskVertex>() as u64,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &VERTEX_ATTRIBUTES,
+                    }],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: kind.format(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let mask_pipeline = create_pipeline(MaskKind::Binary, "fs_mask");
+        let label_pipeline = create_pipeline(MaskKind::Label, "fs_label");
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ROI Mask Uniforms"),
+            size: std::mem::size_of::<MaskUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ROI Mask Bind Group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            mask_pipeline,
+            label_pipeline,
+            uniforms,
+            bind_group,
+            mask: None,
+            labels: None,
+        }
+    }
+
+    // 按顺序把 (形状, 标签) 画进对应种类的离屏纹理并提交，返回该纹理
+    // 二值掩膜忽略标签，画出的是所有形状的并集
+    pub fn render(
+        &mut self,
+        ctx: &GpuContext,
+        shapes: &[(&RoiShape, u16)],
+        width: u32,
+        height: u32,
+        kind: MaskKind,
+    ) -> &OffscreenTarget {
+        let target = match kind {
+            MaskKind::Binary => &mut self.mask,
+            MaskKind::Label => &mut self.labels,
+        };
+        if target
+            .as_ref()
+            .is_none_or(|t| t.texture.width() != width || t.texture.height() != height)
+        {
+            *target = Some(OffscreenTarget::new(
+                &ctx.device,
+                width,
+                height,
+                kind.format(),
+            ));
+        }
+        let target = target.as_ref().unwrap();
+
+        let uniforms = MaskUniforms {
+            image_size: [width as f32, height as f32],
+            _pad: [0; 2],
+        };
+        ctx.queue
+            .write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut buffers: VertexBuffers<MaskVertex, u32> = VertexBuffers::new();
+        let mut tessellator = FillTessellator::new();
+        for &(shape, label) in shapes {
+            let options = FillOptions::tolerance(MASK_TOLERANCE).with_fill_rule(shape.fill_rule());
+            let result = tessellator.tessellate_path(
+                &shape.build_path(),
+                &options,
+                &mut BuffersBuilder::new(&mut buffers, |v: FillVertex| MaskVertex {
+                    position: v.position().to_array(),
+                    label: label as u32,
+                }),
+            );
+            if let Err(e) = result {
+                log::warn!("ROI 掩膜细分失败: {:?}", e);
+            }
+        }
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("ROI Mask Encoder"),
+            });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("ROI Mask Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            if !buffers.indices.is_empty() {
+                let vertices = ctx
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("ROI Mask Vertices"),
+                        contents: bytemuck::cast_slice(&buffers.vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    });
+                let indices = ctx
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("ROI Mask Indices"),
+                        contents: bytemuck::cast_slice(&buffers.indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    });
+                rpass.set_pipeline(match kind {
+                    MaskKind::Binary => &self.mask_pipeline,
+                    MaskKind::Label => &self.label_pipeline,
+                });
+                rpass.set_bind_group(0, &self.bind_group, &[]);
+                rpass.set_vertex_buffer(0, vertices.slice(..));
+                rpass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+                rpass.draw_indexed(0..buffers.indices.len() as u32, 0, 0..1);
+            }
+        }
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+        target
+    }
+
+    // 阻塞地把最近一次 render 的结果拷回 dst，dst_stride 为 0 时按紧密排列
+    // 标签图每像素 2 字节，主机字节序
+    pub fn read(
+        &self,
+        ctx: &GpuContext,
+        kind: MaskKind,
+        dst: &mut [u8],
+        dst_stride: u32,
+    ) -> IrisResult {
+        let target = match kind {
+            MaskKind::Binary => &self.mask,
+            MaskKind::Label => &self.labels,
+        };
+        let Some(target) = target else {
+            return Err(IrisError::new(IrisStatus::InvalidState, "还没有渲染过掩膜"));
+        };
+        let (width, height) = (target.texture.width(), target.texture.height());
+        let row_bytes = width * kind.bytes_per_pixel();
+        let dst_stride = if dst_stride == 0 {
+            row_bytes as usize
+        } else {
+            dst_stride as usize
+        };
+        ensure(
+            dst_stride >= row_bytes as usize
+                && dst.len() >= dst_stride * (height as usize - 1) + row_bytes as usize,
+            IrisStatus::BufferTooSmall,
+            || format!("掩膜缓冲区太小: {} 字节", dst.len()),
+        )?;
+
+        let padded_bytes_per_row = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ROI Mask Readback"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("ROI Mask Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            target.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            target.texture.size(),
+        );
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = ctx.device.poll(wgpu::Maintain::Wait);
+        ensure(
+            matches!(receiver.recv(), Ok(Ok(()))),
+            IrisStatus::GpuError,
+            || "掩膜回读映射失败".to_owned(),
+        )?;
+        {
+            let data = slice.get_mapped_range();
+            for y in 0..height as usize {
+                let src_row = &data[y * padded_bytes_per_row as usize..][..row_bytes as usize];
+                dst[y * dst_stride..][..row_bytes as usize].copy_from_slice(src_row);
+            }
+        }
+        buffer.unmap();
+        Ok(())
+    }
+}
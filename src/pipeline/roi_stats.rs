@@ -0,0 +1,343 @@
+use crate::common::types::{IrisRoiStats, IRIS_STATS_BINS};
+use crate::hardware::instance::GpuContext;
+use std::sync::{Arc, Mutex};
+
+// 与 shaders/roi_stats.wgsl 中的 StatsParams 一致
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct StatsParams {
+    origin: [u32; 2],
+    size: [u32; 2],
+    mono: u32,
+    max_value: f32,
+    _pad: [u32; 2],
+}
+
+// 与 shaders/roi_stats.wgsl 中的 Partial 一致，每个工作组一份
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Partial {
+    count: u32,
+    sum: f32,
+    m2: f32,
+    min: f32,
+    max: f32,
+    _pad: [u32; 3],
+}
+
+const WORKGROUP_EDGE: u32 = 16;
+const BINS_SIZE: u64 = (IRIS_STATS_BINS * std::mem::size_of::<u32>()) as u64;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MapState {
+    Idle,
+    Mapping,
+    Mapped,
+    Failed,
+}
+
+// 一次统计的输入：解码后的图像、ROI 掩膜和 ROI 的外接矩形 (像素，已裁剪到图像内)
+pub struct StatsInput<'a> {
+    pub image: &'a wgpu::TextureView,
+    pub mask: &'a wgpu::TextureView,
+    pub origin: [u32; 2],
+    pub size: [u32; 2],
+    pub mono: bool,
+    pub max_value: f32,
+}
+
+// ROI 统计：计算着色器按掩膜做工作组归约，结果异步映射回 CPU
+// 同一时刻只有一次统计在途，拖动 ROI 时来不及的请求由调用方在下一帧重试
+pub struct RoiStatsComputer {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    params: wgpu::Buffer,
+    bins: wgpu::Buffer,
+    partials: Option<wgpu::Buffer>,
+    readback: Option<wgpu::Buffer>,
+    // 在途统计的工作组数和映射的字节数
+    partial_count: u32,
+    mapped_size: u64,
+    state: Arc<Mutex<MapState>>,
+}
+
+impl RoiStatsComputer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("ROI Stats Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/roi_stats.wgsl").into()),
+        });
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let buffer_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = wgpu::BufferBindingType::Storage { read_only: false };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("ROI Stats Layout"),
+            entries: &[
+                buffer_entry(0, wgpu::BufferBindingType::Uniform),
+                texture_entry(1),
+                texture_entry(2),
+                buffer_entry(3, storage),
+                buffer_entry(4, storage),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("ROI Stats Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("ROI Stats Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ROI Stats Params"),
+            size: std::mem::size_of::<StatsParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bins = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ROI Stats Bins"),
+            size: BINS_SIZE,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            layout,
+            params,
+            bins,
+            partials: None,
+            readback: None,
+            partial_count: 0,
+            mapped_size: 0,
+            state: Arc::new(Mutex::new(MapState::Idle)),
+        }
+    }
+
+    fn state(&self) -> MapState {
+        *self.state.lock().unwrap()
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.state() != MapState::Idle
+    }
+
+    // 提交一次统计，上一次的结果还没取走时返回 false
+    pub fn dispatch(&mut self, ctx: &GpuContext, input: &StatsInput) -> bool {
+        if self.is_busy() {
+            return false;
+        }
+        let groups = [
+            input.size[0].div_ceil(WORKGROUP_EDGE).max(1),
+            input.size[1].div_ceil(WORKGROUP_EDGE).max(1),
+        ];
+        self.partial_count = groups[0] * groups[1];
+        let partials_size = self.partial_count as u64 * std::mem::size_of::<Partial>() as u64;
+        if self
+            .partials
+            .as_ref()
+            .is_none_or(|b| b.size() < partials_size)
+        {
+            let size = partials_size.next_power_of_two();
+            self.partials = Some(ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("ROI Stats Partials"),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }));
+            // 回读缓冲区前面放直方图，后面放各工作组的部分结果
+            self.readback = Some(ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("ROI Stats Readback"),
+                size: BINS_SIZE + size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }));
+        }
+        let (partials, readback) = (
+            self.partials.as_ref().unwrap(),
+            self.readback.as_ref().unwrap(),
+        );
+
+        let params = StatsParams {
+            origin: input.origin,
+            size: input.size,
+            mono: input.mono as u32,
+            max_value: input.max_value,
+            _pad: [0; 2],
+        };
+        ctx.queue
+            .write_buffer(&self.params, 0, bytemuck::bytes_of(&params));
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ROI Stats Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(input.image),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(input.mask),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: partials.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: self.bins.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("ROI Stats Encoder"),
+            });
+        encoder.clear_buffer(&self.bins, 0, None);
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("ROI Stats Pass"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(&self.pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.dispatch_workgroups(groups[0], groups[1], 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.bins, 0, readback, 0, BINS_SIZE);
+        encoder.copy_buffer_to_buffer(partials, 0, readback, BINS_SIZE, partials_size);
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        *self.state.lock().unwrap() = MapState::Mapping;
+        self.mapped_size = BINS_SIZE + partials_size;
+        let state = self.state.clone();
+        readback
+            .slice(..self.mapped_size)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *state.lock().unwrap() = match result {
+                    Ok(()) => MapState::Mapped,
+                    Err(_) => MapState::Failed,
+                };
+            });
+        true
+    }
+
+    // 取回在途统计的结果；wait 为 true 时阻塞到 GPU 完成
+    pub fn poll(&mut self, device: &wgpu::Device, wait: bool) -> Option<IrisRoiStats> {
+        if !self.is_busy() {
+            return None;
+        }
+        let maintain = if wait {
+            wgpu::Maintain::Wait
+        } else {
+            wgpu::Maintain::Poll
+        };
+        let _ = device.poll(maintain);
+        match self.state() {
+            MapState::Mapped => {}
+            MapState::Failed => {
+                *self.state.lock().unwrap() = MapState::Idle;
+                return None;
+            }
+            _ => return None,
+        }
+
+        let readback = self.readback.as_ref().unwrap();
+        let stats = {
+            let data = readback.slice(..self.mapped_size).get_mapped_range();
+            let (bins, partials) = data.split_at(BINS_SIZE as usize);
+            let partials: &[Partial] = bytemuck::cast_slice(partials);
+            let mut stats = merge(partials);
+            stats.histogram.copy_from_slice(bytemuck::cast_slice(bins));
+            stats
+        };
+        readback.unmap();
+        *self.state.lock().unwrap() = MapState::Idle;
+        Some(stats)
+    }
+}
+
+// 按 Chan 等人的并行方差公式合并各工作组的 (n, 均值, M2)
+fn merge(partials: &[Partial]) -> IrisRoiStats {
+    let mut stats = IrisRoiStats::default();
+    let (mut n, mut mean, mut m2) = (0.0f64, 0.0f64, 0.0f64);
+    let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+    for p in partials.iter().filter(|p| p.count > 0) {
+        let nb = p.count as f64;
+        let mean_b = p.sum as f64 / nb;
+        let total = n + nb;
+        let delta = mean_b - mean;
+        mean += delta * nb / total;
+        m2 += p.m2 as f64 + delta * delta * n * nb / total;
+        n = total;
+        min = min.min(p.min as f64);
+        max = max.max(p.max as f64);
+    }
+    if n > 0.0 {
+        stats.count = n as u64;
+        stats.mean = mean;
+        stats.stddev = (m2 / n).sqrt();
+        stats.min = min;
+        stats.max = max;
+    }
+    stats
+}
+
+// 实时统计：每帧检查跟踪的 ROI 或图像是否变过，变了且没有统计在途时重新提交
+// key 为 (场景版本, 图像代数)，拖动 ROI 时场景版本每次移动都会递增
+#[derive(Default)]
+pub struct LiveStats {
+    // 跟踪的 ROI (或分组) ID，0 表示关闭
+    pub id: u64,
+    pub key: Option<(u64, u64)>,
+    // 在途统计属于哪个 ID，阻塞统计插队时据此区分
+    pub in_flight: Option<u64>,
+    // 尚未被取走的最新结果
+    pub latest: Option<IrisRoiStats>,
+}
+
+impl LiveStats {
+    pub fn set_id(&mut self, id: u64) {
+        self.id = id;
+        self.key = None;
+        self.latest = None;
+    }
+
+    // 收下一次在途统计的结果，跟踪的 ID 已经换过时丢弃
+    pub fn deliver(&mut self, stats: IrisRoiStats) {
+        if self.in_flight.take() == Some(self.id) && self.id != 0 {
+            self.latest = Some(stats);
+        }
+    }
+}
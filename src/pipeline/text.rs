@@ -0,0 +1,599 @@
+use crate::common::error::{IrisError, IrisResult};
+use crate::common::math::ViewTransform;
+use crate::common::types::{IrisStatus, IrisTextSpace};
+use crate::hardware::instance::GpuContext;
+use crate::pipeline::roi_2d_shader::write_growable;
+use crate::scene::text::TextItem;
+use ab_glyph::{Font, FontVec, GlyphId, PxScale, ScaleFont};
+use glam::Vec2;
+use std::collections::{HashMap, HashSet};
+
+// 与 shaders/text.wgsl 中的 TextUniforms 一致
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TextUniforms {
+    screen_to_clip: [[f32; 4]; 4],
+    srgb_target: u32,
+    _pad: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TextVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+    wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+
+const ATLAS_SIZE: u32 = 1024;
+// 图集左上角留一块全白区域，背景框采样它得到实心颜色
+const SOLID_SIZE: u32 = 4;
+// 光栅化字号的上限 (屏幕像素)，图像空间的文字放得再大也按它光栅化后拉伸
+const MAX_RASTER_PX: f32 = 128.0;
+// 超过这个字号后按 RASTER_STEP 取整光栅化，缩放时不会每个整数字号都光栅化一遍
+const EXACT_RASTER_PX: f32 = 32.0;
+const RASTER_STEP: f32 = 8.0;
+
+// 图集中的一个字形：位图在图集里的位置，以及相对笔位置 (基线) 的偏移
+#[derive(Clone, Copy)]
+struct AtlasGlyph {
+    origin: [u32; 2],
+    size: [u32; 2],
+    offset: Vec2,
+}
+
+type GlyphKey = (usize, GlyphId, u32);
+
+// 字形图集：R8 覆盖率纹理，按行 (shelf) 打包
+// 装满时若有本帧没用到的旧字形 (如缩放前的字号) 就整体清空重排，否则放不下的字形本帧不画
+struct GlyphAtlas {
+    texture: wgpu::Texture,
+    // (字体序号, 字形, 光栅化字号)
+    glyphs: HashMap<GlyphKey, Option<AtlasGlyph>>,
+    // 本帧用到的字形
+    used: HashSet<GlyphKey>,
+    cursor: [u32; 2],
+    row_height: u32,
+}
+
+impl GlyphAtlas {
+    fn new(ctx: &GpuContext) -> Self {
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas"),
+            size: wgpu::Extent3d {
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let solid = [255u8; (SOLID_SIZE * SOLID_SIZE) as usize];
+        write_region(ctx, &texture, [0, 0], [SOLID_SIZE, SOLID_SIZE], &solid);
+        let mut atlas = Self {
+            texture,
+            glyphs: HashMap::new(),
+            used: HashSet::new(),
+            cursor: [0, 0],
+            row_height: 0,
+        };
+        atlas.reset();
+        atlas
+    }
+
+    fn reset(&mut self) {
+        self.glyphs.clear();
+        self.used.clear();
+        self.cursor = [SOLID_SIZE + 1, 0];
+        self.row_height = SOLID_SIZE + 1;
+    }
+
+    // 图集里有本帧没用到的字形，清空后能腾出空间
+    fn has_stale(&self) -> bool {
+        self.glyphs.len() > self.used.len()
+    }
+
+    // 分配 size 大小的区域，四周留 1 像素避免线性采样串色；放不下时返回 None
+    fn allocate(&mut self, size: [u32; 2]) -> Option<[u32; 2]> {
+        let (w, h) = (size[0] + 1, size[1] + 1);
+        if w > ATLAS_SIZE || h > ATLAS_SIZE {
+            return None;
+        }
+        if self.cursor[0] + w > ATLAS_SIZE {
+            self.cursor = [0, self.cursor[1] + self.row_height];
+            self.row_height = 0;
+        }
+        if self.cursor[1] + h > ATLAS_SIZE {
+            return None;
+        }
+        let origin = self.cursor;
+        self.cursor[0] += w;
+        self.row_height = self.row_height.max(h);
+        Some(origin)
+    }
+}
+
+fn write_region(
+    ctx: &GpuContext,
+    texture: &wgpu::Texture,
+    origin: [u32; 2],
+    size: [u32; 2],
+    data: &[u8],
+) {
+    ctx.queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+                x: origin[0],
+                y: origin[1],
+                z: 0,
+            },
+            aspect: wgpu::TextureAspect::All,
+        },
+        data,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(size[0]),
+            rows_per_image: Some(size[1]),
+        },
+        wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+// 图集放不下新字形
+struct AtlasFull;
+
+// 文字渲染器：字体按加载顺序组成回退链，字符取第一个包含它的字体 (西文字体 + 中文字体)
+// 字形按需光栅化进图集；每帧在 CPU 上排版成屏幕像素的四边形，
+// 图像空间 / 锚定文字与 HUD 文字共用顶点缓冲，按索引区间分两次绘制
+pub struct TextRenderer {
+    pipeline: wgpu::RenderPipeline,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    atlas: GlyphAtlas,
+    fonts: Vec<FontVec>,
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    // 前 scene_index_count 个索引属于图像空间，其余属于 HUD
+    scene_index_count: u32,
+    index_count: u32,
+    srgb_target: bool,
+    // 图集已满的警告每次出现只记一条，恢复后再出现时重新记录
+    atlas_full_logged: bool,
+}
+
+impl TextRenderer {
+    pub fn new(ctx: &GpuContext, target_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let device = &ctx.device;
+        let source = [
+            include_str!("../shaders/srgb.wgsl"),
+            include_str!("../shaders/text.wgsl"),
+        ]
+        .concat();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Text Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<TextVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &VERTEX_ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Uniforms"),
+            size: std::mem::size_of::<TextUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let atlas = GlyphAtlas::new(ctx);
+        let atlas_view = atlas
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Text Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&atlas_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            pipeline,
+            uniforms,
+            bind_group,
+            atlas,
+            fonts: Vec::new(),
+            vertex_buffer: None,
+            index_buffer: None,
+            scene_index_count: 0,
+            index_count: 0,
+            srgb_target: target_format.is_srgb(),
+            atlas_full_logged: false,
+        }
+    }
+
+    // 追加一个字体到回退链末尾，数据不是有效的 TTF/OTF 时返回 InvalidArgument
+    pub fn load_font(&mut self, data: Vec<u8>) -> IrisResult {
+        let font = FontVec::try_from_vec(data).map_err(|e| {
+            IrisError::new(IrisStatus::InvalidArgument, format!("字体加载失败: {}", e))
+        })?;
+        self.fonts.push(font);
+        Ok(())
+    }
+
+    pub fn clear_fonts(&mut self) {
+        self.fonts.clear();
+        self.atlas.reset();
+    }
+
+    // 每帧重新排版：图像空间的文字要跟随视图变换
+    pub fn prepare(&mut self, ctx: &GpuContext, items: &[TextItem], view: &ViewTransform) {
+        let uniforms = TextUniforms {
+            screen_to_clip: view.screen_to_clip().to_cols_array_2d(),
+            srgb_target: self.srgb_target as u32,
+            _pad: [0; 3],
+        };
+        ctx.queue
+            .write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+
+        self.index_count = 0;
+        if self.fonts.is_empty() || items.is_empty() {
+            return;
+        }
+        let (mut vertices, mut indices, mut full) = self.build(ctx, items, view);
+        // 旧字形占着空间时清空重排一次；本帧的字形本身就装不下时不清空，避免每帧重新光栅化
+        if full && self.atlas.has_stale() {
+            self.atlas.reset();
+            (vertices, indices, full) = self.build(ctx, items, view);
+        }
+        if full && !self.atlas_full_logged {
+            log::warn!("字形图集已满，放不下的字形未绘制");
+        }
+        self.atlas_full_logged = full;
+        if indices.is_empty() {
+            return;
+        }
+        write_growable(
+            ctx,
+            &mut self.vertex_buffer,
+            "Text Vertices",
+            wgpu::BufferUsages::VERTEX,
+            bytemuck::cast_slice(&vertices),
+        );
+        write_growable(
+            ctx,
+            &mut self.index_buffer,
+            "Text Indices",
+            wgpu::BufferUsages::INDEX,
+            bytemuck::cast_slice(&indices),
+        );
+        self.index_count = indices.len() as u32;
+    }
+
+    // 排版全部文字；最后一项为 true 表示有字形因图集已满没有画出
+    fn build(
+        &mut self,
+        ctx: &GpuContext,
+        items: &[TextItem],
+        view: &ViewTransform,
+    ) -> (Vec<TextVertex>, Vec<u32>, bool) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut full = false;
+        self.atlas.used.clear();
+        let scene = items.iter().filter(|t| t.space != IrisTextSpace::Screen);
+        let hud = items.iter().filter(|t| t.space == IrisTextSpace::Screen);
+        for item in scene {
+            full |= self.push_item(ctx, item, view, &mut vertices, &mut indices);
+        }
+        self.scene_index_count = indices.len() as u32;
+        for item in hud {
+            full |= self.push_item(ctx, item, view, &mut vertices, &mut indices);
+        }
+        (vertices, indices, full)
+    }
+
+    // 排版一段文字：先在以光栅化像素为单位、y 向下的局部坐标里排好，
+    // 再按文字所在空间映射到屏幕像素；图集放不下的字形跳过 (只留出它的宽度)，此时返回 true
+    fn push_item(
+        &mut self,
+        ctx: &GpuContext,
+        item: &TextItem,
+        view: &ViewTransform,
+        vertices: &mut Vec<TextVertex>,
+        indices: &mut Vec<u32>,
+    ) -> bool {
+        let style = &item.style;
+        let zoom = view.zoom() as f32;
+        let screen_size = match item.space {
+            IrisTextSpace::Image => style.size * zoom,
+            _ => style.size,
+        };
+        if item.text.is_empty() || screen_size.is_nan() || screen_size < 1.0 {
+            return false;
+        }
+        let raster_px = raster_size(screen_size);
+        // 局部坐标的一个单位对应的屏幕像素
+        let unit = screen_size / raster_px;
+
+        let primary = self.fonts[0].as_scaled(PxScale::from(raster_px));
+        let (ascent, descent) = (primary.ascent(), primary.descent());
+        let line_height = ascent - descent + primary.line_gap();
+        let mut glyphs: Vec<(Vec2, AtlasGlyph)> = Vec::new();
+        let mut width = 0.0f32;
+        let mut lines = 1;
+        let mut pen = Vec2::new(0.0, ascent);
+        let mut previous: Option<(usize, GlyphId)> = None;
+        let mut full = false;
+        for c in item.text.chars() {
+            if c == '\n' {
+                width = width.max(pen.x);
+                pen = Vec2::new(0.0, pen.y + line_height);
+                lines += 1;
+                previous = None;
+                continue;
+            }
+            if c.is_control() {
+                continue;
+            }
+            let font_index = self.font_for(c);
+            let font = self.fonts[font_index].as_scaled(PxScale::from(raster_px));
+            let id = font.glyph_id(c);
+            let advance = font.h_advance(id);
+            if let Some((prev_font, prev_id)) = previous {
+                if prev_font == font_index {
+                    pen.x += font.kern(prev_id, id);
+                }
+            }
+            match self.glyph(ctx, font_index, id, raster_px) {
+                Ok(Some(glyph)) => glyphs.push((Vec2::new(pen.x.round(), pen.y.round()), glyph)),
+                Ok(None) => {}
+                Err(AtlasFull) => full = true,
+            }
+            pen.x += advance;
+            previous = Some((font_index, id));
+        }
+        width = width.max(pen.x);
+        let height = ascent - descent + line_height * (lines - 1) as f32;
+
+        // 背景框包含留白，对齐点按锚点取在框上
+        let padding = match item.space {
+            IrisTextSpace::Image => style.padding * zoom,
+            _ => style.padding,
+        } / unit;
+        let box_min = Vec2::splat(-padding);
+        let box_max = Vec2::new(width, height) + padding;
+        let (ax, ay) = style.anchor.fraction();
+        let anchor = box_min + (box_max - box_min) * Vec2::new(ax, ay);
+
+        let to_screen = |local: Vec2| -> [f32; 2] {
+            let offset = ((local - anchor) * unit).as_dvec2();
+            let p = match item.space {
+                IrisTextSpace::Screen => item.position + offset,
+                IrisTextSpace::Anchored => view.image_to_screen(item.position) + offset,
+                // 偏移按图像像素换算后再映射，文字随视图一起旋转
+                IrisTextSpace::Image => view.image_to_screen(item.position + offset / view.zoom()),
+            };
+            p.as_vec2().to_array()
+        };
+        // 屏幕空间和锚定文字把原点对齐到整像素，字形按 1:1 采样保持清晰
+        let snap = match item.space {
+            IrisTextSpace::Image => Vec2::ZERO,
+            _ => {
+                let origin = Vec2::from_array(to_screen(Vec2::ZERO));
+                (origin.round() - origin) / unit
+            }
+        };
+
+        let atlas_scale = 1.0 / ATLAS_SIZE as f32;
+        let mut push_quad = |min: Vec2, max: Vec2, uv_min: Vec2, uv_max: Vec2, color: [f32; 4]| {
+            let base = vertices.len() as u32;
+            for (corner, uv) in [
+                (min, uv_min),
+                (Vec2::new(max.x, min.y), Vec2::new(uv_max.x, uv_min.y)),
+                (max, uv_max),
+                (Vec2::new(min.x, max.y), Vec2::new(uv_min.x, uv_max.y)),
+            ] {
+                vertices.push(TextVertex {
+                    position: to_screen(corner + snap),
+                    uv: uv.to_array(),
+                    color,
+                });
+            }
+            indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+        };
+        if style.background[3] > 0.0 {
+            let solid = Vec2::splat(SOLID_SIZE as f32 * 0.5 * atlas_scale);
+            push_quad(box_min, box_max, solid, solid, style.background);
+        }
+        for (pen, glyph) in glyphs {
+            let min = pen + glyph.offset;
+            let size = Vec2::new(glyph.size[0] as f32, glyph.size[1] as f32);
+            let uv_min = Vec2::new(glyph.origin[0] as f32, glyph.origin[1] as f32) * atlas_scale;
+            push_quad(
+                min,
+                min + size,
+                uv_min,
+                uv_min + size * atlas_scale,
+                style.color,
+            );
+        }
+        full
+    }
+
+    // 回退链中第一个包含该字符的字体，都没有时用第一个字体的缺字符号
+    fn font_for(&self, c: char) -> usize {
+        self.fonts
+            .iter()
+            .position(|f| f.glyph_id(c).0 != 0)
+            .unwrap_or(0)
+    }
+
+    // 取图集中的字形，没有则光栅化并上传；空白字形返回 None
+    fn glyph(
+        &mut self,
+        ctx: &GpuContext,
+        font_index: usize,
+        id: GlyphId,
+        raster_px: f32,
+    ) -> Result<Option<AtlasGlyph>, AtlasFull> {
+        let key = (font_index, id, raster_px as u32);
+        if let Some(&glyph) = self.atlas.glyphs.get(&key) {
+            self.atlas.used.insert(key);
+            return Ok(glyph);
+        }
+        let glyph = id.with_scale(PxScale::from(raster_px));
+        let Some(outline) = self.fonts[font_index].outline_glyph(glyph) else {
+            self.atlas.glyphs.insert(key, None);
+            self.atlas.used.insert(key);
+            return Ok(None);
+        };
+        let bounds = outline.px_bounds();
+        let size = [bounds.width() as u32, bounds.height() as u32];
+        if size[0] == 0 || size[1] == 0 {
+            self.atlas.glyphs.insert(key, None);
+            self.atlas.used.insert(key);
+            return Ok(None);
+        }
+        let origin = self.atlas.allocate(size).ok_or(AtlasFull)?;
+        let mut coverage = vec![0u8; (size[0] * size[1]) as usize];
+        outline.draw(|x, y, c| {
+            coverage[(y * size[0] + x) as usize] = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+        write_region(ctx, &self.atlas.texture, origin, size, &coverage);
+        let entry = AtlasGlyph {
+            origin,
+            size,
+            offset: Vec2::new(bounds.min.x, bounds.min.y),
+        };
+        self.atlas.glyphs.insert(key, Some(entry));
+        self.atlas.used.insert(key);
+        Ok(Some(entry))
+    }
+
+    // 绘制图像空间和锚定的文字
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        self.draw_range(rpass, 0..self.scene_index_count.min(self.index_count));
+    }
+
+    // 绘制屏幕空间的文字
+    pub fn draw_hud(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        self.draw_range(rpass, self.scene_index_count..self.index_count);
+    }
+
+    fn draw_range(&self, rpass: &mut wgpu::RenderPass<'_>, range: std::ops::Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+        let (Some(vertices), Some(indices)) = (&self.vertex_buffer, &self.index_buffer) else {
+            return;
+        };
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_vertex_buffer(0, vertices.slice(..));
+        rpass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+        rpass.draw_indexed(range, 0, 0..1);
+    }
+}
+
+// 光栅化字号：小字号按整数像素保证清晰，大字号按步长取整并封顶，其余靠拉伸
+fn raster_size(screen_size: f32) -> f32 {
+    if screen_size <= EXACT_RASTER_PX {
+        screen_size.round()
+    } else {
+        ((screen_size / RASTER_STEP).round() * RASTER_STEP).min(MAX_RASTER_PX)
+    }
+}
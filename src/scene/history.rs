@@ -0,0 +1,236 @@
+use crate::scene::manager::SceneObject;
+use std::collections::VecDeque;
+
+// 可撤销的场景修改，记录修改前后的完整对象快照，撤销 / 重做时整体替换
+pub enum Command {
+    // 新增的对象及其在对象列表中的位置 (按位置升序)
+    Add(Vec<(usize, SceneObject)>),
+    // 删除的对象及其删除前的位置 (按位置升序)
+    Remove(Vec<(usize, SceneObject)>),
+    // 几何或属性修改：(修改前, 修改后)
+    Modify(Vec<(SceneObject, SceneObject)>),
+    // 叠放顺序修改：修改前后的对象 ID 顺序
+    Reorder { before: Vec<u64>, after: Vec<u64> },
+    // 作为一步撤销的多条命令，撤销时逆序执行
+    Batch(Vec<Command>),
+}
+
+// 撤销 / 重做栈：新的修改会清空重做栈，超出深度时丢弃最早的记录
+pub struct History {
+    undo: VecDeque<Command>,
+    redo: Vec<Command>,
+    depth: usize,
+}
+
+impl History {
+    pub const DEFAULT_DEPTH: usize = 100;
+
+    pub fn new() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            depth: Self::DEFAULT_DEPTH,
+        }
+    }
+
+    pub fn push(&mut self, command: Command) {
+        self.redo.clear();
+        self.push_undo(command);
+    }
+
+    fn push_undo(&mut self, command: Command) {
+        self.undo.push_back(command);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+        // 重做栈底部是离当前最远的记录，优先丢弃
+        let excess = self.redo.len().saturating_sub(self.depth);
+        self.redo.drain(..excess);
+    }
+
+    // 深度为 0 时不记录历史
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        self.trim();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // 取出待撤销的命令，撤销完成后由调用方交给 undone
+    pub fn take_undo(&mut self) -> Option<Command> {
+        self.undo.pop_back()
+    }
+
+    pub fn undone(&mut self, command: Command) {
+        self.redo.push(command);
+    }
+
+    pub fn take_redo(&mut self) -> Option<Command> {
+        self.redo.pop()
+    }
+
+    pub fn redone(&mut self, command: Command) {
+        self.push_undo(command);
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+    use crate::common::types::{IrisLayer, IrisRoiBoolOp};
+    use crate::scene::manager::SceneManager;
+    use crate::scene::roi::{Roi, RoiShape, RoiStyle};
+
+    fn rect(x: f64, y: f64) -> Roi {
+        Roi {
+            shape: RoiShape::Rect {
+                x,
+                y,
+                width: 40.0,
+                height: 30.0,
+            },
+            style: RoiStyle::default(),
+        }
+    }
+
+    fn ids(scene: &SceneManager) -> Vec<u64> {
+        scene.objects().map(|o| o.id).collect()
+    }
+
+    #[test]
+    fn undo_redo_add() {
+        let mut scene = SceneManager::new();
+        let id = scene.add_roi(rect(0.0, 0.0));
+        assert!(scene.undo());
+        assert!(scene.object(id).is_none());
+        assert!(scene.redo());
+        assert_eq!(scene.roi(id), Some(&rect(0.0, 0.0)));
+        assert!(!scene.redo());
+    }
+
+    #[test]
+    fn undo_remove_restores_position() {
+        let mut scene = SceneManager::new();
+        let a = scene.add_roi(rect(0.0, 0.0));
+        let b = scene.add_roi(rect(50.0, 0.0));
+        let c = scene.add_roi(rect(100.0, 0.0));
+        assert!(scene.remove_roi(b));
+        assert_eq!(ids(&scene), [a, c]);
+        assert!(scene.undo());
+        assert_eq!(ids(&scene), [a, b, c]);
+        assert!(scene.redo());
+        assert_eq!(ids(&scene), [a, c]);
+    }
+
+    #[test]
+    fn clear_keeps_other_layers() {
+        let mut scene = SceneManager::new();
+        let a = scene.add_roi(rect(0.0, 0.0));
+        let b = scene.add_roi(rect(50.0, 0.0));
+        let c = scene.add_roi(rect(100.0, 0.0));
+        assert!(scene.set_layer(b, IrisLayer::Annotation));
+        scene.clear_rois();
+        assert_eq!(ids(&scene), [b]);
+        assert!(scene.undo());
+        assert_eq!(ids(&scene), [a, b, c]);
+    }
+
+    #[test]
+    fn style_stays_on_single_object() {
+        let mut scene = SceneManager::new();
+        let a = scene.add_roi(rect(0.0, 0.0));
+        let b = scene.add_roi(rect(50.0, 0.0));
+        let group = scene.group(&[a, b]);
+        let style = RoiStyle {
+            stroke_width: 5.0,
+            ..RoiStyle::default()
+        };
+        assert!(!scene.set_style(group, style));
+        assert!(scene.set_style(a, style));
+        assert_eq!(scene.roi(a).unwrap().style, style);
+        assert_eq!(scene.roi(b).unwrap().style, RoiStyle::default());
+    }
+
+    #[test]
+    fn undo_redo_modify() {
+        let mut scene = SceneManager::new();
+        let id = scene.add_roi(rect(0.0, 0.0));
+        assert!(scene.set_shape(id, rect(10.0, 20.0).shape));
+        assert!(scene.set_name(id, "A"));
+        assert!(scene.undo());
+        assert_eq!(scene.object(id).unwrap().name, "");
+        assert!(scene.undo());
+        assert_eq!(scene.roi(id), Some(&rect(0.0, 0.0)));
+        assert!(scene.redo());
+        assert_eq!(scene.roi(id), Some(&rect(10.0, 20.0)));
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut scene = SceneManager::new();
+        let id = scene.add_roi(rect(0.0, 0.0));
+        assert!(scene.set_name(id, "A"));
+        assert!(scene.undo());
+        assert!(scene.can_redo());
+        assert!(scene.set_name(id, "B"));
+        assert!(!scene.can_redo());
+    }
+
+    // 布尔组合删除操作数并修改第一个对象，整体是一步撤销
+    #[test]
+    fn undo_redo_batch() {
+        let mut scene = SceneManager::new();
+        let a = scene.add_roi(rect(0.0, 0.0));
+        let b = scene.add_roi(rect(20.0, 10.0));
+        assert_eq!(scene.combine(IrisRoiBoolOp::Union, &[a, b]), a);
+        assert_eq!(ids(&scene), [a]);
+        assert!(scene.undo());
+        assert_eq!(ids(&scene), [a, b]);
+        assert_eq!(scene.roi(a), Some(&rect(0.0, 0.0)));
+        assert_eq!(scene.roi(b), Some(&rect(20.0, 10.0)));
+        assert!(scene.redo());
+        assert_eq!(ids(&scene), [a]);
+        assert!(matches!(
+            scene.roi(a).unwrap().shape,
+            RoiShape::Composite { .. }
+        ));
+    }
+
+    #[test]
+    fn depth_cap_drops_oldest() {
+        let mut scene = SceneManager::new();
+        let extra = 20;
+        for i in 0..History::DEFAULT_DEPTH + extra {
+            scene.add_roi(rect(i as f64, 0.0));
+        }
+        let mut undone = 0;
+        while scene.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, History::DEFAULT_DEPTH);
+        assert_eq!(scene.objects().count(), extra);
+    }
+
+    #[test]
+    fn zero_depth_records_nothing() {
+        let mut scene = SceneManager::new();
+        scene.set_history_depth(0);
+        scene.add_roi(rect(0.0, 0.0));
+        assert!(!scene.can_undo());
+    }
+}
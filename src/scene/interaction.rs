@@ -0,0 +1,401 @@
+use crate::common::types::{IrisCursor, IrisRoiTool};
+use crate::scene::roi::RoiShape;
+use glam::DVec2;
+use std::f64::consts::TAU;
+
+// 手柄绘制边长和命中半径 (屏幕像素)
+pub const HANDLE_SIZE: f64 = 8.0;
+pub const HANDLE_HIT_RADIUS: f64 = 6.0;
+// 轮廓命中容差 (屏幕像素)
+pub const OUTLINE_HIT_TOLERANCE: f64 = 4.0;
+// 旋转手柄离形状的距离 (屏幕像素)
+const ROTATE_HANDLE_DISTANCE: f64 = 24.0;
+// 拖动时允许的最小尺寸 (图像像素)，防止形状退化
+const MIN_SIZE: f64 = 0.5;
+// 扇环的最小张角
+const MIN_SWEEP: f64 = 1e-3;
+
+// 矩形类局部坐标的角点 / 边中点方向，顺序与 rotated_rect_corners 一致
+const CORNER_SIGNS: [(f64, f64); 4] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+// 0 上、1 右、2 下、3 左
+const EDGE_SIGNS: [(f64, f64); 4] = [(0.0, -1.0), (1.0, 0.0), (0.0, 1.0), (-1.0, 0.0)];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HandleKind {
+    Corner(usize),
+    Edge(usize),
+    Radius,
+    AxisX,
+    AxisY,
+    InnerRadius,
+    OuterRadius,
+    StartAngle,
+    EndAngle,
+    Rotate,
+    Vertex(usize),
+}
+
+// 编辑手柄：anchor 为形状上的图像坐标点，offset 为沿图像坐标轴方向的屏幕像素偏移
+// (旋转手柄需要与形状保持固定的屏幕距离，和缩放无关)
+#[derive(Clone, Copy, Debug)]
+pub struct Handle {
+    pub kind: HandleKind,
+    pub anchor: DVec2,
+    pub offset: DVec2,
+}
+
+impl Handle {
+    pub fn position(&self, zoom: f64) -> DVec2 {
+        self.anchor + self.offset / zoom
+    }
+}
+
+// 矩形和旋转矩形统一表示为 (中心, 半长, 角度)
+fn box_frame(shape: &RoiShape) -> Option<(DVec2, DVec2, f64)> {
+    match *shape {
+        RoiShape::Rect {
+            x,
+            y,
+            width,
+            height,
+        } => Some((
+            DVec2::new(x + width * 0.5, y + height * 0.5),
+            DVec2::new(width * 0.5, height * 0.5),
+            0.0,
+        )),
+        RoiShape::RotatedRect {
+            cx,
+            cy,
+            half_width,
+            half_height,
+            angle,
+        } => Some((
+            DVec2::new(cx, cy),
+            DVec2::new(half_width, half_height),
+            angle,
+        )),
+        _ => None,
+    }
+}
+
+fn axes(angle: f64) -> (DVec2, DVec2) {
+    let u = DVec2::from_angle(angle);
+    (u, u.perp())
+}
+
+// 选中形状时显示的手柄
+pub fn handles(shape: &RoiShape) -> Vec<Handle> {
+    let handle = |kind, anchor| Handle {
+        kind,
+        anchor,
+        offset: DVec2::ZERO,
+    };
+    let mut list = Vec::new();
+    match *shape {
+        RoiShape::Rect { .. } | RoiShape::RotatedRect { .. } => {
+            let (center, half, angle) = box_frame(shape).unwrap();
+            let (u, v) = axes(angle);
+            let local = |sx: f64, sy: f64| center + u * (sx * half.x) + v * (sy * half.y);
+            for (i, &(sx, sy)) in CORNER_SIGNS.iter().enumerate() {
+                list.push(handle(HandleKind::Corner(i), local(sx, sy)));
+            }
+            for (i, &(sx, sy)) in EDGE_SIGNS.iter().enumerate() {
+                list.push(handle(HandleKind::Edge(i), local(sx, sy)));
+            }
+            if matches!(shape, RoiShape::RotatedRect { .. }) {
+                list.push(Handle {
+                    kind: HandleKind::Rotate,
+                    anchor: local(1.0, 0.0),
+                    offset: u * ROTATE_HANDLE_DISTANCE,
+                });
+            }
+        }
+        RoiShape::Circle { cx, cy, radius } => {
+            let center = DVec2::new(cx, cy);
+            for dir in [DVec2::X, DVec2::Y, DVec2::NEG_X, DVec2::NEG_Y] {
+                list.push(handle(HandleKind::Radius, center + dir * radius));
+            }
+        }
+        RoiShape::Ellipse {
+            cx,
+            cy,
+            rx,
+            ry,
+            angle,
+        } => {
+            let center = DVec2::new(cx, cy);
+            let (u, v) = axes(angle);
+            list.push(handle(HandleKind::AxisX, center + u * rx));
+            list.push(handle(HandleKind::AxisX, center - u * rx));
+            list.push(handle(HandleKind::AxisY, center + v * ry));
+            list.push(handle(HandleKind::AxisY, center - v * ry));
+            list.push(Handle {
+                kind: HandleKind::Rotate,
+                anchor: center + u * rx,
+                offset: u * ROTATE_HANDLE_DISTANCE,
+            });
+        }
+        RoiShape::Annulus {
+            cx,
+            cy,
+            inner_radius,
+            outer_radius,
+            start_angle,
+            sweep_angle,
+        } => {
+            let center = DVec2::new(cx, cy);
+            let full = sweep_angle.abs() >= TAU;
+            let mid = if full {
+                start_angle
+            } else {
+                start_angle + sweep_angle * 0.5
+            };
+            let at = |angle: f64, radius: f64| center + DVec2::from_angle(angle) * radius;
+            list.push(handle(HandleKind::OuterRadius, at(mid, outer_radius)));
+            list.push(handle(HandleKind::InnerRadius, at(mid, inner_radius)));
+            if !full {
+                let radius = (inner_radius + outer_radius) * 0.5;
+                list.push(handle(HandleKind::StartAngle, at(start_angle, radius)));
+                list.push(handle(
+                    HandleKind::EndAngle,
+                    at(start_angle + sweep_angle, radius),
+                ));
+            }
+        }
+        RoiShape::Polygon { ref points }
+        | RoiShape::Distance { ref points }
+        | RoiShape::Angle { ref points }
+        | RoiShape::Polyline { ref points } => {
+            for (i, &[x, y]) in points.iter().enumerate() {
+                list.push(handle(HandleKind::Vertex(i), DVec2::new(x, y)));
+            }
+        }
+        // 组合形状没有手柄，只能整体移动
+        RoiShape::Composite { .. } => {}
+    }
+    list
+}
+
+// 拖动手柄：总是基于拖动开始时的形状和当前指针位置计算，避免误差累积
+pub fn drag_handle(original: &RoiShape, kind: HandleKind, p: DVec2) -> RoiShape {
+    let mut shape = original.clone();
+    match (&mut shape, kind) {
+        (RoiShape::Rect { .. } | RoiShape::RotatedRect { .. }, _) => {
+            let (center, half, angle) = box_frame(original).unwrap();
+            let (u, v) = axes(angle);
+            if kind == HandleKind::Rotate {
+                if let RoiShape::RotatedRect { angle, .. } = &mut shape {
+                    *angle = angle_of(p - center);
+                }
+                return shape;
+            }
+            // 在局部坐标系里固定对边 / 对角，只移动被拖动的一侧
+            let q = DVec2::new((p - center).dot(u), (p - center).dot(v));
+            let (mut min, mut max) = (-half, half);
+            let (sx, sy) = match kind {
+                HandleKind::Corner(i) => CORNER_SIGNS[i % 4],
+                HandleKind::Edge(i) => EDGE_SIGNS[i % 4],
+                _ => return shape,
+            };
+            if sx != 0.0 {
+                let fixed = -sx * half.x;
+                min.x = fixed.min(q.x);
+                max.x = fixed.max(q.x);
+            }
+            if sy != 0.0 {
+                let fixed = -sy * half.y;
+                min.y = fixed.min(q.y);
+                max.y = fixed.max(q.y);
+            }
+            let new_half = ((max - min) * 0.5).max(DVec2::splat(MIN_SIZE * 0.5));
+            let mid = (min + max) * 0.5;
+            let new_center = center + u * mid.x + v * mid.y;
+            match &mut shape {
+                RoiShape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => {
+                    *x = new_center.x - new_half.x;
+                    *y = new_center.y - new_half.y;
+                    *width = new_half.x * 2.0;
+                    *height = new_half.y * 2.0;
+                }
+                RoiShape::RotatedRect {
+                    cx,
+                    cy,
+                    half_width,
+                    half_height,
+                    ..
+                } => {
+                    *cx = new_center.x;
+                    *cy = new_center.y;
+                    *half_width = new_half.x;
+                    *half_height = new_half.y;
+                }
+                _ => {}
+            }
+        }
+        (RoiShape::Circle { cx, cy, radius }, HandleKind::Radius) => {
+            *radius = p.distance(DVec2::new(*cx, *cy)).max(MIN_SIZE);
+        }
+        (
+            RoiShape::Ellipse {
+                cx,
+                cy,
+                rx,
+                ry,
+                angle,
+            },
+            _,
+        ) => {
+            let d = p - DVec2::new(*cx, *cy);
+            let (u, v) = axes(*angle);
+            match kind {
+                HandleKind::AxisX => *rx = d.dot(u).abs().max(MIN_SIZE),
+                HandleKind::AxisY => *ry = d.dot(v).abs().max(MIN_SIZE),
+                HandleKind::Rotate => *angle = angle_of(d),
+                _ => {}
+            }
+        }
+        (
+            RoiShape::Annulus {
+                cx,
+                cy,
+                inner_radius,
+                outer_radius,
+                start_angle,
+                sweep_angle,
+            },
+            _,
+        ) => {
+            let d = p - DVec2::new(*cx, *cy);
+            let end = *start_angle + *sweep_angle;
+            match kind {
+                HandleKind::OuterRadius => {
+                    *outer_radius = d.length().max(*inner_radius + MIN_SIZE);
+                }
+                HandleKind::InnerRadius => {
+                    *inner_radius = d.length().min(*outer_radius - MIN_SIZE).max(0.0);
+                }
+                // 保持另一端不动，张角方向 (正负) 不变
+                HandleKind::StartAngle => {
+                    let a = angle_of(d);
+                    *sweep_angle = signed_sweep(end - a, *sweep_angle);
+                    *start_angle = a;
+                }
+                HandleKind::EndAngle => {
+                    *sweep_angle = signed_sweep(angle_of(d) - *start_angle, *sweep_angle);
+                }
+                _ => {}
+            }
+        }
+        (
+            RoiShape::Polygon { points }
+            | RoiShape::Distance { points }
+            | RoiShape::Angle { points }
+            | RoiShape::Polyline { points },
+            HandleKind::Vertex(i),
+        ) => {
+            if let Some(point) = points.get_mut(i) {
+                *point = [p.x, p.y];
+            }
+        }
+        _ => {}
+    }
+    shape
+}
+
+fn angle_of(d: DVec2) -> f64 {
+    d.y.atan2(d.x)
+}
+
+// 把角度差归一到与 reference 同号的 (0, 2π) 区间
+fn signed_sweep(delta: f64, reference: f64) -> f64 {
+    if reference >= 0.0 {
+        delta.rem_euclid(TAU).max(MIN_SWEEP)
+    } else {
+        -(-delta).rem_euclid(TAU).max(MIN_SWEEP)
+    }
+}
+
+// 根据手柄在屏幕上相对形状中心的方向选择缩放光标
+pub fn resize_cursor(screen_dir: DVec2) -> IrisCursor {
+    let degrees = screen_dir
+        .y
+        .atan2(screen_dir.x)
+        .to_degrees()
+        .rem_euclid(180.0);
+    match degrees {
+        d if !(22.5..157.5).contains(&d) => IrisCursor::SizeWE,
+        d if d < 67.5 => IrisCursor::SizeNWSE,
+        d if d < 112.5 => IrisCursor::SizeNS,
+        _ => IrisCursor::SizeNESW,
+    }
+}
+
+// 拖动创建形状：start 为按下位置，current 为当前位置 (图像坐标)，尺寸过小时返回 None
+pub fn create_shape(tool: IrisRoiTool, start: DVec2, current: DVec2) -> Option<RoiShape> {
+    let min = start.min(current);
+    let size = (current - start).abs();
+    let center = (start + current) * 0.5;
+    let shape = match tool {
+        IrisRoiTool::Rect => RoiShape::Rect {
+            x: min.x,
+            y: min.y,
+            width: size.x,
+            height: size.y,
+        },
+        IrisRoiTool::RotatedRect => RoiShape::RotatedRect {
+            cx: center.x,
+            cy: center.y,
+            half_width: size.x * 0.5,
+            half_height: size.y * 0.5,
+            angle: 0.0,
+        },
+        IrisRoiTool::Circle => RoiShape::Circle {
+            cx: start.x,
+            cy: start.y,
+            radius: start.distance(current),
+        },
+        IrisRoiTool::Ellipse => RoiShape::Ellipse {
+            cx: center.x,
+            cy: center.y,
+            rx: size.x * 0.5,
+            ry: size.y * 0.5,
+            angle: 0.0,
+        },
+        // 默认内径为外径的一半的完整圆环，之后可拖动手柄调整
+        IrisRoiTool::Annulus => {
+            let radius = start.distance(current);
+            RoiShape::Annulus {
+                cx: start.x,
+                cy: start.y,
+                inner_radius: radius * 0.5,
+                outer_radius: radius,
+                start_angle: 0.0,
+                sweep_angle: TAU,
+            }
+        }
+        IrisRoiTool::Distance => RoiShape::Distance {
+            points: vec![start.into(), current.into()],
+        },
+        IrisRoiTool::Select | IrisRoiTool::Polygon | IrisRoiTool::Angle | IrisRoiTool::Polyline => {
+            return None
+        }
+    };
+    shape.is_valid().then_some(shape)
+}
+
+// 多边形边上离 p 最近的插入位置：返回 (插入下标, 距离)
+pub fn nearest_edge(points: &[[f64; 2]], p: DVec2) -> Option<(usize, f64)> {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let a = DVec2::from(points[i]);
+            let b = DVec2::from(points[(i + 1) % n]);
+            (i + 1, crate::scene::roi::distance_to_segment(p, a, b))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
@@ -1 +1,1085 @@
+use crate::common::math::ViewTransform;
+use crate::common::types::{
+    IrisCursor, IrisLayer, IrisMouseButton, IrisMouseResult, IrisRoiBoolOp, IrisRoiEvent,
+    IrisRoiTool, IrisZOrder, IRIS_MOD_CTRL, IRIS_MOD_SHIFT,
+};
+use crate::scene::history::{Command, History};
+use crate::scene::interaction::{
+    create_shape, drag_handle, handles, nearest_edge, resize_cursor, HandleKind, HANDLE_HIT_RADIUS,
+    OUTLINE_HIT_TOLERANCE,
+};
+use crate::scene::roi::{Roi, RoiShape, RoiStyle};
+use glam::DVec2;
 
+// 场景对象：几何和样式之外，还带有图层、显示 / 锁定状态、不透明度和分组
+// HUD 图层的对象坐标为屏幕像素，其余图层为图像像素
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneObject {
+    pub id: u64,
+    pub roi: Roi,
+    // 宿主给的名称和标签，都随配方保存；名称非空时画成 ROI 旁的文字标签
+    pub name: String,
+    pub tags: Vec<String>,
+    pub layer: IrisLayer,
+    pub visible: bool,
+    pub locked: bool,
+    pub opacity: f32,
+    // 所属分组的 ID，分组与对象共用 ID 空间
+    pub group: Option<u64>,
+}
+
+#[derive(Clone, Copy)]
+struct LayerState {
+    visible: bool,
+    locked: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DragTarget {
+    Body,
+    Handle(HandleKind),
+}
+
+// 一次按下-拖动-抬起过程中的状态
+enum Drag {
+    Idle,
+    // 屏幕坐标，用于计算平移增量
+    Pan {
+        last: DVec2,
+    },
+    // 编辑已有 ROI：original 为按下时的形状，start 为按下时的图像坐标
+    // 拖动分组成员的主体时，group 中的其他成员随之平移
+    // before 为按下前所有受影响对象的快照，抬起时整个拖动记为一条历史
+    Edit {
+        before: Vec<SceneObject>,
+        id: u64,
+        target: DragTarget,
+        original: RoiShape,
+        group: Vec<(u64, RoiShape)>,
+        start: DVec2,
+        cursor: IrisCursor,
+        changed: bool,
+    },
+    // 拖动创建，尺寸足够后才真正加入场景
+    Create {
+        tool: IrisRoiTool,
+        start: DVec2,
+        id: Option<u64>,
+    },
+}
+
+// 逐点创建中的多边形 / 角度 / 折线：points 为已确定的顶点，预览时再附加指针位置
+struct PolygonDraft {
+    tool: IrisRoiTool,
+    points: Vec<[f64; 2]>,
+    id: Option<u64>,
+}
+
+impl PolygonDraft {
+    // 完成后的形状；预览时角度不足三点先按折线显示
+    fn shape(&self, points: Vec<[f64; 2]>) -> RoiShape {
+        match self.tool {
+            IrisRoiTool::Polygon => RoiShape::Polygon { points },
+            IrisRoiTool::Angle if points.len() == 3 => RoiShape::Angle { points },
+            _ => RoiShape::Polyline { points },
+        }
+    }
+}
+
+// 测量对象放在注释图层，不参与掩膜
+fn layer_for(shape: &RoiShape) -> IrisLayer {
+    if shape.is_measurement() {
+        IrisLayer::Annotation
+    } else {
+        IrisLayer::Roi
+    }
+}
+
+// 保留模式的场景：对象按图层 (Image < Roi < Annotation < HUD) 叠放，
+// 同一图层内按列表顺序绘制 (靠后的在上层)
+// ID 从 1 开始单调递增、不复用，0 留给 FFI 表示失败
+pub struct SceneManager {
+    objects: Vec<SceneObject>,
+    layers: [LayerState; IrisLayer::COUNT],
+    next_id: u64,
+    // 任何几何或样式变化都会递增，渲染器据此判断是否需要重新细分
+    revision: u64,
+    // 鼠标交互状态
+    tool: IrisRoiTool,
+    selected: Option<u64>,
+    drag: Drag,
+    polygon_draft: Option<PolygonDraft>,
+    history: History,
+}
+
+impl SceneManager {
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            layers: [LayerState {
+                visible: true,
+                locked: false,
+            }; IrisLayer::COUNT],
+            next_id: 1,
+            revision: 0,
+            tool: IrisRoiTool::Select,
+            selected: None,
+            drag: Drag::Idle,
+            polygon_draft: None,
+            history: History::new(),
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn allocate_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    // 新对象放在 ROI 图层 (测量对象为注释图层) 的最上层
+    pub fn add_roi(&mut self, roi: Roi) -> u64 {
+        let layer = layer_for(&roi.shape);
+        self.add_object(roi, layer)
+    }
+
+    pub fn add_object(&mut self, roi: Roi, layer: IrisLayer) -> u64 {
+        let id = self.push_object(roi, layer);
+        let index = self.objects.len() - 1;
+        self.history
+            .push(Command::Add(vec![(index, self.objects[index].clone())]));
+        id
+    }
+
+    // 不记录历史的添加，交互创建时先加入草稿，完成后再整体记录
+    fn push_object(&mut self, roi: Roi, layer: IrisLayer) -> u64 {
+        let id = self.allocate_id();
+        self.objects.push(SceneObject {
+            id,
+            roi,
+            name: String::new(),
+            tags: Vec::new(),
+            layer,
+            visible: true,
+            locked: false,
+            opacity: 1.0,
+            group: None,
+        });
+        self.revision += 1;
+        id
+    }
+
+    pub fn object(&self, id: u64) -> Option<&SceneObject> {
+        self.objects.iter().find(|o| o.id == id)
+    }
+
+    // 不记录历史的修改，取得可变引用即视为发生修改
+    fn roi_mut(&mut self, id: u64) -> Option<&mut Roi> {
+        let object = self.objects.iter_mut().find(|o| o.id == id)?;
+        self.revision += 1;
+        Some(&mut object.roi)
+    }
+
+    pub fn roi(&self, id: u64) -> Option<&Roi> {
+        self.object(id).map(|o| &o.roi)
+    }
+
+    // 宿主按 ID 替换几何，形状无效时不修改
+    pub fn set_shape(&mut self, id: u64, shape: RoiShape) -> bool {
+        if !shape.is_valid() || self.object(id).is_none() {
+            return false;
+        }
+        self.update_targets(id, |o| o.roi.shape = shape.clone())
+    }
+
+    // 样式只作用于单个对象，不扩散到同组成员；分组 ID 返回 false
+    pub fn set_style(&mut self, id: u64, style: RoiStyle) -> bool {
+        if self.object(id).is_none() {
+            return false;
+        }
+        self.update_targets(id, |o| o.roi.style = style)
+    }
+
+    pub fn set_name(&mut self, id: u64, name: &str) -> bool {
+        self.update_targets(id, |o| o.name = name.to_owned())
+    }
+
+    // 重复的标签只保留一个
+    pub fn add_tag(&mut self, id: u64, tag: &str) -> bool {
+        self.update_targets(id, |o| {
+            if !o.tags.iter().any(|t| t == tag) {
+                o.tags.push(tag.to_owned());
+            }
+        })
+    }
+
+    pub fn clear_tags(&mut self, id: u64) -> bool {
+        self.update_targets(id, |o| o.tags.clear())
+    }
+
+    // 整体替换场景中的对象 (加载配方)，ID 沿用传入的值，撤销历史随之清空
+    // 调用方保证 ID 和分组 ID 都小于 u64::MAX (配方加载时已校验)
+    pub fn load_objects(&mut self, objects: Vec<SceneObject>) {
+        // 放弃进行中的创建和拖动
+        self.set_tool(self.tool);
+        let max_id = objects
+            .iter()
+            .flat_map(|o| std::iter::once(o.id).chain(o.group))
+            .max()
+            .unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+        self.objects = objects;
+        self.selected = None;
+        self.history.clear();
+        self.revision += 1;
+    }
+
+    // id 可以是对象或分组，分组会移除全部成员
+    pub fn remove_roi(&mut self, id: u64) -> bool {
+        let targets = self.targets(id);
+        if targets.is_empty() {
+            return false;
+        }
+        let removed = self.remove_objects(&targets);
+        self.history.push(Command::Remove(removed));
+        true
+    }
+
+    // 只删除 ROI 图层上的对象，标注和 HUD 保留；可整体撤销
+    pub fn clear_rois(&mut self) {
+        let ids: Vec<u64> = self
+            .objects
+            .iter()
+            .filter(|o| o.layer == IrisLayer::Roi)
+            .map(|o| o.id)
+            .collect();
+        if !ids.is_empty() {
+            let removed = self.remove_objects(&ids);
+            self.history.push(Command::Remove(removed));
+        }
+    }
+
+    // 不记录历史的删除，返回被删对象及其原位置 (升序)
+    fn remove_objects(&mut self, ids: &[u64]) -> Vec<(usize, SceneObject)> {
+        let mut removed = Vec::new();
+        for (index, object) in std::mem::take(&mut self.objects).into_iter().enumerate() {
+            if ids.contains(&object.id) {
+                removed.push((index, object));
+            } else {
+                self.objects.push(object);
+            }
+        }
+        if self.selected.is_some_and(|s| ids.contains(&s)) {
+            self.selected = None;
+        }
+        self.revision += 1;
+        removed
+    }
+
+    fn insert_objects(&mut self, objects: &[(usize, SceneObject)]) {
+        for (index, object) in objects {
+            let index = (*index).min(self.objects.len());
+            self.objects.insert(index, object.clone());
+        }
+        self.revision += 1;
+    }
+
+    // 交互创建完成后把草稿对象记为一次添加
+    fn record_add(&mut self, id: u64) {
+        if let Some(index) = self.objects.iter().position(|o| o.id == id) {
+            self.history
+                .push(Command::Add(vec![(index, self.objects[index].clone())]));
+        }
+    }
+
+    fn snapshot(&self, ids: &[u64]) -> Vec<SceneObject> {
+        self.objects
+            .iter()
+            .filter(|o| ids.contains(&o.id))
+            .cloned()
+            .collect()
+    }
+
+    // 与修改前的快照比较，有变化的对象记为一条历史
+    fn record_modify(&mut self, before: Vec<SceneObject>) {
+        let changes: Vec<(SceneObject, SceneObject)> = before
+            .into_iter()
+            .filter_map(|old| {
+                let new = self.object(old.id)?;
+                (*new != old).then(|| (old, new.clone()))
+            })
+            .collect();
+        if !changes.is_empty() {
+            self.history.push(Command::Modify(changes));
+        }
+    }
+
+    fn replace_objects(&mut self, objects: impl Iterator<Item = SceneObject>) {
+        for object in objects {
+            if let Some(slot) = self.objects.iter_mut().find(|o| o.id == object.id) {
+                *slot = object;
+            }
+        }
+        self.revision += 1;
+    }
+
+    fn reorder(&mut self, order: &[u64]) {
+        self.objects
+            .sort_by_key(|o| order.iter().position(|&id| id == o.id));
+        self.revision += 1;
+    }
+
+    // 撤销 / 重做：拖动或创建进行中时不处理
+    pub fn undo(&mut self) -> bool {
+        if !self.history_ready() {
+            return false;
+        }
+        let Some(command) = self.history.take_undo() else {
+            return false;
+        };
+        self.undo_command(&command);
+        self.history.undone(command);
+        self.drop_uneditable_selection();
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if !self.history_ready() {
+            return false;
+        }
+        let Some(command) = self.history.take_redo() else {
+            return false;
+        };
+        self.redo_command(&command);
+        self.history.redone(command);
+        self.drop_uneditable_selection();
+        true
+    }
+
+    fn undo_command(&mut self, command: &Command) {
+        match command {
+            Command::Add(objects) => {
+                let ids: Vec<u64> = objects.iter().map(|(_, o)| o.id).collect();
+                self.remove_objects(&ids);
+            }
+            Command::Remove(objects) => self.insert_objects(objects),
+            Command::Modify(changes) => {
+                self.replace_objects(changes.iter().map(|(before, _)| before.clone()))
+            }
+            Command::Reorder { before, .. } => self.reorder(before),
+            Command::Batch(commands) => {
+                for command in commands.iter().rev() {
+                    self.undo_command(command);
+                }
+            }
+        }
+    }
+
+    fn redo_command(&mut self, command: &Command) {
+        match command {
+            Command::Add(objects) => self.insert_objects(objects),
+            Command::Remove(objects) => {
+                let ids: Vec<u64> = objects.iter().map(|(_, o)| o.id).collect();
+                self.remove_objects(&ids);
+            }
+            Command::Modify(changes) => {
+                self.replace_objects(changes.iter().map(|(_, after)| after.clone()))
+            }
+            Command::Reorder { after, .. } => self.reorder(after),
+            Command::Batch(commands) => {
+                for command in commands {
+                    self.redo_command(command);
+                }
+            }
+        }
+    }
+
+    fn history_ready(&self) -> bool {
+        matches!(self.drag, Drag::Idle) && self.polygon_draft.is_none()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history_ready() && self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history_ready() && self.history.can_redo()
+    }
+
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history.set_depth(depth);
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &SceneObject> {
+        self.objects.iter()
+    }
+
+    // 按绘制顺序返回某个图层中可见的对象 (图层本身隐藏时为空)
+    pub fn visible_objects(&self, layer: IrisLayer) -> impl Iterator<Item = &SceneObject> {
+        let layer_visible = self.layers[layer as usize].visible;
+        self.objects
+            .iter()
+            .filter(move |o| layer_visible && o.layer == layer && o.visible)
+    }
+
+    // 掩膜光栅化的输入：ids 为空时取 ROI 图层的全部对象，分组 ID 展开为成员
+    // 返回形状及其对象 ID；HUD 对象不在图像坐标下、测量对象没有内部，遇到它们或 ID 不存在时返回 None
+    pub fn mask_shapes(&self, ids: &[u64]) -> Option<Vec<(&RoiShape, u64)>> {
+        let ids: Vec<u64> = if ids.is_empty() {
+            self.objects
+                .iter()
+                .filter(|o| o.layer == IrisLayer::Roi && !o.roi.shape.is_measurement())
+                .map(|o| o.id)
+                .collect()
+        } else {
+            let mut expanded = Vec::new();
+            for &id in ids {
+                let targets = self.targets(id);
+                if targets.is_empty() {
+                    return None;
+                }
+                expanded.extend(targets);
+            }
+            expanded
+        };
+        ids.into_iter()
+            .map(|id| {
+                let object = self.object(id)?;
+                if object.layer == IrisLayer::Hud || object.roi.shape.is_measurement() {
+                    return None;
+                }
+                Some((&object.roi.shape, id))
+            })
+            .collect()
+    }
+
+    // 属性操作的目标：对象 ID 返回自身，分组 ID 返回全部成员
+    fn targets(&self, id: u64) -> Vec<u64> {
+        if id == 0 {
+            return Vec::new();
+        }
+        if self.object(id).is_some() {
+            return vec![id];
+        }
+        self.objects
+            .iter()
+            .filter(|o| o.group == Some(id))
+            .map(|o| o.id)
+            .collect()
+    }
+
+    // 对对象或分组的每个成员执行修改，没有目标时返回 false
+    fn update_targets(&mut self, id: u64, mut f: impl FnMut(&mut SceneObject)) -> bool {
+        let targets = self.targets(id);
+        if targets.is_empty() {
+            return false;
+        }
+        let before = self.snapshot(&targets);
+        for object in self.objects.iter_mut().filter(|o| targets.contains(&o.id)) {
+            f(object);
+        }
+        self.revision += 1;
+        self.record_modify(before);
+        self.drop_uneditable_selection();
+        true
+    }
+
+    pub fn set_visible(&mut self, id: u64, visible: bool) -> bool {
+        self.update_targets(id, |o| o.visible = visible)
+    }
+
+    // 锁定的对象仍然显示，但不能被鼠标选中和编辑
+    pub fn set_locked(&mut self, id: u64, locked: bool) -> bool {
+        self.update_targets(id, |o| o.locked = locked)
+    }
+
+    pub fn set_opacity(&mut self, id: u64, opacity: f32) -> bool {
+        let opacity = if opacity.is_finite() {
+            opacity.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        self.update_targets(id, |o| o.opacity = opacity)
+    }
+
+    // Image 图层只放相机图像，不接受对象
+    pub fn set_layer(&mut self, id: u64, layer: IrisLayer) -> bool {
+        if layer == IrisLayer::Image {
+            return false;
+        }
+        self.update_targets(id, |o| o.layer = layer)
+    }
+
+    // 在同一图层内调整叠放顺序；分组按成员原有的相对顺序整体移动
+    pub fn set_z_order(&mut self, id: u64, order: IrisZOrder) -> bool {
+        let mut targets = self.targets(id);
+        if targets.is_empty() {
+            return false;
+        }
+        // 上移时从最上面的成员开始处理，避免成员之间互相交换
+        let before: Vec<u64> = self.objects.iter().map(|o| o.id).collect();
+        let position = |objects: &[SceneObject], id: u64| objects.iter().position(|o| o.id == id);
+        targets.sort_by_key(|&t| position(&self.objects, t));
+        if matches!(order, IrisZOrder::BringToFront | IrisZOrder::BringForward) {
+            targets.reverse();
+        }
+        for target in targets.iter().copied() {
+            let Some(index) = position(&self.objects, target) else {
+                continue;
+            };
+            let layer = self.objects[index].layer;
+            let same_layer = |o: &SceneObject| o.layer == layer && !targets.contains(&o.id);
+            let object = self.objects.remove(index);
+            let new_index = match order {
+                IrisZOrder::BringToFront => self.objects.len(),
+                IrisZOrder::SendToBack => 0,
+                // 越过同图层中相邻的一个对象
+                IrisZOrder::BringForward => self.objects[index..]
+                    .iter()
+                    .position(same_layer)
+                    .map_or(self.objects.len(), |i| index + i + 1),
+                IrisZOrder::SendBackward => self.objects[..index]
+                    .iter()
+                    .rposition(same_layer)
+                    .unwrap_or(0),
+            };
+            self.objects.insert(new_index, object);
+        }
+        // 置顶 / 置底后保持成员原有的相对顺序
+        if order == IrisZOrder::BringToFront {
+            let n = self.objects.len();
+            let count = targets.len();
+            self.objects[n - count..].reverse();
+        } else if order == IrisZOrder::SendToBack {
+            self.objects[..targets.len()].reverse();
+        }
+        self.revision += 1;
+        let after: Vec<u64> = self.objects.iter().map(|o| o.id).collect();
+        if after != before {
+            self.history.push(Command::Reorder { before, after });
+        }
+        true
+    }
+
+    // 把若干对象编为一组，返回分组 ID；对象原有的分组会被替换
+    pub fn group(&mut self, ids: &[u64]) -> u64 {
+        if ids.is_empty() || ids.iter().any(|&id| self.object(id).is_none()) {
+            return 0;
+        }
+        let group = self.allocate_id();
+        let before = self.snapshot(ids);
+        for object in self.objects.iter_mut().filter(|o| ids.contains(&o.id)) {
+            object.group = Some(group);
+        }
+        self.revision += 1;
+        self.record_modify(before);
+        group
+    }
+
+    // 布尔组合：第一个对象的形状换成全部操作数的组合，保留它的 ID、名称和样式，
+    // 其余对象被吸收删除；操作数必须是同一图层上互不相同、可编辑 (未锁定、可见) 的对象，
+    // 运算结果为空时不组合；整体作为一步撤销
+    pub fn combine(&mut self, op: IrisRoiBoolOp, ids: &[u64]) -> u64 {
+        let Some(&first) = ids.first() else {
+            return 0;
+        };
+        let Some(layer) = self.object(first).map(|o| o.layer) else {
+            return 0;
+        };
+        let mut operands = Vec::with_capacity(ids.len());
+        for (i, &id) in ids.iter().enumerate() {
+            match self.object(id) {
+                Some(o) if o.layer == layer && self.editable(o) && !ids[..i].contains(&id) => {
+                    operands.push(o.roi.shape.clone())
+                }
+                _ => return 0,
+            }
+        }
+        let shape = RoiShape::composite(op, operands);
+        if !shape.is_valid() || shape.is_empty_composite() {
+            return 0;
+        }
+
+        let before = self.snapshot(&[first]).remove(0);
+        let removed = self.remove_objects(&ids[1..]);
+        self.roi_mut(first).unwrap().shape = shape;
+        let after = self.object(first).unwrap().clone();
+        self.history.push(Command::Batch(vec![
+            Command::Remove(removed),
+            Command::Modify(vec![(before, after)]),
+        ]));
+        first
+    }
+
+    pub fn ungroup(&mut self, group: u64) -> bool {
+        let members: Vec<u64> = self
+            .objects
+            .iter()
+            .filter(|o| o.group == Some(group))
+            .map(|o| o.id)
+            .collect();
+        if members.is_empty() {
+            return false;
+        }
+        self.update_targets(group, |o| o.group = None)
+    }
+
+    pub fn set_layer_visible(&mut self, layer: IrisLayer, visible: bool) {
+        self.layers[layer as usize].visible = visible;
+        self.revision += 1;
+        self.drop_uneditable_selection();
+    }
+
+    pub fn set_layer_locked(&mut self, layer: IrisLayer, locked: bool) {
+        self.layers[layer as usize].locked = locked;
+        self.drop_uneditable_selection();
+    }
+
+    pub fn layer_visible(&self, layer: IrisLayer) -> bool {
+        self.layers[layer as usize].visible
+    }
+
+    // 可被鼠标命中和编辑：对象和所在图层都可见且未锁定，HUD 对象不参与交互
+    fn editable(&self, object: &SceneObject) -> bool {
+        let layer = self.layers[object.layer as usize];
+        object.visible
+            && !object.locked
+            && layer.visible
+            && !layer.locked
+            && matches!(object.layer, IrisLayer::Roi | IrisLayer::Annotation)
+    }
+
+    fn drop_uneditable_selection(&mut self) {
+        let Some(id) = self.selected else {
+            return;
+        };
+        if !self.object(id).is_some_and(|o| self.editable(o)) {
+            self.selected = None;
+            self.revision += 1;
+        }
+    }
+
+    pub fn selected(&self) -> Option<u64> {
+        self.selected
+    }
+
+    // 选择 ROI，传 0 取消选择；选中状态会显示编辑手柄，锁定或隐藏的对象不能选中
+    pub fn select(&mut self, id: u64) -> bool {
+        let selected = (id != 0).then_some(id);
+        if selected.is_some() && !self.object(id).is_some_and(|o| self.editable(o)) {
+            return false;
+        }
+        if self.selected != selected {
+            self.selected = selected;
+            self.revision += 1;
+        }
+        true
+    }
+
+    // 切换工具会放弃进行中的创建
+    pub fn set_tool(&mut self, tool: IrisRoiTool) {
+        if let Some(PolygonDraft { id: Some(id), .. }) = self.polygon_draft.take() {
+            self.remove_objects(&[id]);
+        }
+        if let Drag::Create { id: Some(id), .. } = std::mem::replace(&mut self.drag, Drag::Idle) {
+            self.remove_objects(&[id]);
+        }
+        self.tool = tool;
+    }
+
+    pub fn mouse_down(
+        &mut self,
+        view: &mut ViewTransform,
+        screen: DVec2,
+        button: IrisMouseButton,
+        modifiers: u32,
+    ) -> IrisMouseResult {
+        let p = view.screen_to_image(screen);
+        match button {
+            IrisMouseButton::Middle => {
+                self.drag = Drag::Pan { last: screen };
+                return result(IrisCursor::Pan, IrisRoiEvent::None, 0);
+            }
+            // 右键结束多边形 / 折线
+            IrisMouseButton::Right => return self.finish_polygon(),
+            IrisMouseButton::Left => {}
+        }
+
+        match self.tool {
+            IrisRoiTool::Select => {}
+            IrisRoiTool::Polygon | IrisRoiTool::Angle | IrisRoiTool::Polyline => {
+                return self.polygon_click(view, screen, p);
+            }
+            tool => {
+                self.drag = Drag::Create {
+                    tool,
+                    start: p,
+                    id: None,
+                };
+                return result(IrisCursor::Crosshair, IrisRoiEvent::None, 0);
+            }
+        }
+
+        let Some((id, mut target, mut cursor)) = self.hit_test(view, screen) else {
+            // 点在空白处：取消选择并拖动平移
+            self.drag = Drag::Pan { last: screen };
+            let event = if self.selected.is_some() {
+                self.select(0);
+                IrisRoiEvent::SelectionChanged
+            } else {
+                IrisRoiEvent::None
+            };
+            return result(IrisCursor::Pan, event, 0);
+        };
+
+        // 拖动分组成员的主体时整组平移，其余情况只修改命中的对象
+        let group = match self.object(id).and_then(|o| o.group) {
+            Some(group) if target == DragTarget::Body => self
+                .objects
+                .iter()
+                .filter(|o| o.id != id && o.group == Some(group) && self.editable(o))
+                .map(|o| o.id)
+                .collect(),
+            _ => Vec::new(),
+        };
+        let before = self.snapshot(&[&[id], group.as_slice()].concat());
+
+        // 多边形顶点编辑：Shift 单击删除顶点，Ctrl 单击边插入顶点并直接拖动
+        let mut vertex_event = IrisRoiEvent::None;
+        if let Some(RoiShape::Polygon { points }) = self.roi(id).map(|r| &r.shape) {
+            let mut points = points.clone();
+            match target {
+                DragTarget::Handle(HandleKind::Vertex(i))
+                    if modifiers & IRIS_MOD_SHIFT != 0 && points.len() > 3 =>
+                {
+                    points.remove(i);
+                    self.roi_mut(id).unwrap().shape = RoiShape::Polygon { points };
+                    self.record_modify(before);
+                    return result(cursor, IrisRoiEvent::Changed, id);
+                }
+                DragTarget::Body if modifiers & IRIS_MOD_CTRL != 0 => {
+                    if let Some((index, _)) = nearest_edge(&points, p) {
+                        points.insert(index, [p.x, p.y]);
+                        self.roi_mut(id).unwrap().shape = RoiShape::Polygon { points };
+                        target = DragTarget::Handle(HandleKind::Vertex(index));
+                        cursor = IrisCursor::Vertex;
+                        vertex_event = IrisRoiEvent::Changing;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let event = if self.selected != Some(id) {
+            self.select(id);
+            IrisRoiEvent::SelectionChanged
+        } else {
+            vertex_event
+        };
+        let original = self.roi(id).unwrap().shape.clone();
+        let group = group
+            .into_iter()
+            .filter_map(|member| Some((member, self.roi(member)?.shape.clone())))
+            .collect();
+        self.drag = Drag::Edit {
+            before,
+            id,
+            target,
+            original,
+            group,
+            start: p,
+            cursor,
+            changed: vertex_event != IrisRoiEvent::None,
+        };
+        result(cursor, event, id)
+    }
+
+    pub fn mouse_move(&mut self, view: &mut ViewTransform, screen: DVec2) -> IrisMouseResult {
+        let p = view.screen_to_image(screen);
+        match &mut self.drag {
+            Drag::Pan { last } => {
+                let delta = screen - *last;
+                *last = screen;
+                view.pan(delta.x, delta.y);
+                result(IrisCursor::Pan, IrisRoiEvent::None, 0)
+            }
+            Drag::Edit {
+                id,
+                target,
+                original,
+                group,
+                start,
+                cursor,
+                changed,
+                ..
+            } => {
+                let delta = p - *start;
+                let shape = match *target {
+                    DragTarget::Body => {
+                        let mut shape = original.clone();
+                        shape.translate(delta);
+                        shape
+                    }
+                    DragTarget::Handle(kind) => drag_handle(original, kind, p),
+                };
+                let (id, cursor) = (*id, *cursor);
+                if !shape.is_valid() {
+                    return result(cursor, IrisRoiEvent::None, id);
+                }
+                *changed = true;
+                let members: Vec<(u64, RoiShape)> = group
+                    .iter()
+                    .map(|(member, original)| {
+                        let mut shape = original.clone();
+                        shape.translate(delta);
+                        (*member, shape)
+                    })
+                    .collect();
+                for (member, shape) in std::iter::once((id, shape)).chain(members) {
+                    if let Some(roi) = self.roi_mut(member) {
+                        roi.shape = shape;
+                    }
+                }
+                result(cursor, IrisRoiEvent::Changing, id)
+            }
+            Drag::Create { tool, start, id } => {
+                let Some(shape) = create_shape(*tool, *start, p) else {
+                    return result(IrisCursor::Crosshair, IrisRoiEvent::None, 0);
+                };
+                let id = match *id {
+                    Some(id) => {
+                        if let Some(roi) = self.roi_mut(id) {
+                            roi.shape = shape;
+                        }
+                        id
+                    }
+                    None => {
+                        let layer = layer_for(&shape);
+                        let new_id = self.push_object(
+                            Roi {
+                                shape,
+                                style: RoiStyle::default(),
+                            },
+                            layer,
+                        );
+                        if let Drag::Create { id, .. } = &mut self.drag {
+                            *id = Some(new_id);
+                        }
+                        new_id
+                    }
+                };
+                result(IrisCursor::Crosshair, IrisRoiEvent::Changing, id)
+            }
+            Drag::Idle => {
+                if self.polygon_draft.is_some() {
+                    let id = self.update_polygon_preview(p);
+                    return result(IrisCursor::Crosshair, IrisRoiEvent::None, id);
+                }
+                if self.tool != IrisRoiTool::Select {
+                    return result(IrisCursor::Crosshair, IrisRoiEvent::None, 0);
+                }
+                self.hover(view, screen)
+            }
+        }
+    }
+
+    pub fn mouse_up(
+        &mut self,
+        view: &mut ViewTransform,
+        screen: DVec2,
+        button: IrisMouseButton,
+    ) -> IrisMouseResult {
+        if button == IrisMouseButton::Right {
+            return self.hover(view, screen);
+        }
+        match std::mem::replace(&mut self.drag, Drag::Idle) {
+            // 整个拖动过程合并为一条历史
+            Drag::Edit {
+                before,
+                id,
+                cursor,
+                changed: true,
+                ..
+            } => {
+                self.record_modify(before);
+                result(cursor, IrisRoiEvent::Changed, id)
+            }
+            // 创建完成后选中新形状并回到选择工具
+            Drag::Create { id: Some(id), .. } => {
+                self.record_add(id);
+                self.select(id);
+                self.tool = IrisRoiTool::Select;
+                result(IrisCursor::Arrow, IrisRoiEvent::Created, id)
+            }
+            _ => self.hover(view, screen),
+        }
+    }
+
+    // 滚轮以指针为锚点缩放，delta 为 WPF 的滚轮增量 (一格 120)
+    pub fn mouse_wheel(
+        &mut self,
+        view: &mut ViewTransform,
+        screen: DVec2,
+        delta: f64,
+    ) -> IrisMouseResult {
+        view.zoom_at(screen.x, screen.y, 1.2f64.powf(delta / 120.0));
+        self.hover(view, screen)
+    }
+
+    // 无按键移动时的光标提示
+    fn hover(&self, view: &ViewTransform, screen: DVec2) -> IrisMouseResult {
+        if self.tool != IrisRoiTool::Select {
+            return result(IrisCursor::Crosshair, IrisRoiEvent::None, 0);
+        }
+        match self.hit_test(view, screen) {
+            Some((id, _, cursor)) => result(cursor, IrisRoiEvent::None, id),
+            None => result(IrisCursor::Arrow, IrisRoiEvent::None, 0),
+        }
+    }
+
+    // 命中测试：先测选中 ROI 的手柄，再从上到下测 ROI 的内部和轮廓
+    fn hit_test(
+        &self,
+        view: &ViewTransform,
+        screen: DVec2,
+    ) -> Option<(u64, DragTarget, IrisCursor)> {
+        let zoom = view.zoom();
+        let p = view.screen_to_image(screen);
+        if let Some(id) = self.selected {
+            if let Some(roi) = self.object(id).filter(|o| self.editable(o)).map(|o| &o.roi) {
+                let center = view.image_to_screen(roi.shape.center());
+                for handle in handles(&roi.shape) {
+                    let position = view.image_to_screen(handle.position(zoom));
+                    if position.distance(screen) <= HANDLE_HIT_RADIUS {
+                        let cursor = match handle.kind {
+                            HandleKind::Rotate | HandleKind::StartAngle | HandleKind::EndAngle => {
+                                IrisCursor::Rotate
+                            }
+                            HandleKind::Vertex(_) => IrisCursor::Vertex,
+                            _ => resize_cursor(position - center),
+                        };
+                        return Some((id, DragTarget::Handle(handle.kind), cursor));
+                    }
+                }
+            }
+        }
+        let tolerance = (0.5 / zoom) as f32;
+        // 注释图层在 ROI 图层之上，先测
+        let mut candidates: Vec<&SceneObject> =
+            self.objects.iter().filter(|o| self.editable(o)).collect();
+        candidates.sort_by_key(|o| o.layer as u32);
+        candidates
+            .into_iter()
+            .rev()
+            .find(|o| {
+                o.roi.shape.contains(p, tolerance)
+                    || o.roi.shape.distance_to_outline(p, tolerance) * zoom <= OUTLINE_HIT_TOLERANCE
+            })
+            .map(|o| (o.id, DragTarget::Body, IrisCursor::Move))
+    }
+
+    // 逐点工具单击：添加顶点；多边形点回第一个顶点、折线点回最后一个顶点时结束，
+    // 角度点满三个点时结束
+    fn polygon_click(&mut self, view: &ViewTransform, screen: DVec2, p: DVec2) -> IrisMouseResult {
+        let tool = self.tool;
+        let draft = self.polygon_draft.get_or_insert(PolygonDraft {
+            tool,
+            points: Vec::new(),
+            id: None,
+        });
+        let close = match tool {
+            IrisRoiTool::Polygon => draft.points.first().filter(|_| draft.points.len() >= 3),
+            IrisRoiTool::Polyline => draft.points.last().filter(|_| draft.points.len() >= 2),
+            _ => None,
+        };
+        if close.is_some_and(|&q| {
+            view.image_to_screen(DVec2::from(q)).distance(screen) <= HANDLE_HIT_RADIUS
+        }) {
+            return self.finish_polygon();
+        }
+        if tool == IrisRoiTool::Angle && draft.points.len() == 2 {
+            // 预览即为完整的角度，先保证草稿对象存在再结束
+            self.update_polygon_preview(p);
+            if let Some(draft) = &mut self.polygon_draft {
+                draft.points.push([p.x, p.y]);
+            }
+            return self.finish_polygon();
+        }
+        draft.points.push([p.x, p.y]);
+        let id = self.update_polygon_preview(p);
+        result(IrisCursor::Crosshair, IrisRoiEvent::None, id)
+    }
+
+    // 预览形状为已确定顶点加上指针位置，形状有效后才加入场景
+    fn update_polygon_preview(&mut self, p: DVec2) -> u64 {
+        let Some(draft) = &self.polygon_draft else {
+            return 0;
+        };
+        let mut points = draft.points.clone();
+        points.push([p.x, p.y]);
+        let shape = draft.shape(points);
+        if !shape.is_valid() {
+            return draft.id.unwrap_or(0);
+        }
+        match draft.id {
+            Some(id) => {
+                if let Some(roi) = self.roi_mut(id) {
+                    roi.shape = shape;
+                }
+                id
+            }
+            None => {
+                let layer = layer_for(&shape);
+                let id = self.push_object(
+                    Roi {
+                        shape,
+                        style: RoiStyle::default(),
+                    },
+                    layer,
+                );
+                if let Some(draft) = &mut self.polygon_draft {
+                    draft.id = Some(id);
+                }
+                id
+            }
+        }
+    }
+
+    fn finish_polygon(&mut self) -> IrisMouseResult {
+        let Some(draft) = self.polygon_draft.take() else {
+            return result(IrisCursor::Arrow, IrisRoiEvent::None, 0);
+        };
+        let Some(id) = draft.id else {
+            return result(IrisCursor::Arrow, IrisRoiEvent::None, 0);
+        };
+        let shape = draft.shape(draft.points.clone());
+        // 角度不足三点时 shape() 给的是预览用的折线，不能当作折线对象提交
+        let incomplete_angle = draft.tool == IrisRoiTool::Angle && draft.points.len() != 3;
+        if incomplete_angle || !shape.is_valid() {
+            self.remove_objects(&[id]);
+            return result(IrisCursor::Arrow, IrisRoiEvent::None, 0);
+        }
+        if let Some(roi) = self.roi_mut(id) {
+            roi.shape = shape;
+        }
+        self.record_add(id);
+        self.select(id);
+        self.tool = IrisRoiTool::Select;
+        result(IrisCursor::Arrow, IrisRoiEvent::Created, id)
+    }
+}
+
+fn result(cursor: IrisCursor, event: IrisRoiEvent, roi_id: u64) -> IrisMouseResult {
+    IrisMouseResult {
+        cursor,
+        event,
+        roi_id,
+    }
+}
@@ -0,0 +1,240 @@
+use crate::common::math::{Calibration, ViewTransform};
+use crate::common::types::{
+    IrisLayer, IrisMeasureKind, IrisMeasurement, IrisTextAnchor, IrisTextSpace,
+};
+use crate::scene::manager::SceneManager;
+use crate::scene::roi::RoiShape;
+use crate::scene::text::{TextItem, TextStyle};
+use glam::DVec2;
+
+// 测量值和 ROI 名称标签的外观 (屏幕像素)，文字颜色取对象的描边色
+const LABEL_SIZE: f32 = 14.0;
+const LABEL_PADDING: f32 = 3.0;
+const LABEL_BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+
+// 九宫格锚点，按 行 * 3 + 列 排列，与 IrisTextAnchor 的取值一致
+const ANCHORS: [IrisTextAnchor; 9] = [
+    IrisTextAnchor::TopLeft,
+    IrisTextAnchor::Top,
+    IrisTextAnchor::TopRight,
+    IrisTextAnchor::Left,
+    IrisTextAnchor::Center,
+    IrisTextAnchor::Right,
+    IrisTextAnchor::BottomLeft,
+    IrisTextAnchor::Bottom,
+    IrisTextAnchor::BottomRight,
+];
+
+// 测量形状的测量值，有标定时同时给出世界坐标 (毫米) 下的值；其他形状返回 None
+pub fn measurement(shape: &RoiShape, calibration: Option<&Calibration>) -> Option<IrisMeasurement> {
+    let kind = match shape {
+        RoiShape::Distance { .. } => IrisMeasureKind::Distance,
+        RoiShape::Angle { .. } => IrisMeasureKind::Angle,
+        RoiShape::Polyline { .. } => IrisMeasureKind::PolylineLength,
+        _ => return None,
+    };
+    let value = shape.measure(|p| p)?;
+    let world_value = match calibration {
+        Some(calibration) => shape.measure(|p| calibration.image_to_world(p))?,
+        None => value,
+    };
+    Some(IrisMeasurement {
+        kind,
+        value,
+        world_value,
+        calibrated: calibration.is_some(),
+    })
+}
+
+fn format(m: &IrisMeasurement) -> String {
+    match (m.kind, m.calibrated) {
+        (IrisMeasureKind::Angle, _) => format!("{:.1}°", m.world_value),
+        (_, true) => format!("{:.3} mm", m.world_value),
+        (_, false) => format!("{:.2} px", m.value),
+    }
+}
+
+// 每帧为可见的测量对象生成锚定文字，大小不随缩放变化
+pub fn labels(scene: &SceneManager, view: &ViewTransform) -> Vec<TextItem> {
+    let mut items = Vec::new();
+    for layer in [IrisLayer::Roi, IrisLayer::Annotation] {
+        for object in scene.visible_objects(layer) {
+            let shape = &object.roi.shape;
+            let Some(m) = measurement(shape, view.calibration()) else {
+                continue;
+            };
+            let (position, direction) = placement(shape, view);
+            let mut color = object.roi.style.stroke_color;
+            color[3] *= object.opacity;
+            let mut background = LABEL_BACKGROUND;
+            background[3] *= object.opacity;
+            items.push(TextItem {
+                id: 0,
+                text: format(&m),
+                position,
+                space: IrisTextSpace::Anchored,
+                style: TextStyle {
+                    size: LABEL_SIZE,
+                    color,
+                    background,
+                    padding: LABEL_PADDING,
+                    anchor: anchor_facing(direction),
+                },
+            });
+        }
+    }
+    items
+}
+
+// 每帧为有名称的可见 ROI 生成锚定的名称标签，放在形状屏幕外接框的上沿正中
+pub fn name_labels(scene: &SceneManager, view: &ViewTransform) -> Vec<TextItem> {
+    // 展平精度取半个屏幕像素
+    let tolerance = (0.5 / view.zoom()) as f32;
+    let mut items = Vec::new();
+    for layer in [IrisLayer::Roi, IrisLayer::Annotation] {
+        for object in scene.visible_objects(layer) {
+            if object.name.is_empty() {
+                continue;
+            }
+            let points = object.roi.shape.outline(tolerance).into_iter().flatten();
+            let (min, max) = points.map(|p| view.image_to_screen(p)).fold(
+                (DVec2::splat(f64::INFINITY), DVec2::splat(f64::NEG_INFINITY)),
+                |(min, max), p| (min.min(p), max.max(p)),
+            );
+            if !min.is_finite() || !max.is_finite() {
+                continue;
+            }
+            let top = DVec2::new((min.x + max.x) * 0.5, min.y);
+            let mut color = object.roi.style.stroke_color;
+            color[3] *= object.opacity;
+            let mut background = LABEL_BACKGROUND;
+            background[3] *= object.opacity;
+            items.push(TextItem {
+                id: 0,
+                text: object.name.clone(),
+                position: view.screen_to_image(top),
+                space: IrisTextSpace::Anchored,
+                style: TextStyle {
+                    size: LABEL_SIZE,
+                    color,
+                    background,
+                    padding: LABEL_PADDING,
+                    anchor: IrisTextAnchor::Bottom,
+                },
+            });
+        }
+    }
+    items
+}
+
+// 标签位置 (图像坐标) 和文字相对该点展开的屏幕方向：
+// 距离在中点朝屏幕上方一侧，角度在顶点朝角的外侧，折线在末点沿最后一段延伸
+fn placement(shape: &RoiShape, view: &ViewTransform) -> (DVec2, DVec2) {
+    let screen = |p: &[f64; 2]| view.image_to_screen(DVec2::from(*p));
+    match shape {
+        RoiShape::Distance { points } => {
+            let (a, b) = (screen(&points[0]), screen(&points[1]));
+            let normal = (b - a).perp().normalize_or_zero();
+            let normal = if normal.y > 0.0 { -normal } else { normal };
+            (
+                (DVec2::from(points[0]) + DVec2::from(points[1])) * 0.5,
+                normal,
+            )
+        }
+        RoiShape::Angle { points } => {
+            let vertex = screen(&points[1]);
+            let bisector = (screen(&points[0]) - vertex).normalize_or_zero()
+                + (screen(&points[2]) - vertex).normalize_or_zero();
+            (
+                DVec2::from(points[1]),
+                -bisector.try_normalize().unwrap_or(DVec2::NEG_Y),
+            )
+        }
+        RoiShape::Polyline { points } => {
+            let n = points.len();
+            let direction = screen(&points[n - 1]) - screen(&points[n - 2]);
+            (DVec2::from(points[n - 1]), direction.normalize_or_zero())
+        }
+        _ => (shape.center(), DVec2::ZERO),
+    }
+}
+
+// 让文字框朝 direction 方向展开：例如朝右时锚点取框的左边
+fn anchor_facing(direction: DVec2) -> IrisTextAnchor {
+    let pick = |d: f64| match d {
+        d if d > 0.38 => 0,
+        d if d < -0.38 => 2,
+        _ => 1,
+    };
+    ANCHORS[pick(direction.y) * 3 + pick(direction.x)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, measurement};
+    use crate::common::math::Calibration;
+    use crate::common::types::IrisMeasureKind;
+    use crate::scene::roi::RoiShape;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn distance() {
+        let shape = RoiShape::Distance {
+            points: vec![[10.0, 20.0], [13.0, 24.0]],
+        };
+        let m = measurement(&shape, None).unwrap();
+        assert_eq!(m.kind, IrisMeasureKind::Distance);
+        assert!(close(m.value, 5.0) && close(m.world_value, 5.0) && !m.calibrated);
+        assert_eq!(format(&m), "5.00 px");
+
+        let calibration = Calibration::PixelSize { mm_per_pixel: 0.01 };
+        let m = measurement(&shape, Some(&calibration)).unwrap();
+        assert!(close(m.value, 5.0) && close(m.world_value, 0.05) && m.calibrated);
+        assert_eq!(format(&m), "0.050 mm");
+    }
+
+    #[test]
+    fn angle() {
+        let shape = RoiShape::Angle {
+            points: vec![[10.0, 0.0], [0.0, 0.0], [0.0, -10.0]],
+        };
+        let m = measurement(&shape, None).unwrap();
+        assert_eq!(m.kind, IrisMeasureKind::Angle);
+        assert!(close(m.value, 90.0));
+        assert_eq!(format(&m), "90.0°");
+
+        // 各向异性标定下角度在世界坐标里测量：(1, 1) 方向的 45° 变为 atan(1 / 2)
+        let shape = RoiShape::Angle {
+            points: vec![[1.0, 0.0], [0.0, 0.0], [1.0, 1.0]],
+        };
+        let calibration = Calibration::Affine {
+            matrix: [2.0, 0.0, 5.0, 0.0, 1.0, 7.0],
+        };
+        let m = measurement(&shape, Some(&calibration)).unwrap();
+        assert!(close(m.value, 45.0));
+        assert!(close(m.world_value, 0.5f64.atan().to_degrees()));
+    }
+
+    #[test]
+    fn polyline_length() {
+        let shape = RoiShape::Polyline {
+            points: vec![[0.0, 0.0], [3.0, 4.0], [3.0, 10.0]],
+        };
+        let m = measurement(&shape, None).unwrap();
+        assert_eq!(m.kind, IrisMeasureKind::PolylineLength);
+        assert!(close(m.value, 11.0));
+    }
+
+    #[test]
+    fn regions_have_no_measurement() {
+        let shape = RoiShape::Circle {
+            cx: 0.0,
+            cy: 0.0,
+            radius: 5.0,
+        };
+        assert!(measurement(&shape, None).is_none());
+    }
+}
@@ -1 +1,7 @@
-
+pub mod history;
+pub mod interaction;
+pub mod manager;
+pub mod measure;
+pub mod recipe;
+pub mod roi;
+pub mod text;
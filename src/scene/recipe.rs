@@ -0,0 +1,383 @@
+use crate::common::error::IrisError;
+use crate::common::math::Calibration;
+use crate::common::types::{IrisLayer, IrisStatus};
+use crate::scene::manager::{SceneManager, SceneObject};
+use crate::scene::measure;
+use crate::scene::roi::{Roi, RoiShape, RoiStyle};
+use std::fmt;
+use std::path::Path;
+
+// 配方 JSON 的版本号，不兼容的结构调整时递增；加载时拒绝比它新的文件
+pub const RECIPE_VERSION: u32 = 1;
+
+// 配方文件：
+// { "version": 1, "rois": [ { "id": 1, "name": "...", "tags": [...], "layer": "roi",
+//   "visible": true, "locked": false, "opacity": 1.0, "group": null,
+//   "style": { "stroke_color": [r, g, b, a], "fill_color": [...], "stroke_width": 2.0 },
+//   "shape": { "type": "rect", "x": 0.0, "y": 0.0, "width": 10.0, "height": 10.0 } } ] }
+// 组合形状保存操作数树: { "type": "composite", "op": "difference", "operands": [ shape, ... ] }
+// 测量对象: { "type": "distance" | "angle" | "polyline", "points": [[x, y], ...] }
+// 几何为图像坐标 (HUD 图层为屏幕坐标)，颜色为 0~1 的非预乘 RGBA
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RecipeFile {
+    version: u32,
+    rois: Vec<RoiRecord>,
+}
+
+// 除 shape 外的字段都可省略，省略时取新建 ROI 的默认值
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RoiRecord {
+    id: u64,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default = "default_layer")]
+    layer: IrisLayer,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default = "default_opacity")]
+    opacity: f32,
+    #[serde(default)]
+    group: Option<u64>,
+    #[serde(default)]
+    style: RoiStyle,
+    shape: RoiShape,
+}
+
+// 世界坐标几何导出：
+// { "version": 1, "unit": "mm", "calibration": { ... }, "rois": [ { "id": 1, "name": "...", "tags": [...],
+//   "layer": "roi", "closed": true, "rings": [ [[x, y], ...], ... ], "measurement": null } ] }
+// 未标定时 unit 为 "px"、calibration 为 null；closed 为 false 的是测量折线，measurement 为其测量值
+#[derive(serde::Serialize)]
+struct WorldFile<'a> {
+    version: u32,
+    unit: &'static str,
+    calibration: Option<&'a Calibration>,
+    rois: Vec<WorldRecord<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct WorldRecord<'a> {
+    id: u64,
+    name: &'a str,
+    tags: &'a [String],
+    layer: IrisLayer,
+    closed: bool,
+    rings: Vec<Vec<[f64; 2]>>,
+    measurement: Option<f64>,
+}
+
+// 世界坐标导出时曲线的展平精度 (图像像素)
+const WORLD_TOLERANCE: f32 = 0.05;
+
+fn default_layer() -> IrisLayer {
+    IrisLayer::Roi
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+#[derive(Debug)]
+pub enum RecipeError {
+    Io(std::io::Error),
+    // JSON 语法错误或字段类型不符
+    Syntax(serde_json::Error),
+    UnsupportedVersion(u32),
+    // 第 index 个 ROI 的内容无效
+    InvalidRoi { index: usize, reason: &'static str },
+}
+
+impl RecipeError {
+    pub fn status(&self) -> IrisStatus {
+        match self {
+            RecipeError::Io(_) => IrisStatus::IoError,
+            RecipeError::Syntax(_) => IrisStatus::SyntaxError,
+            RecipeError::UnsupportedVersion(_) => IrisStatus::UnsupportedVersion,
+            RecipeError::InvalidRoi { .. } => IrisStatus::InvalidRoi,
+        }
+    }
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecipeError::Io(e) => write!(f, "读写配方文件失败: {}", e),
+            RecipeError::Syntax(e) => write!(f, "配方 JSON 格式错误: {}", e),
+            RecipeError::UnsupportedVersion(v) => {
+                write!(f, "不支持的配方版本 {} (当前为 {})", v, RECIPE_VERSION)
+            }
+            RecipeError::InvalidRoi { index, reason } => {
+                write!(f, "第 {} 个 ROI 无效: {}", index, reason)
+            }
+        }
+    }
+}
+
+impl From<RecipeError> for IrisError {
+    fn from(e: RecipeError) -> Self {
+        IrisError::new(e.status(), e.to_string())
+    }
+}
+
+impl SceneManager {
+    // 导出全部对象 (按叠放顺序) 为配方 JSON
+    pub fn to_json(&self) -> String {
+        let file = RecipeFile {
+            version: RECIPE_VERSION,
+            rois: self
+                .objects()
+                .map(|o| RoiRecord {
+                    id: o.id,
+                    name: o.name.clone(),
+                    tags: o.tags.clone(),
+                    layer: o.layer,
+                    visible: o.visible,
+                    locked: o.locked,
+                    opacity: o.opacity,
+                    group: o.group,
+                    style: o.roi.style,
+                    shape: o.roi.shape.clone(),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&file).expect("配方序列化不会失败")
+    }
+
+    // 导出图像空间对象在世界坐标下的轮廓，HUD 对象不在图像坐标下，不导出
+    pub fn to_world_json(&self, calibration: Option<&Calibration>) -> String {
+        let to_world = |p: glam::DVec2| match calibration {
+            Some(calibration) => calibration.image_to_world(p),
+            None => p,
+        };
+        let file = WorldFile {
+            version: RECIPE_VERSION,
+            unit: if calibration.is_some() { "mm" } else { "px" },
+            calibration,
+            rois: self
+                .objects()
+                .filter(|o| o.layer != IrisLayer::Hud)
+                .map(|o| WorldRecord {
+                    id: o.id,
+                    name: &o.name,
+                    tags: &o.tags,
+                    layer: o.layer,
+                    closed: !o.roi.shape.is_measurement(),
+                    rings: o
+                        .roi
+                        .shape
+                        .outline(WORLD_TOLERANCE)
+                        .into_iter()
+                        .map(|ring| ring.into_iter().map(|p| to_world(p).into()).collect())
+                        .collect(),
+                    measurement: measure::measurement(&o.roi.shape, calibration)
+                        .map(|m| m.world_value),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&file).expect("几何序列化不会失败")
+    }
+
+    // 从配方 JSON 加载，替换当前全部对象；内容有任何错误时场景保持不变
+    pub fn load_json(&mut self, json: &str) -> Result<(), RecipeError> {
+        // 先只解析版本号，避免新版本的结构变化被报告成语法错误
+        #[derive(serde::Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let Version { version } = serde_json::from_str(json).map_err(RecipeError::Syntax)?;
+        if version > RECIPE_VERSION {
+            return Err(RecipeError::UnsupportedVersion(version));
+        }
+        let file: RecipeFile = serde_json::from_str(json).map_err(RecipeError::Syntax)?;
+
+        let mut objects: Vec<SceneObject> = Vec::with_capacity(file.rois.len());
+        for (index, record) in file.rois.into_iter().enumerate() {
+            let invalid = |reason| Err(RecipeError::InvalidRoi { index, reason });
+            // u64::MAX 保留，否则加载后无法再分配新 ID
+            if record.id == 0 || record.id == u64::MAX || objects.iter().any(|o| o.id == record.id)
+            {
+                return invalid("ID 为 0、u64::MAX 或重复");
+            }
+            if !record.shape.is_valid() {
+                return invalid("几何参数无效");
+            }
+            if record.layer == IrisLayer::Image {
+                return invalid("不能放在 Image 图层");
+            }
+            if !(0.0..=1.0).contains(&record.opacity) {
+                return invalid("不透明度超出 0~1");
+            }
+            let style = record.style;
+            let colors = style.stroke_color.iter().chain(&style.fill_color);
+            if !style.stroke_width.is_finite() || style.stroke_width < 0.0 {
+                return invalid("描边宽度无效");
+            }
+            if !colors.into_iter().all(|c| (0.0..=1.0).contains(c)) {
+                return invalid("颜色分量超出 0~1");
+            }
+            objects.push(SceneObject {
+                id: record.id,
+                roi: Roi {
+                    shape: record.shape,
+                    style,
+                },
+                name: record.name,
+                tags: record.tags,
+                layer: record.layer,
+                visible: record.visible,
+                locked: record.locked,
+                opacity: record.opacity,
+                group: record.group,
+            });
+        }
+        // 分组 ID 与对象 ID 共用 ID 空间，不能为 0、u64::MAX 或指向某个对象
+        if let Some(index) = objects.iter().position(|o| {
+            o.group
+                .is_some_and(|g| g == 0 || g == u64::MAX || objects.iter().any(|m| m.id == g))
+        }) {
+            return Err(RecipeError::InvalidRoi {
+                index,
+                reason: "分组 ID 无效",
+            });
+        }
+        self.load_objects(objects);
+        Ok(())
+    }
+
+    pub fn save_file(&self, path: &Path) -> Result<(), RecipeError> {
+        std::fs::write(path, self.to_json()).map_err(RecipeError::Io)
+    }
+
+    pub fn load_file(&mut self, path: &Path) -> Result<(), RecipeError> {
+        let json = std::fs::read_to_string(path).map_err(RecipeError::Io)?;
+        self.load_json(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecipeError, RECIPE_VERSION};
+    use crate::common::types::{IrisLayer, IrisRoiBoolOp};
+    use crate::scene::manager::{SceneManager, SceneObject};
+    use crate::scene::roi::{Roi, RoiShape, RoiStyle};
+
+    fn roi(shape: RoiShape) -> Roi {
+        Roi {
+            shape,
+            style: RoiStyle::default(),
+        }
+    }
+
+    fn rect(x: f64) -> RoiShape {
+        RoiShape::Rect {
+            x,
+            y: 0.0,
+            width: 40.0,
+            height: 30.0,
+        }
+    }
+
+    fn sample_scene() -> SceneManager {
+        let mut scene = SceneManager::new();
+        let a = scene.add_roi(roi(rect(0.0)));
+        let b = scene.add_roi(roi(RoiShape::Circle {
+            cx: 100.0,
+            cy: 50.0,
+            radius: 20.0,
+        }));
+        scene.add_roi(roi(RoiShape::composite(
+            IrisRoiBoolOp::Difference,
+            vec![rect(200.0), rect(220.0)],
+        )));
+        scene.add_roi(roi(RoiShape::Distance {
+            points: vec![[0.0, 0.0], [30.0, 40.0]],
+        }));
+        scene.set_name(a, "A");
+        scene.add_tag(a, "检测");
+        scene.set_locked(b, true);
+        scene.set_opacity(b, 0.5);
+        scene.group(&[a, b]);
+        scene
+    }
+
+    fn objects(scene: &SceneManager) -> Vec<SceneObject> {
+        scene.objects().cloned().collect()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let scene = sample_scene();
+        let json = scene.to_json();
+        let mut loaded = SceneManager::new();
+        loaded.load_json(&json).unwrap();
+        assert_eq!(objects(&loaded), objects(&scene));
+        assert_eq!(loaded.to_json(), json);
+        // 加载配方清空撤销历史
+        assert!(!loaded.can_undo());
+        let distance = loaded.objects().last().unwrap();
+        assert_eq!(distance.layer, IrisLayer::Annotation);
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let mut scene = sample_scene();
+        let before = objects(&scene);
+        // 新版本即使结构不同也报告为版本不支持，而不是语法错误
+        let json = format!(r#"{{ "version": {}, "shapes": 1 }}"#, RECIPE_VERSION + 1);
+        let error = scene.load_json(&json).unwrap_err();
+        assert!(matches!(error, RecipeError::UnsupportedVersion(v) if v == RECIPE_VERSION + 1));
+        assert_eq!(objects(&scene), before);
+    }
+
+    #[test]
+    fn rejects_invalid_content_without_changes() {
+        let mut scene = sample_scene();
+        let before = objects(&scene);
+        assert!(matches!(
+            scene.load_json("{ \"version\": 1, "),
+            Err(RecipeError::Syntax(_))
+        ));
+        let duplicate = r#"{ "version": 1, "rois": [
+            { "id": 1, "shape": { "type": "circle", "cx": 0.0, "cy": 0.0, "radius": 5.0 } },
+            { "id": 1, "shape": { "type": "circle", "cx": 9.0, "cy": 0.0, "radius": 5.0 } } ] }"#;
+        assert!(matches!(
+            scene.load_json(duplicate),
+            Err(RecipeError::InvalidRoi { index: 1, .. })
+        ));
+        assert_eq!(objects(&scene), before);
+    }
+
+    #[test]
+    fn rejects_max_ids() {
+        let mut scene = sample_scene();
+        let before = objects(&scene);
+        let max_id = format!(
+            r#"{{ "version": 1, "rois": [
+            {{ "id": {}, "shape": {{ "type": "circle", "cx": 0.0, "cy": 0.0, "radius": 5.0 }} }} ] }}"#,
+            u64::MAX
+        );
+        assert!(matches!(
+            scene.load_json(&max_id),
+            Err(RecipeError::InvalidRoi { index: 0, .. })
+        ));
+        let max_group = format!(
+            r#"{{ "version": 1, "rois": [
+            {{ "id": 1, "shape": {{ "type": "circle", "cx": 0.0, "cy": 0.0, "radius": 5.0 }} }},
+            {{ "id": 2, "group": {}, "shape": {{ "type": "circle", "cx": 9.0, "cy": 0.0, "radius": 5.0 }} }} ] }}"#,
+            u64::MAX
+        );
+        assert!(matches!(
+            scene.load_json(&max_group),
+            Err(RecipeError::InvalidRoi { index: 1, .. })
+        ));
+        assert_eq!(objects(&scene), before);
+    }
+}
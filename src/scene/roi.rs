@@ -0,0 +1,697 @@
+use crate::common::types::IrisRoiBoolOp;
+use glam::DVec2;
+use i_overlay::core::fill_rule::FillRule as OverlayFillRule;
+use i_overlay::core::overlay_rule::OverlayRule;
+use i_overlay::float::simplify::SimplifyShape;
+use i_overlay::float::single::SingleFloatOverlay;
+use lyon::geom::Arc;
+use lyon::math::{point, vector, Angle, Box2D, Point};
+use lyon::path::iterator::PathIterator;
+use lyon::path::{FillRule, Path, PathEvent, Polygon, Winding};
+use std::sync::OnceLock;
+
+// 组合形状展平操作数的精度 (图像像素)，与掩膜的展平精度一致
+const COMPOSITE_TOLERANCE: f32 = 0.05;
+
+// ROI 几何形状，全部以图像坐标 (像素) 表示，角度为弧度
+// 图像坐标 y 轴向下，正角度在屏幕上表现为顺时针
+// 序列化时以 "type" 字段区分形状，字段名即配方 JSON 中的几何参数名
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoiShape {
+    // 轴对齐矩形，(x, y) 为左上角
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    // 旋转矩形：中心、半长和绕中心的旋转角
+    RotatedRect {
+        cx: f64,
+        cy: f64,
+        half_width: f64,
+        half_height: f64,
+        angle: f64,
+    },
+    Circle {
+        cx: f64,
+        cy: f64,
+        radius: f64,
+    },
+    Ellipse {
+        cx: f64,
+        cy: f64,
+        rx: f64,
+        ry: f64,
+        angle: f64,
+    },
+    // 圆环 / 扇环：sweep_angle 绝对值达到 2π 时为完整圆环
+    Annulus {
+        cx: f64,
+        cy: f64,
+        inner_radius: f64,
+        outer_radius: f64,
+        start_angle: f64,
+        sweep_angle: f64,
+    },
+    // 任意多边形，自动闭合
+    Polygon {
+        points: Vec<[f64; 2]>,
+    },
+    // 测量：两点距离 (2 个点)、三点角度 (3 个点，points[1] 为角的顶点)、折线长度 (至少 2 个点)
+    // 测量形状是不闭合的线，没有内部，不参与掩膜和布尔运算
+    Distance {
+        points: Vec<[f64; 2]>,
+    },
+    Angle {
+        points: Vec<[f64; 2]>,
+    },
+    Polyline {
+        points: Vec<[f64; 2]>,
+    },
+    // 布尔组合：操作数按顺序两两做区域运算，操作数本身也可以是组合
+    // rings 缓存运算结果，不写进配方 JSON
+    Composite {
+        op: IrisRoiBoolOp,
+        operands: Vec<RoiShape>,
+        #[serde(skip)]
+        rings: CompositeRings,
+    },
+}
+
+// 组合形状的运算结果 (外轮廓和洞)：创建时算好，平移时跟着平移，
+// 从 JSON 读入的组合在第一次使用时计算；渲染和命中测试不再重复做布尔运算
+#[derive(Clone, Debug, Default)]
+pub struct CompositeRings(OnceLock<Vec<Vec<[f64; 2]>>>);
+
+impl CompositeRings {
+    fn get(&self, op: IrisRoiBoolOp, operands: &[RoiShape]) -> &[Vec<[f64; 2]>] {
+        self.0.get_or_init(|| composite_rings(op, operands))
+    }
+
+    fn translate(&mut self, delta: DVec2) {
+        if let Some(rings) = self.0.get_mut() {
+            for p in rings.iter_mut().flatten() {
+                p[0] += delta.x;
+                p[1] += delta.y;
+            }
+        }
+    }
+}
+
+// 缓存完全由操作数决定，比较形状时不参与
+impl PartialEq for CompositeRings {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl RoiShape {
+    // 组合形状，立即做一次布尔运算并缓存结果
+    pub fn composite(op: IrisRoiBoolOp, operands: Vec<RoiShape>) -> Self {
+        let rings = CompositeRings::default();
+        rings.get(op, &operands);
+        RoiShape::Composite {
+            op,
+            operands,
+            rings,
+        }
+    }
+
+    // 组合形状的运算结果为空 (如互不相交的交集) 时返回 true，其余形状总是 false
+    pub fn is_empty_composite(&self) -> bool {
+        match self {
+            RoiShape::Composite {
+                op,
+                operands,
+                rings,
+            } => rings.get(*op, operands).is_empty(),
+            _ => false,
+        }
+    }
+
+    // 尺寸为正且所有数值有限才是有效形状
+    pub fn is_valid(&self) -> bool {
+        let finite = |values: &[f64]| values.iter().all(|v| v.is_finite());
+        match *self {
+            RoiShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => finite(&[x, y, width, height]) && width > 0.0 && height > 0.0,
+            RoiShape::RotatedRect {
+                cx,
+                cy,
+                half_width,
+                half_height,
+                angle,
+            } => {
+                finite(&[cx, cy, half_width, half_height, angle])
+                    && half_width > 0.0
+                    && half_height > 0.0
+            }
+            RoiShape::Circle { cx, cy, radius } => finite(&[cx, cy, radius]) && radius > 0.0,
+            RoiShape::Ellipse {
+                cx,
+                cy,
+                rx,
+                ry,
+                angle,
+            } => finite(&[cx, cy, rx, ry, angle]) && rx > 0.0 && ry > 0.0,
+            RoiShape::Annulus {
+                cx,
+                cy,
+                inner_radius,
+                outer_radius,
+                start_angle,
+                sweep_angle,
+            } => {
+                finite(&[cx, cy, inner_radius, outer_radius, start_angle, sweep_angle])
+                    && inner_radius >= 0.0
+                    && outer_radius > inner_radius
+                    && sweep_angle != 0.0
+            }
+            RoiShape::Polygon { ref points } => {
+                points.len() >= 3 && points.iter().all(|p| finite(p))
+            }
+            RoiShape::Distance { ref points } => {
+                points.len() == 2 && points.iter().all(|p| finite(p)) && points[0] != points[1]
+            }
+            RoiShape::Angle { ref points } => {
+                points.len() == 3
+                    && points.iter().all(|p| finite(p))
+                    && points[0] != points[1]
+                    && points[2] != points[1]
+            }
+            RoiShape::Polyline { ref points } => {
+                points.len() >= 2 && points.iter().all(|p| finite(p))
+            }
+            RoiShape::Composite { ref operands, .. } => {
+                operands.len() >= 2 && operands.iter().all(|o| o.is_valid() && !o.is_measurement())
+            }
+        }
+    }
+
+    pub fn is_measurement(&self) -> bool {
+        matches!(
+            self,
+            RoiShape::Distance { .. } | RoiShape::Angle { .. } | RoiShape::Polyline { .. }
+        )
+    }
+
+    // 测量值：距离和折线为长度，角度为 0~180 度；map 把图像坐标换算到测量单位
+    pub fn measure(&self, map: impl Fn(DVec2) -> DVec2) -> Option<f64> {
+        let points: Vec<DVec2> = match self {
+            RoiShape::Distance { points }
+            | RoiShape::Angle { points }
+            | RoiShape::Polyline { points } => points.iter().map(|&p| map(p.into())).collect(),
+            _ => return None,
+        };
+        match self {
+            RoiShape::Angle { .. } => {
+                let (a, b) = (points[0] - points[1], points[2] - points[1]);
+                Some(a.angle_between(b).abs().to_degrees())
+            }
+            _ => Some(points.windows(2).map(|w| w[0].distance(w[1])).sum()),
+        }
+    }
+
+    // 圆环和多边形可能带洞或自相交，按奇偶规则填充
+    // 组合形状的运算结果是外轮廓加洞，同样按奇偶规则
+    pub fn fill_rule(&self) -> FillRule {
+        match self {
+            RoiShape::Annulus { .. } | RoiShape::Polygon { .. } | RoiShape::Composite { .. } => {
+                FillRule::EvenOdd
+            }
+            _ => FillRule::NonZero,
+        }
+    }
+
+    // 构建 lyon 路径，供填充 / 描边细分使用
+    pub fn build_path(&self) -> Path {
+        let mut builder = Path::builder();
+        match *self {
+            RoiShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let rect = Box2D::new(
+                    point(x as f32, y as f32),
+                    point((x + width) as f32, (y + height) as f32),
+                );
+                builder.add_rectangle(&rect, Winding::Positive);
+            }
+            RoiShape::RotatedRect {
+                cx,
+                cy,
+                half_width,
+                half_height,
+                angle,
+            } => {
+                let corners = rotated_rect_corners(cx, cy, half_width, half_height, angle);
+                let points = corners.map(|[x, y]| point(x as f32, y as f32));
+                builder.add_polygon(Polygon {
+                    points: &points,
+                    closed: true,
+                });
+            }
+            RoiShape::Circle { cx, cy, radius } => {
+                builder.add_circle(
+                    point(cx as f32, cy as f32),
+                    radius as f32,
+                    Winding::Positive,
+                );
+            }
+            RoiShape::Ellipse {
+                cx,
+                cy,
+                rx,
+                ry,
+                angle,
+            } => {
+                builder.add_ellipse(
+                    point(cx as f32, cy as f32),
+                    vector(rx as f32, ry as f32),
+                    Angle::radians(angle as f32),
+                    Winding::Positive,
+                );
+            }
+            RoiShape::Annulus {
+                cx,
+                cy,
+                inner_radius,
+                outer_radius,
+                start_angle,
+                sweep_angle,
+            } => {
+                let center = point(cx as f32, cy as f32);
+                if sweep_angle.abs() >= std::f64::consts::TAU {
+                    // 完整圆环：两个同心圆，奇偶规则挖空内圆
+                    builder.add_circle(center, outer_radius as f32, Winding::Positive);
+                    if inner_radius > 0.0 {
+                        builder.add_circle(center, inner_radius as f32, Winding::Negative);
+                    }
+                } else {
+                    // 扇环：外弧正向、内弧反向，首尾用径向线段连接
+                    let outer = arc(center, outer_radius, start_angle, sweep_angle);
+                    builder.begin(outer.from());
+                    outer.for_each_quadratic_bezier(&mut |q| {
+                        builder.quadratic_bezier_to(q.ctrl, q.to);
+                    });
+                    if inner_radius > 0.0 {
+                        let inner = arc(
+                            center,
+                            inner_radius,
+                            start_angle + sweep_angle,
+                            -sweep_angle,
+                        );
+                        builder.line_to(inner.from());
+                        inner.for_each_quadratic_bezier(&mut |q| {
+                            builder.quadratic_bezier_to(q.ctrl, q.to);
+                        });
+                    } else {
+                        builder.line_to(center);
+                    }
+                    builder.end(true);
+                }
+            }
+            RoiShape::Polygon { ref points } => {
+                let points: Vec<Point> = points
+                    .iter()
+                    .map(|&[x, y]| point(x as f32, y as f32))
+                    .collect();
+                builder.add_polygon(Polygon {
+                    points: &points,
+                    closed: true,
+                });
+            }
+            RoiShape::Distance { ref points }
+            | RoiShape::Angle { ref points }
+            | RoiShape::Polyline { ref points } => {
+                let points: Vec<Point> = points
+                    .iter()
+                    .map(|&[x, y]| point(x as f32, y as f32))
+                    .collect();
+                builder.add_polygon(Polygon {
+                    points: &points,
+                    closed: false,
+                });
+            }
+            RoiShape::Composite {
+                op,
+                ref operands,
+                ref rings,
+            } => {
+                for ring in rings.get(op, operands) {
+                    let points: Vec<Point> = ring
+                        .iter()
+                        .map(|&[x, y]| point(x as f32, y as f32))
+                        .collect();
+                    builder.add_polygon(Polygon {
+                        points: &points,
+                        closed: true,
+                    });
+                }
+            }
+        }
+        builder.build()
+    }
+
+    // 按自身填充规则规整成互不重叠的外轮廓和洞，作为布尔运算的输入
+    fn region(&self) -> Vec<Vec<Vec<[f64; 2]>>> {
+        let rings: Vec<Vec<[f64; 2]>> = self
+            .outline(COMPOSITE_TOLERANCE)
+            .into_iter()
+            .map(|ring| ring.into_iter().map(|p| p.to_array()).collect())
+            .collect();
+        let fill_rule = match self.fill_rule() {
+            FillRule::EvenOdd => OverlayFillRule::EvenOdd,
+            FillRule::NonZero => OverlayFillRule::NonZero,
+        };
+        rings.simplify_shape(fill_rule, 0.0)
+    }
+
+    // 形状的参考中心，用于缩放手柄方向和旋转
+    pub fn center(&self) -> DVec2 {
+        match *self {
+            RoiShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => DVec2::new(x + width * 0.5, y + height * 0.5),
+            RoiShape::RotatedRect { cx, cy, .. }
+            | RoiShape::Circle { cx, cy, .. }
+            | RoiShape::Ellipse { cx, cy, .. }
+            | RoiShape::Annulus { cx, cy, .. } => DVec2::new(cx, cy),
+            RoiShape::Angle { ref points } => DVec2::from(points[1]),
+            RoiShape::Polygon { ref points }
+            | RoiShape::Distance { ref points }
+            | RoiShape::Polyline { ref points } => {
+                let sum = points
+                    .iter()
+                    .fold(DVec2::ZERO, |acc, &[x, y]| acc + DVec2::new(x, y));
+                sum / points.len().max(1) as f64
+            }
+            // 以第一个操作数 (差集的被减区域) 为基准
+            RoiShape::Composite { ref operands, .. } => {
+                operands.first().map_or(DVec2::ZERO, RoiShape::center)
+            }
+        }
+    }
+
+    pub fn translate(&mut self, delta: DVec2) {
+        match self {
+            RoiShape::Rect { x, y, .. } => {
+                *x += delta.x;
+                *y += delta.y;
+            }
+            RoiShape::RotatedRect { cx, cy, .. }
+            | RoiShape::Circle { cx, cy, .. }
+            | RoiShape::Ellipse { cx, cy, .. }
+            | RoiShape::Annulus { cx, cy, .. } => {
+                *cx += delta.x;
+                *cy += delta.y;
+            }
+            RoiShape::Polygon { points }
+            | RoiShape::Distance { points }
+            | RoiShape::Angle { points }
+            | RoiShape::Polyline { points } => {
+                for p in points.iter_mut() {
+                    p[0] += delta.x;
+                    p[1] += delta.y;
+                }
+            }
+            RoiShape::Composite {
+                operands, rings, ..
+            } => {
+                for operand in operands.iter_mut() {
+                    operand.translate(delta);
+                }
+                rings.translate(delta);
+            }
+        }
+    }
+
+    // 按精度 (图像像素) 展平成若干折线，供命中测试使用；除测量形状外都是闭合的
+    pub fn outline(&self, tolerance: f32) -> Vec<Vec<DVec2>> {
+        let mut rings: Vec<Vec<DVec2>> = Vec::new();
+        for event in self.build_path().iter().flattened(tolerance) {
+            match event {
+                PathEvent::Begin { at } => rings.push(vec![to_dvec2(at)]),
+                PathEvent::Line { to, .. } => {
+                    if let Some(ring) = rings.last_mut() {
+                        ring.push(to_dvec2(to));
+                    }
+                }
+                _ => {}
+            }
+        }
+        rings.retain(|ring| ring.len() >= 2);
+        rings
+    }
+
+    // 外接矩形 (最小点, 最大点)，由展平后的轮廓求得，曲线部分的误差不超过 tolerance
+    pub fn bounds(&self, tolerance: f32) -> (DVec2, DVec2) {
+        let points = self.outline(tolerance).into_iter().flatten();
+        points.fold(
+            (DVec2::splat(f64::INFINITY), DVec2::splat(f64::NEG_INFINITY)),
+            |(min, max), p| (min.min(p), max.max(p)),
+        )
+    }
+
+    // 点是否在形状内部，遵循与渲染相同的填充规则；测量形状没有内部
+    pub fn contains(&self, p: DVec2, tolerance: f32) -> bool {
+        if self.is_measurement() {
+            return false;
+        }
+        let mut winding = 0i32;
+        for ring in self.outline(tolerance) {
+            for (i, &a) in ring.iter().enumerate() {
+                let b = ring[(i + 1) % ring.len()];
+                if a.y <= p.y && b.y > p.y && cross(b - a, p - a) > 0.0 {
+                    winding += 1;
+                } else if a.y > p.y && b.y <= p.y && cross(b - a, p - a) < 0.0 {
+                    winding -= 1;
+                }
+            }
+        }
+        match self.fill_rule() {
+            FillRule::EvenOdd => winding % 2 != 0,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+
+    // 点到轮廓的最近距离 (图像像素)
+    pub fn distance_to_outline(&self, p: DVec2, tolerance: f32) -> f64 {
+        let mut best = f64::INFINITY;
+        let closed = !self.is_measurement();
+        for ring in self.outline(tolerance) {
+            let segments = if closed { ring.len() } else { ring.len() - 1 };
+            for i in 0..segments {
+                let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+                best = best.min(distance_to_segment(p, a, b));
+            }
+        }
+        best
+    }
+}
+
+// 组合形状的运算结果：依次把累积结果与下一个操作数做运算，返回全部外轮廓和洞
+fn composite_rings(op: IrisRoiBoolOp, operands: &[RoiShape]) -> Vec<Vec<[f64; 2]>> {
+    let rule = match op {
+        IrisRoiBoolOp::Union => OverlayRule::Union,
+        IrisRoiBoolOp::Intersection => OverlayRule::Intersect,
+        IrisRoiBoolOp::Difference => OverlayRule::Difference,
+        IrisRoiBoolOp::Xor => OverlayRule::Xor,
+    };
+    let mut regions = operands.iter().map(RoiShape::region);
+    let Some(first) = regions.next() else {
+        return Vec::new();
+    };
+    let result = regions.fold(first, |acc, next| {
+        acc.overlay(&next, rule, OverlayFillRule::EvenOdd)
+    });
+    result.into_iter().flatten().collect()
+}
+
+fn to_dvec2(p: Point) -> DVec2 {
+    DVec2::new(p.x as f64, p.y as f64)
+}
+
+fn cross(a: DVec2, b: DVec2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+pub fn distance_to_segment(p: DVec2, a: DVec2, b: DVec2) -> f64 {
+    let ab = b - a;
+    let len2 = ab.length_squared();
+    let t = if len2 > 0.0 {
+        ((p - a).dot(ab) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
+
+// 旋转矩形的四个角点，顺序为左上、右上、右下、左下 (旋转前)
+pub fn rotated_rect_corners(
+    cx: f64,
+    cy: f64,
+    half_width: f64,
+    half_height: f64,
+    angle: f64,
+) -> [[f64; 2]; 4] {
+    let (sin, cos) = angle.sin_cos();
+    [
+        (-half_width, -half_height),
+        (half_width, -half_height),
+        (half_width, half_height),
+        (-half_width, half_height),
+    ]
+    .map(|(dx, dy)| [cx + dx * cos - dy * sin, cy + dx * sin + dy * cos])
+}
+
+fn arc(center: Point, radius: f64, start_angle: f64, sweep_angle: f64) -> Arc<f32> {
+    Arc {
+        center,
+        radii: vector(radius as f32, radius as f32),
+        start_angle: Angle::radians(start_angle as f32),
+        sweep_angle: Angle::radians(sweep_angle as f32),
+        x_rotation: Angle::radians(0.0),
+    }
+}
+
+// ROI 外观：描边宽度以屏幕像素计，缩放时保持不变
+// 颜色为非预乘的 RGBA (0~1)，填充 alpha 为 0 时不填充，描边宽度为 0 时不描边
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RoiStyle {
+    pub stroke_color: [f32; 4],
+    pub fill_color: [f32; 4],
+    pub stroke_width: f32,
+}
+
+impl Default for RoiStyle {
+    fn default() -> Self {
+        Self {
+            stroke_color: [0.0, 1.0, 0.0, 1.0],
+            fill_color: [0.0, 1.0, 0.0, 0.0],
+            stroke_width: 2.0,
+        }
+    }
+}
+
+// 宿主常用的 0xAARRGGBB (.NET Color.ToArgb) 转为 RGBA
+pub fn argb_to_rgba(argb: u32) -> [f32; 4] {
+    let channel = |shift: u32| ((argb >> shift) & 0xff) as f32 / 255.0;
+    [channel(16), channel(8), channel(0), channel(24)]
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Roi {
+    pub shape: RoiShape,
+    pub style: RoiStyle,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoiShape;
+    use crate::common::types::IrisRoiBoolOp;
+    use glam::DVec2;
+
+    fn rect(x: f64, y: f64) -> RoiShape {
+        RoiShape::Rect {
+            x,
+            y,
+            width: 40.0,
+            height: 30.0,
+        }
+    }
+
+    // 外轮廓与洞的方向相反，有向面积之和即区域面积
+    fn area(shape: &RoiShape) -> f64 {
+        let signed: f64 = shape
+            .outline(0.01)
+            .iter()
+            .map(|ring| {
+                let n = ring.len();
+                (0..n)
+                    .map(|i| super::cross(ring[i], ring[(i + 1) % n]))
+                    .sum::<f64>()
+                    / 2.0
+            })
+            .sum();
+        signed.abs()
+    }
+
+    fn inside(shape: &RoiShape, x: f64, y: f64) -> bool {
+        shape.contains(DVec2::new(x, y), 0.01)
+    }
+
+    // 两个 40x30 的矩形重叠 20x20
+    #[test]
+    fn overlapping_operands() {
+        let operands = vec![rect(0.0, 0.0), rect(20.0, 10.0)];
+        let combine = |op| RoiShape::composite(op, operands.clone());
+
+        let union = combine(IrisRoiBoolOp::Union);
+        assert!((area(&union) - 2000.0).abs() < 1e-3);
+        assert!(inside(&union, 5.0, 5.0) && inside(&union, 55.0, 35.0));
+        assert!(!inside(&union, 5.0, 35.0));
+
+        let intersection = combine(IrisRoiBoolOp::Intersection);
+        assert!((area(&intersection) - 400.0).abs() < 1e-3);
+        assert!(inside(&intersection, 30.0, 20.0) && !inside(&intersection, 5.0, 5.0));
+
+        let difference = combine(IrisRoiBoolOp::Difference);
+        assert!((area(&difference) - 800.0).abs() < 1e-3);
+        assert!(inside(&difference, 5.0, 5.0));
+        assert!(!inside(&difference, 30.0, 20.0) && !inside(&difference, 55.0, 35.0));
+
+        let xor = combine(IrisRoiBoolOp::Xor);
+        assert!((area(&xor) - 1600.0).abs() < 1e-3);
+        assert!(inside(&xor, 5.0, 5.0) && inside(&xor, 55.0, 35.0));
+        assert!(!inside(&xor, 30.0, 20.0));
+
+        assert!(!union.is_empty_composite() && !intersection.is_empty_composite());
+    }
+
+    #[test]
+    fn disjoint_operands() {
+        let operands = vec![rect(0.0, 0.0), rect(100.0, 0.0)];
+        let combine = |op| RoiShape::composite(op, operands.clone());
+
+        let union = combine(IrisRoiBoolOp::Union);
+        assert!((area(&union) - 2400.0).abs() < 1e-3);
+        assert!(inside(&union, 5.0, 5.0) && inside(&union, 105.0, 5.0));
+
+        assert!(combine(IrisRoiBoolOp::Intersection).is_empty_composite());
+
+        let difference = combine(IrisRoiBoolOp::Difference);
+        assert!((area(&difference) - 1200.0).abs() < 1e-3);
+        assert!(!inside(&difference, 105.0, 5.0));
+
+        assert!((area(&combine(IrisRoiBoolOp::Xor)) - 2400.0).abs() < 1e-3);
+    }
+
+    // 平移后缓存的运算结果与重新计算的一致
+    #[test]
+    fn translated_composite_matches_recomputed() {
+        let mut shape = RoiShape::composite(
+            IrisRoiBoolOp::Difference,
+            vec![rect(0.0, 0.0), rect(20.0, 10.0)],
+        );
+        shape.translate(DVec2::new(100.0, 50.0));
+        let RoiShape::Composite { op, operands, .. } = &shape else {
+            unreachable!();
+        };
+        let recomputed = RoiShape::composite(*op, operands.clone());
+        assert_eq!(shape.outline(0.01), recomputed.outline(0.01));
+        assert!(inside(&shape, 105.0, 55.0) && !inside(&shape, 5.0, 5.0));
+    }
+}
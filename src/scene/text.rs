@@ -0,0 +1,82 @@
+use crate::common::types::{IrisTextAnchor, IrisTextSpace};
+use glam::DVec2;
+
+// 文字外观：字号按文字所在空间计 (Image 为图像像素，其余为屏幕像素)
+// 颜色为非预乘的 RGBA (0~1)，背景 alpha 为 0 时不画背景框
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    pub size: f32,
+    pub color: [f32; 4],
+    pub background: [f32; 4],
+    // 背景框相对文字四周的留白，单位同字号
+    pub padding: f32,
+    pub anchor: IrisTextAnchor,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            size: 16.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            background: [0.0, 0.0, 0.0, 0.0],
+            padding: 2.0,
+            anchor: IrisTextAnchor::TopLeft,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextItem {
+    pub id: u64,
+    // UTF-8 文本，'\n' 换行
+    pub text: String,
+    pub position: DVec2,
+    pub space: IrisTextSpace,
+    pub style: TextStyle,
+}
+
+// 文字对象列表，按添加顺序绘制；ID 与 ROI 的 ID 相互独立
+pub struct TextSet {
+    items: Vec<TextItem>,
+    next_id: u64,
+}
+
+impl TextSet {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn add(&mut self, text: &str, position: DVec2, space: IrisTextSpace) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.items.push(TextItem {
+            id,
+            text: text.to_owned(),
+            position,
+            space,
+            style: TextStyle::default(),
+        });
+        id
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut TextItem> {
+        self.items.iter_mut().find(|t| t.id == id)
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        let len = self.items.len();
+        self.items.retain(|t| t.id != id);
+        self.items.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn items(&self) -> &[TextItem] {
+        &self.items
+    }
+}
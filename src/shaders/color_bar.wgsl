@@ -0,0 +1,79 @@
+// 屏幕空间的色带图例，上下两端标注窗口的最大 / 最小值
+// 依赖 digit_font.wgsl
+struct ColorBarUniforms {
+    viewport: vec2<f32>,
+    glyph_scale: f32,
+    lut_enabled: u32,
+    // 以下矩形均为屏幕像素坐标 (min.xy, max.xy)
+    panel: vec4<f32>,
+    bar: vec4<f32>,
+    top_label_pos: vec2<f32>,
+    bottom_label_pos: vec2<f32>,
+    srgb_target: u32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
+    top_label: array<vec4<u32>, 4>,
+    bottom_label: array<vec4<u32>, 4>,
+}
+
+@group(0) @binding(0) var<uniform> u: ColorBarUniforms;
+@group(0) @binding(1) var lut: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let p = mix(u.panel.xy, u.panel.zw, corners[index]);
+    let clip = p / u.viewport * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+    return vec4<f32>(clip, 0.0, 1.0);
+}
+
+fn label_code(top: bool, index: u32) -> u32 {
+    if (index >= 16u) {
+        return GLYPH_BLANK;
+    }
+    if (top) {
+        return u.top_label[index / 4u][index % 4u];
+    }
+    return u.bottom_label[index / 4u][index % 4u];
+}
+
+// 字符间距 1 像素，即每个字符占 4 x 5 个字体像素
+fn label_pixel(top: bool, origin: vec2<f32>, p: vec2<f32>) -> bool {
+    let local = (p - origin) / u.glyph_scale;
+    if (local.x < 0.0 || local.y < 0.0) {
+        return false;
+    }
+    let cell = vec2<i32>(floor(local));
+    let index = u32(cell.x / 4);
+    return glyph_pixel(label_code(top, index), vec2<i32>(cell.x % 4, cell.y));
+}
+
+@fragment
+fn fs_main(@builtin(position) frag: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = frag.xy;
+    if (all(p >= u.bar.xy) && all(p < u.bar.zw)) {
+        // 上端为最大值
+        let t = clamp((u.bar.w - p.y) / (u.bar.w - u.bar.y), 0.0, 1.0);
+        var rgb = vec3<f32>(t);
+        if (u.lut_enabled != 0u) {
+            let index = i32(round(t * f32(textureDimensions(lut).x - 1u)));
+            rgb = textureLoad(lut, vec2<i32>(index, 0), 0).rgb;
+        }
+        if (u.srgb_target != 0u) {
+            rgb = srgb_to_linear(rgb);
+        }
+        return vec4<f32>(rgb, 1.0);
+    }
+    if (label_pixel(true, u.top_label_pos, p) || label_pixel(false, u.bottom_label_pos, p)) {
+        return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    }
+    return vec4<f32>(0.0, 0.0, 0.0, 0.6);
+}
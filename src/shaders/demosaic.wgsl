@@ -0,0 +1,79 @@
+// Bayer 去马赛克，输出写入与普通解码相同的 rgba32float 纹理
+// 依赖 raw_input.wgsl 中的绑定和读取函数
+// demosaic_mode: 0 = 双线性，1 = Malvar-He-Cutler (梯度校正的 5x5 线性插值)
+
+// 读取一个 Bayer 采样并归一化；越界时按 2 像素镜像，保持 CFA 相位不变
+fn bayer(x: i32, y: i32) -> f32 {
+    let w = i32(params.width);
+    let h = i32(params.height);
+    var sx = x;
+    var sy = y;
+    if (sx < 0) {
+        sx = sx + 2 * ((1 - sx) / 2);
+    }
+    if (sx >= w) {
+        sx = sx - 2 * ((sx - w) / 2 + 1);
+    }
+    if (sy < 0) {
+        sy = sy + 2 * ((1 - sy) / 2);
+    }
+    if (sy >= h) {
+        sy = sy - 2 * ((sy - h) / 2 + 1);
+    }
+    sx = clamp(sx, 0, w - 1);
+    sy = clamp(sy, 0, h - 1);
+
+    let row = u32(sy);
+    let col = u32(sx);
+    if (params.bit_depth == 8u) {
+        return f32(byte_at(row, col)) / 255.0;
+    }
+    let max_value = f32((1u << params.bit_depth) - 1u);
+    return f32(u16_at(row, col * 2u) & ((1u << params.bit_depth) - 1u)) / max_value;
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let x = i32(id.x);
+    let y = i32(id.y);
+
+    // 换算到 RGGB 相位：(0,0)=R，(1,0)=R 行上的 G，(0,1)=B 行上的 G，(1,1)=B
+    let cx = (id.x ^ (params.bayer_pattern & 1u)) & 1u;
+    let cy = (id.y ^ ((params.bayer_pattern >> 1u) & 1u)) & 1u;
+
+    let c = bayer(x, y);
+    let h1 = bayer(x - 1, y) + bayer(x + 1, y);
+    let v1 = bayer(x, y - 1) + bayer(x, y + 1);
+    let diag = bayer(x - 1, y - 1) + bayer(x + 1, y - 1) + bayer(x - 1, y + 1) + bayer(x + 1, y + 1);
+
+    // 三种插值：G 在 R/B 处，G 处的水平 / 垂直邻居色，R/B 处的对角色
+    var green_at_rb = (h1 + v1) * 0.25;
+    var horizontal = h1 * 0.5;
+    var vertical = v1 * 0.5;
+    var opposite = diag * 0.25;
+    if (params.demosaic_mode == 1u) {
+        let h2 = bayer(x - 2, y) + bayer(x + 2, y);
+        let v2 = bayer(x, y - 2) + bayer(x, y + 2);
+        green_at_rb = (4.0 * c + 2.0 * (h1 + v1) - (h2 + v2)) / 8.0;
+        horizontal = (5.0 * c + 4.0 * h1 - diag - h2 + 0.5 * v2) / 8.0;
+        vertical = (5.0 * c + 4.0 * v1 - diag - v2 + 0.5 * h2) / 8.0;
+        opposite = (6.0 * c + 2.0 * diag - 1.5 * (h2 + v2)) / 8.0;
+    }
+
+    var rgb: vec3<f32>;
+    if (cx == 0u && cy == 0u) {
+        rgb = vec3<f32>(c, green_at_rb, opposite);
+    } else if (cx == 1u && cy == 0u) {
+        rgb = vec3<f32>(horizontal, c, vertical);
+    } else if (cx == 0u && cy == 1u) {
+        rgb = vec3<f32>(vertical, c, horizontal);
+    } else {
+        rgb = vec3<f32>(opposite, green_at_rb, c);
+    }
+
+    rgb = max(rgb * params.wb_gains.rgb, vec3<f32>(0.0));
+    textureStore(decoded, vec2<i32>(x, y), vec4<f32>(rgb, 1.0));
+}
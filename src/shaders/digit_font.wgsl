@@ -0,0 +1,31 @@
+// 3x5 点阵数字字体，供色带标签和像素值叠加使用，拼接在各自着色器之前
+// 字符编码：0~9 为数字，10 为 '-'，11 为 '.'，其余为空白
+// 每个字形 15 位，从上到下每行 3 位，高位在左
+const GLYPH_BLANK: u32 = 15u;
+
+fn glyph_bits(code: u32) -> u32 {
+    switch code {
+        case 0u: { return 0x7b6fu; }
+        case 1u: { return 0x2c97u; }
+        case 2u: { return 0x73e7u; }
+        case 3u: { return 0x73cfu; }
+        case 4u: { return 0x5bc9u; }
+        case 5u: { return 0x79cfu; }
+        case 6u: { return 0x79efu; }
+        case 7u: { return 0x7249u; }
+        case 8u: { return 0x7befu; }
+        case 9u: { return 0x7bcfu; }
+        case 10u: { return 0x01c0u; }
+        case 11u: { return 0x0002u; }
+        default: { return 0u; }
+    }
+}
+
+// cell 为字形内的像素坐标 (0~2, 0~4)
+fn glyph_pixel(code: u32, cell: vec2<i32>) -> bool {
+    if (cell.x < 0 || cell.x > 2 || cell.y < 0 || cell.y > 4) {
+        return false;
+    }
+    let bit = 14u - u32(cell.y * 3 + cell.x);
+    return ((glyph_bits(code) >> bit) & 1u) != 0u;
+}
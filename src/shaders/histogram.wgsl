@@ -0,0 +1,18 @@
+// 统计解码后图像的亮度直方图，用于自动窗宽窗位
+// 彩色图像取三通道最大值，避免高光被截断
+const BIN_COUNT: u32 = 4096u;
+
+@group(0) @binding(0) var image: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> bins: array<atomic<u32>, BIN_COUNT>;
+
+@compute @workgroup_size(16, 16)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(image);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let c = textureLoad(image, vec2<i32>(id.xy), 0);
+    let v = clamp(max(c.r, max(c.g, c.b)), 0.0, 1.0);
+    let bin = min(u32(v * f32(BIN_COUNT)), BIN_COUNT - 1u);
+    atomicAdd(&bins[bin], 1u);
+}
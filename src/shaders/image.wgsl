@@ -0,0 +1,182 @@
+// 图像层：以图像像素为单位的四边形，由 image_to_clip 映射到屏幕
+// 依赖 digit_font.wgsl (像素值叠加)
+struct ImageUniforms {
+    image_to_clip: mat4x4<f32>,
+    image_size: vec2<f32>,
+    srgb_target: u32,
+    _pad: u32,
+    // 窗宽窗位 (归一化)、gamma 和反相
+    window_min: f32,
+    window_max: f32,
+    gamma: f32,
+    invert: u32,
+    lut_enabled: u32,
+    // 高倍放大时的像素检查：最近邻采样、像素网格、像素值文字 (是否生效已按缩放判断)
+    nearest: u32,
+    grid: u32,
+    values: u32,
+    mono: u32,
+    // 屏幕像素 / 图像像素
+    zoom: f32,
+    // 当前位深的最大原始值，用于把归一化值还原成原始值
+    max_value: f32,
+    _pad1: u32,
+    viewport: vec2<f32>,
+    _pad2: u32,
+    _pad3: u32,
+}
+
+@group(0) @binding(0) var<uniform> u: ImageUniforms;
+@group(0) @binding(1) var image: texture_2d<f32>;
+@group(0) @binding(2) var lut: texture_2d<f32>;
+
+struct VsOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) image_pos: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VsOut {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let p = corners[index] * u.image_size;
+    var out: VsOut;
+    out.position = u.image_to_clip * vec4<f32>(p, 0.0, 1.0);
+    out.image_pos = p;
+    return out;
+}
+
+fn load_clamped(p: vec2<i32>) -> vec4<f32> {
+    let max_p = vec2<i32>(u.image_size) - vec2<i32>(1, 1);
+    return textureLoad(image, clamp(p, vec2<i32>(0, 0), max_p), 0);
+}
+
+// rgba32float 不可过滤，这里手动做双线性插值
+fn sample_bilinear(pos: vec2<f32>) -> vec4<f32> {
+    let p = pos - vec2<f32>(0.5, 0.5);
+    let base = floor(p);
+    let f = p - base;
+    let i = vec2<i32>(base);
+    let c00 = load_clamped(i);
+    let c10 = load_clamped(i + vec2<i32>(1, 0));
+    let c01 = load_clamped(i + vec2<i32>(0, 1));
+    let c11 = load_clamped(i + vec2<i32>(1, 1));
+    return mix(mix(c00, c10, f.x), mix(c01, c11, f.x), f.y);
+}
+
+// 像素值文字：每个字符占 4 x 6 个字体像素 (含间距)，整体不超过像素的 80%
+const TEXT_FILL: f32 = 0.8;
+const MAX_DIGITS: u32 = 6u;
+
+fn raw_value(c: f32) -> u32 {
+    return min(u32(round(max(c, 0.0) * u.max_value)), 999999u);
+}
+
+fn digit_count(n: u32) -> u32 {
+    var count = 1u;
+    var m = n / 10u;
+    loop {
+        if (m == 0u || count >= MAX_DIGITS) {
+            break;
+        }
+        m = m / 10u;
+        count = count + 1u;
+    }
+    return count;
+}
+
+// 右对齐：index 为从左数第几个字符，width 为这一行的总字符数
+fn digit_code(n: u32, index: u32, width: u32) -> u32 {
+    let count = digit_count(n);
+    if (index + count < width) {
+        return GLYPH_BLANK;
+    }
+    var m = n;
+    for (var i = index + 1u; i < width; i = i + 1u) {
+        m = m / 10u;
+    }
+    return m % 10u;
+}
+
+// 判断屏幕点 frag 是否落在所在像素的数值文字笔画上
+fn value_text_pixel(frag: vec2<f32>, pixel: vec2<f32>, raw: vec4<f32>) -> bool {
+    var values = vec3<u32>(raw_value(raw.r), raw_value(raw.g), raw_value(raw.b));
+    var lines = 3u;
+    if (u.mono != 0u) {
+        values = vec3<u32>(values.x, 0u, 0u);
+        lines = 1u;
+    }
+    let width = max(digit_count(values.x), max(digit_count(values.y), digit_count(values.z)));
+
+    // 文字始终在屏幕上正向显示，以像素中心的屏幕位置为基准排版
+    let clip = u.image_to_clip * vec4<f32>(pixel + vec2<f32>(0.5, 0.5), 0.0, 1.0);
+    let center = (clip.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5)) * u.viewport;
+    let text_size = vec2<f32>(f32(width * 4u - 1u), f32(lines * 6u - 1u));
+    let scale = floor(min(u.zoom * TEXT_FILL / f32(width * 4u), u.zoom * TEXT_FILL / f32(lines * 6u)));
+    if (scale < 1.0) {
+        return false;
+    }
+    let local = (frag - center) / scale + text_size * 0.5;
+    if (local.x < 0.0 || local.y < 0.0) {
+        return false;
+    }
+    let cell = vec2<u32>(floor(local));
+    let line = cell.y / 6u;
+    let index = cell.x / 4u;
+    if (line >= lines || index >= width) {
+        return false;
+    }
+    let code = digit_code(values[line], index, width);
+    return glyph_pixel(code, vec2<i32>(i32(cell.x % 4u), i32(cell.y % 6u)));
+}
+
+@fragment
+fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
+    let pixel = floor(in.image_pos);
+    let raw = load_clamped(vec2<i32>(pixel));
+    var color = raw;
+    if (u.nearest == 0u) {
+        color = sample_bilinear(in.image_pos);
+    }
+
+    // 窗宽窗位：window_min 映射为黑，window_max 映射为白
+    let range = max(u.window_max - u.window_min, 1e-6);
+    var rgb = clamp((color.rgb - vec3<f32>(u.window_min)) / range, vec3<f32>(0.0), vec3<f32>(1.0));
+    rgb = pow(rgb, vec3<f32>(1.0 / u.gamma));
+    if (u.invert != 0u) {
+        rgb = vec3<f32>(1.0) - rgb;
+    }
+    // 伪彩色：按亮度查表
+    if (u.lut_enabled != 0u) {
+        let luma = dot(rgb, vec3<f32>(0.299, 0.587, 0.114));
+        let index = i32(round(luma * f32(textureDimensions(lut).x - 1u)));
+        rgb = textureLoad(lut, vec2<i32>(index, 0), 0).rgb;
+    }
+
+    // 像素网格：每条像素边界只命中一列 / 一行屏幕像素，
+    // 区间偏移 1/4 屏幕像素，避免边界正好落在两个屏幕像素中间时两边都画或都不画
+    if (u.grid != 0u) {
+        let edge = fract(in.image_pos + vec2<f32>(0.75 / u.zoom));
+        if (any(edge < vec2<f32>(1.0 / u.zoom))) {
+            rgb = mix(rgb, vec3<f32>(0.5), 0.6);
+        }
+    }
+    // 像素值：按显示亮度选黑字或白字
+    if (u.values != 0u && value_text_pixel(in.position.xy, pixel, raw)) {
+        let luma = dot(rgb, vec3<f32>(0.299, 0.587, 0.114));
+        rgb = select(vec3<f32>(1.0), vec3<f32>(0.0), luma > 0.5);
+    }
+    color = vec4<f32>(rgb, color.a);
+
+    // 相机数据本身就是显示值，sRGB 目标会再编码一次，这里先还原成线性
+    if (u.srgb_target != 0u) {
+        color = vec4<f32>(srgb_to_linear(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0))), color.a);
+    }
+    return vec4<f32>(color.rgb, 1.0);
+}
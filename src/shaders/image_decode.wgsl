@@ -0,0 +1,108 @@
+// 把上传的原始字节解码成 rgba32float 纹理，格式编号与 IrisPixelFormat 一致
+// 依赖 raw_input.wgsl 中的绑定和读取函数
+
+fn unorm8(row: u32, offset: u32) -> f32 {
+    return f32(byte_at(row, offset)) / 255.0;
+}
+
+// GigE Vision Mono10Packed / Mono12Packed：每 2 个像素占 3 字节，低位放在中间字节
+fn gige_packed(row: u32, x: u32, bits: u32) -> u32 {
+    let base = (x / 2u) * 3u;
+    let mid = byte_at(row, base + 1u);
+    let low_bits = bits - 8u;
+    let low_mask = (1u << low_bits) - 1u;
+    if (x % 2u == 0u) {
+        return (byte_at(row, base) << low_bits) | (mid & low_mask);
+    }
+    return (byte_at(row, base + 2u) << low_bits) | ((mid >> 4u) & low_mask);
+}
+
+// PFNC Mono10p / Mono12p：LSB 优先的连续位流，一个像素最多跨 3 个字节
+fn lsb_packed(row: u32, x: u32, bits: u32) -> u32 {
+    let bit = x * bits;
+    let offset = bit / 8u;
+    let word = byte_at(row, offset) | (byte_at(row, offset + 1u) << 8u) | (byte_at(row, offset + 2u) << 16u);
+    return (word >> (bit % 8u)) & ((1u << bits) - 1u);
+}
+
+fn mono(v: f32) -> vec4<f32> {
+    return vec4<f32>(v, v, v, 1.0);
+}
+
+// BT.601 全范围 YUV -> RGB
+fn yuv_to_rgba(y: f32, u: f32, v: f32) -> vec4<f32> {
+    let cb = u - 0.5;
+    let cr = v - 0.5;
+    let rgb = vec3<f32>(y + 1.402 * cr, y - 0.344136 * cb - 0.714136 * cr, y + 1.772 * cb);
+    return vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let x = id.x;
+    let y = id.y;
+    var color = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    switch params.format {
+        // Mono8
+        case 0u: {
+            color = mono(unorm8(y, x));
+        }
+        // Rgba8
+        case 1u: {
+            color = vec4<f32>(unorm8(y, x * 4u), unorm8(y, x * 4u + 1u), unorm8(y, x * 4u + 2u), unorm8(y, x * 4u + 3u));
+        }
+        // Bgra8
+        case 2u: {
+            color = vec4<f32>(unorm8(y, x * 4u + 2u), unorm8(y, x * 4u + 1u), unorm8(y, x * 4u), unorm8(y, x * 4u + 3u));
+        }
+        // Mono10 / Mono12 / Mono16
+        case 3u: {
+            color = mono(f32(u16_at(y, x * 2u) & 0x3ffu) / 1023.0);
+        }
+        case 4u: {
+            color = mono(f32(u16_at(y, x * 2u) & 0xfffu) / 4095.0);
+        }
+        case 5u: {
+            color = mono(f32(u16_at(y, x * 2u)) / 65535.0);
+        }
+        // Mono10Packed / Mono12Packed
+        case 6u: {
+            color = mono(f32(gige_packed(y, x, 10u)) / 1023.0);
+        }
+        case 7u: {
+            color = mono(f32(gige_packed(y, x, 12u)) / 4095.0);
+        }
+        // Mono10p / Mono12p
+        case 8u: {
+            color = mono(f32(lsb_packed(y, x, 10u)) / 1023.0);
+        }
+        case 9u: {
+            color = mono(f32(lsb_packed(y, x, 12u)) / 4095.0);
+        }
+        // Rgb8
+        case 10u: {
+            color = vec4<f32>(unorm8(y, x * 3u), unorm8(y, x * 3u + 1u), unorm8(y, x * 3u + 2u), 1.0);
+        }
+        // Bgr8
+        case 11u: {
+            color = vec4<f32>(unorm8(y, x * 3u + 2u), unorm8(y, x * 3u + 1u), unorm8(y, x * 3u), 1.0);
+        }
+        // YUV422 YUYV：Y0 U Y1 V
+        case 12u: {
+            let base = (x / 2u) * 4u;
+            let luma = unorm8(y, base + (x % 2u) * 2u);
+            color = yuv_to_rgba(luma, unorm8(y, base + 1u), unorm8(y, base + 3u));
+        }
+        // YUV422 UYVY：U Y0 V Y1
+        case 13u: {
+            let base = (x / 2u) * 4u;
+            let luma = unorm8(y, base + 1u + (x % 2u) * 2u);
+            color = yuv_to_rgba(luma, unorm8(y, base), unorm8(y, base + 2u));
+        }
+        default: {}
+    }
+    textureStore(decoded, vec2<i32>(i32(x), i32(y)), color);
+}
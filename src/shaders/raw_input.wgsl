@@ -0,0 +1,32 @@
+// 解码 / 去马赛克共用的原始数据输入，拼接在各自着色器之前
+struct DecodeParams {
+    width: u32,
+    height: u32,
+    row_words: u32,
+    format: u32,
+    // Bayer 图中 R 像素相对 (0, 0) 的偏移，x 在 bit0，y 在 bit1
+    bayer_pattern: u32,
+    bit_depth: u32,
+    demosaic_mode: u32,
+    _pad: u32,
+    wb_gains: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: DecodeParams;
+@group(0) @binding(1) var<storage, read> raw: array<u32>;
+@group(0) @binding(2) var decoded: texture_storage_2d<rgba32float, write>;
+
+// 每行按 4 字节对齐存放，读取第 row 行偏移 offset 处的一个字节
+fn byte_at(row: u32, offset: u32) -> u32 {
+    if (offset >= params.row_words * 4u) {
+        return 0u;
+    }
+    let word = raw[row * params.row_words + offset / 4u];
+    return (word >> ((offset % 4u) * 8u)) & 0xffu;
+}
+
+// 16 位小端容器
+fn u16_at(row: u32, offset: u32) -> u32 {
+    return byte_at(row, offset) | (byte_at(row, offset + 1u) << 8u);
+}
+
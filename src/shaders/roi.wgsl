@@ -0,0 +1,44 @@
+// ROI 几何：lyon 细分出的三角形，顶点在图像坐标下
+// 描边顶点带法线，在这里按屏幕像素宽度外扩，缩放时线宽不变
+struct RoiUniforms {
+    image_to_clip: mat4x4<f32>,
+    // 屏幕像素 / 图像像素
+    zoom: f32,
+    srgb_target: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+@group(0) @binding(0) var<uniform> u: RoiUniforms;
+
+struct VsIn {
+    @location(0) position: vec2<f32>,
+    // 填充顶点的法线为 0
+    @location(1) normal: vec2<f32>,
+    @location(2) stroke_width: f32,
+    @location(3) color: vec4<f32>,
+}
+
+struct VsOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VsIn) -> VsOut {
+    let offset = in.normal * (in.stroke_width * 0.5 / u.zoom);
+    var out: VsOut;
+    out.position = u.image_to_clip * vec4<f32>(in.position + offset, 0.0, 1.0);
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
+    var rgb = in.color.rgb;
+    // 颜色由宿主按 sRGB 给出
+    if (u.srgb_target != 0u) {
+        rgb = srgb_to_linear(rgb);
+    }
+    return vec4<f32>(rgb, in.color.a);
+}
@@ -0,0 +1,39 @@
+// ROI 掩膜：把 lyon 细分出的填充三角形按图像像素画进与图像同尺寸的目标
+// 二值掩膜 (R8Unorm) 内部输出 1.0 即 255；标签图 (R16Uint) 输出每个顶点携带的标签
+struct MaskUniforms {
+    image_size: vec2<f32>,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+@group(0) @binding(0) var<uniform> u: MaskUniforms;
+
+struct VsIn {
+    @location(0) position: vec2<f32>,
+    @location(1) label: u32,
+}
+
+struct VsOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) label: u32,
+}
+
+@vertex
+fn vs_main(in: VsIn) -> VsOut {
+    // 图像坐标 (y 向下) -> 裁剪空间，像素中心落在 x + 0.5
+    let ndc = in.position / u.image_size * 2.0 - vec2<f32>(1.0);
+    var out: VsOut;
+    out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    out.label = in.label;
+    return out;
+}
+
+@fragment
+fn fs_mask(in: VsOut) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+
+@fragment
+fn fs_label(in: VsOut) -> @location(0) vec4<u32> {
+    return vec4<u32>(in.label, 0u, 0u, 0u);
+}
@@ -0,0 +1,105 @@
+// ROI 内的像素统计：掩膜 (R8) 选出 ROI 内的像素，每个工作组在共享内存里归约出
+// 像素数、和、相对组内均值的平方和 (M2)、最小值和最大值，CPU 再按 Chan 的并行公式合并
+// 直方图直接用全局原子计数
+const WORKGROUP_SIZE: u32 = 256u;
+const BIN_COUNT: u32 = 256u;
+const FLT_MAX: f32 = 3.402823e38;
+
+struct StatsParams {
+    // 只统计 ROI 外接矩形 [origin, origin + size) 内的像素
+    origin: vec2<u32>,
+    size: vec2<u32>,
+    // 单色图取 R 通道，彩色图取 BT.601 亮度
+    mono: u32,
+    // 解码纹理是归一化的，乘以它换回原始单位
+    max_value: f32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+struct Partial {
+    count: u32,
+    sum: f32,
+    m2: f32,
+    min_value: f32,
+    max_value: f32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: StatsParams;
+@group(0) @binding(1) var image: texture_2d<f32>;
+@group(0) @binding(2) var mask: texture_2d<f32>;
+@group(0) @binding(3) var<storage, read_write> partials: array<Partial>;
+@group(0) @binding(4) var<storage, read_write> bins: array<atomic<u32>, BIN_COUNT>;
+
+var<workgroup> wg_count: array<u32, WORKGROUP_SIZE>;
+var<workgroup> wg_sum: array<f32, WORKGROUP_SIZE>;
+var<workgroup> wg_min: array<f32, WORKGROUP_SIZE>;
+var<workgroup> wg_max: array<f32, WORKGROUP_SIZE>;
+
+@compute @workgroup_size(16, 16)
+fn cs_main(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) li: u32,
+    @builtin(workgroup_id) wid: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    var hit = false;
+    var v = 0.0;
+    if (gid.x < params.size.x && gid.y < params.size.y) {
+        let p = vec2<i32>(params.origin + gid.xy);
+        if (textureLoad(mask, p, 0).r > 0.5) {
+            let c = textureLoad(image, p, 0);
+            var n = c.r;
+            if (params.mono == 0u) {
+                n = dot(c.rgb, vec3<f32>(0.299, 0.587, 0.114));
+            }
+            n = clamp(n, 0.0, 1.0);
+            hit = true;
+            v = n * params.max_value;
+            atomicAdd(&bins[min(u32(n * f32(BIN_COUNT)), BIN_COUNT - 1u)], 1u);
+        }
+    }
+
+    wg_count[li] = select(0u, 1u, hit);
+    wg_sum[li] = select(0.0, v, hit);
+    wg_min[li] = select(FLT_MAX, v, hit);
+    wg_max[li] = select(-FLT_MAX, v, hit);
+    workgroupBarrier();
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride = stride / 2u) {
+        if (li < stride) {
+            wg_count[li] += wg_count[li + stride];
+            wg_sum[li] += wg_sum[li + stride];
+            wg_min[li] = min(wg_min[li], wg_min[li + stride]);
+            wg_max[li] = max(wg_max[li], wg_max[li + stride]);
+        }
+        workgroupBarrier();
+    }
+    let count = wg_count[0];
+    let sum = wg_sum[0];
+    let mean = sum / f32(max(count, 1u));
+    workgroupBarrier();
+
+    // 第二轮：相对组内均值的平方和，避免大数相减损失精度
+    let d = v - mean;
+    wg_sum[li] = select(0.0, d * d, hit);
+    workgroupBarrier();
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride = stride / 2u) {
+        if (li < stride) {
+            wg_sum[li] += wg_sum[li + stride];
+        }
+        workgroupBarrier();
+    }
+
+    if (li == 0u) {
+        var out: Partial;
+        out.count = count;
+        out.sum = sum;
+        out.m2 = wg_sum[0];
+        out.min_value = wg_min[0];
+        out.max_value = wg_max[0];
+        partials[wid.y * groups.x + wid.x] = out;
+    }
+}
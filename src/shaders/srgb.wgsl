@@ -0,0 +1,6 @@
+// sRGB 编码值还原为线性值，输出到 sRGB 目标的着色器共用，拼接在各自着色器之前
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let lo = c / 12.92;
+    let hi = pow((c + vec3<f32>(0.055)) / 1.055, vec3<f32>(2.4));
+    return select(hi, lo, c <= vec3<f32>(0.04045));
+}
@@ -0,0 +1,45 @@
+// 文字：CPU 排版好的四边形，顶点为屏幕像素
+// 字形从 R8 图集取覆盖率作为 alpha，背景框采样图集左上角的全白区域
+struct TextUniforms {
+    screen_to_clip: mat4x4<f32>,
+    srgb_target: u32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
+}
+
+@group(0) @binding(0) var<uniform> u: TextUniforms;
+@group(0) @binding(1) var atlas: texture_2d<f32>;
+@group(0) @binding(2) var atlas_sampler: sampler;
+
+struct VsIn {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VsOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VsIn) -> VsOut {
+    var out: VsOut;
+    out.position = u.screen_to_clip * vec4<f32>(in.position, 0.0, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas, atlas_sampler, in.uv).r;
+    var rgb = in.color.rgb;
+    // 颜色由宿主按 sRGB 给出
+    if (u.srgb_target != 0u) {
+        rgb = srgb_to_linear(rgb);
+    }
+    return vec4<f32>(rgb, in.color.a * coverage);
+}